@@ -139,11 +139,20 @@ async fn render_all_files(path: &'static str, black: i32, white: i32) -> Vec<Pag
 
 	while let Some(info) = from_render_rx.next().await {
 		match info.expect("Renderer ran into an error while rendering") {
-			RenderInfo::Reloaded | RenderInfo::SearchResults { .. } => (),
+			RenderInfo::Reloaded
+			| RenderInfo::SearchResults { .. }
+			| RenderInfo::Outline(_)
+			| RenderInfo::DocInfo(_)
+			| RenderInfo::PageLabels(_)
+			| RenderInfo::Thumbnail { .. }
+			| RenderInfo::PageText { .. }
+			| RenderInfo::NeedsPassword { .. }
+			| RenderInfo::SyncTexTarget { .. }
+			| RenderInfo::SyncTexSource { .. } => (),
 			RenderInfo::NumPages(num) => fill_default(&mut pages, num),
 			RenderInfo::Page(page) => {
 				let num = page.page_num;
-				pages[num] = Some(page);
+				pages[num] = Some(*page);
 			}
 		}
 
@@ -10,7 +10,8 @@ use ratatui_image::{
 };
 use tdf::{
 	converter::{ConvertedPage, ConverterMsg, run_conversion_loop},
-	renderer::{RenderError, RenderInfo, RenderNotif, fill_default, start_rendering}
+	renderer::{RenderError, RenderInfo, RenderNotif, fill_default, start_rendering},
+	search::SearchQuery
 };
 
 pub fn handle_renderer_msg(
@@ -23,9 +24,20 @@ pub fn handle_renderer_msg(
 			fill_default(pages, num);
 			to_converter_tx.send(ConverterMsg::NumPages(num)).unwrap();
 		}
-		Ok(RenderInfo::Page(info)) => to_converter_tx.send(ConverterMsg::AddImg(info)).unwrap(),
+		Ok(RenderInfo::Page(info)) => to_converter_tx.send(ConverterMsg::AddImg(*info)).unwrap(),
 		// We can ignore the these variants 'cause they're only used to send info to the TUI
-		Ok(RenderInfo::Reloaded | RenderInfo::SearchResults { .. }) => (),
+		Ok(
+			RenderInfo::Reloaded
+			| RenderInfo::SearchResults { .. }
+			| RenderInfo::Outline(_)
+			| RenderInfo::DocInfo(_)
+			| RenderInfo::PageLabels(_)
+			| RenderInfo::Thumbnail { .. }
+			| RenderInfo::PageText { .. }
+			| RenderInfo::NeedsPassword { .. }
+			| RenderInfo::SyncTexTarget { .. }
+			| RenderInfo::SyncTexSource { .. }
+		) => (),
 		Err(e) => panic!("Got error from renderer: {e:?}")
 	}
 }
@@ -103,7 +115,11 @@ pub fn start_rendering_loop(
 			cell_width_px,
 			tdf::PrerenderLimit::All,
 			black,
-			white
+			white,
+			tdf::renderer::Trim::Off,
+			true,
+			None,
+			false
 		)
 	});
 
@@ -132,7 +148,8 @@ pub fn start_converting_loop(
 		picker,
 		prerender,
 		// just assume shms work for now, who cares
-		true
+		true,
+		0
 	));
 
 	let from_converter_rx = from_converter_rx.into_stream();
@@ -176,7 +193,7 @@ pub async fn render_doc(
 
 	if let Some(term) = search_term {
 		to_render_tx
-			.send(RenderNotif::Search(term.to_owned()))
+			.send(RenderNotif::Search(SearchQuery::parse(term).unwrap()))
 			.unwrap();
 	}
 
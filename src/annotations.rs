@@ -0,0 +1,257 @@
+use ratatui::{
+	Frame,
+	layout::{Constraint, Layout, Rect},
+	style::{Color, Modifier, Style},
+	symbols::border,
+	text::{Line, Span},
+	widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph}
+};
+
+use crate::{
+	keymap::{Action, Keymap},
+	selection::PdfRect
+};
+
+/// A single annotation on a page (like a sticky note or a highlight), as read from mupdf
+#[derive(Debug, Clone)]
+pub struct Annotation {
+	// What sort of annotation it is, in words, e.g. "Note" or "Highlight"
+	pub kind: &'static str,
+	pub author: Option<String>,
+	// The text of the note attached to it, if there is one
+	pub contents: Option<String>,
+	pub rect: PdfRect
+}
+
+impl Annotation {
+	// The contents all on one line, since that's the only way they'll fit in a list or the bottom
+	// bar
+	fn flat_contents(&self) -> Option<String> {
+		let contents = self.contents.as_deref()?.split_whitespace();
+		Some(contents.collect::<Vec<_>>().join(" ")).filter(|c| !c.is_empty())
+	}
+
+	fn describe(&self) -> String {
+		let mut desc = self.kind.to_owned();
+		if let Some(ref author) = self.author {
+			desc.push_str(" from ");
+			desc.push_str(author);
+		}
+		if let Some(contents) = self.flat_contents() {
+			desc.push_str(": ");
+			desc.push_str(&contents);
+		}
+		desc
+	}
+}
+
+/// What to show in the bottom bar for the notes on the current page, which is the first one that
+/// has any text and how many others there are
+#[must_use]
+pub fn summary<'a>(annotations: impl IntoIterator<Item = &'a Annotation>) -> Option<String> {
+	let mut with_text = annotations
+		.into_iter()
+		.filter(|a| a.flat_contents().is_some());
+	let first = with_text.next()?.describe();
+	Some(match with_text.count() {
+		0 => first,
+		more => format!("{first} (+{more} more)")
+	})
+}
+
+/// The popup that lists every annotation in the document, so one can be picked to jump to
+#[derive(Default)]
+pub struct AnnotationList {
+	// Index into the annotations of the whole document, in page order
+	selected: usize,
+	// Only used to let ratatui keep track of the scroll offset of the list between renders
+	list_state: ListState
+}
+
+impl AnnotationList {
+	pub fn select_next(&mut self, len: usize) {
+		self.selected = (self.selected + 1).min(len.saturating_sub(1));
+	}
+
+	pub fn select_prev(&mut self) {
+		self.selected = self.selected.saturating_sub(1);
+	}
+
+	/// Selects the first annotation on `page` or after it, so that opening the list shows you
+	/// what's around where you are
+	pub fn select_page(&mut self, page: usize, pages: impl Iterator<Item = usize>) {
+		let mut last = None;
+		for (idx, p) in pages.enumerate() {
+			last = Some(idx);
+			if p >= page {
+				self.selected = idx;
+				return;
+			}
+		}
+		self.selected = last.unwrap_or_default();
+	}
+
+	#[must_use]
+	pub fn selected(&self) -> usize {
+		self.selected
+	}
+
+	pub fn render(
+		&mut self,
+		frame: &mut Frame<'_>,
+		annotations: &[(usize, Annotation)],
+		keymap: &Keymap
+	) {
+		let frame_area = frame.area();
+		frame.render_widget(Clear, frame_area);
+
+		let area = centered(frame_area, annotations.len());
+		let block = Block::new()
+			.title("Annotations")
+			.title_bottom(Span::styled(
+				footer(keymap),
+				Style::new().fg(Color::DarkGray)
+			))
+			.padding(Padding::horizontal(1))
+			.borders(Borders::ALL)
+			.border_set(border::ROUNDED)
+			.border_style(Color::Blue);
+		let inner = block.inner(area);
+		frame.render_widget(block, area);
+
+		if annotations.is_empty() {
+			frame.render_widget(
+				Paragraph::new("This document doesn't have any annotations").style(Color::DarkGray),
+				inner
+			);
+			return;
+		}
+
+		let width = usize::from(inner.width);
+		let items = annotations
+			.iter()
+			.map(|(page, annotation)| {
+				let page_str = format!("{:>4}  ", page + 1);
+				let desc = annotation
+					.describe()
+					.chars()
+					.take(width.saturating_sub(page_str.len()))
+					.collect::<String>();
+				ListItem::new(Line::from(vec![
+					Span::styled(page_str, Style::new().fg(Color::Cyan)),
+					Span::raw(desc),
+				]))
+			})
+			.collect::<Vec<_>>();
+
+		self.selected = self.selected.min(items.len() - 1);
+		self.list_state.select(Some(self.selected));
+
+		let list = List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, inner, &mut self.list_state);
+	}
+}
+
+// Which keys do what in the list. Moving goes by whatever scrolls the pages, so it follows the
+// keymap, but enter and esc are always the same.
+fn footer(keymap: &Keymap) -> String {
+	let mut hints = Vec::with_capacity(3);
+	if let (Some(next), Some(prev)) = (
+		keymap.key_for(Action::NextScreen),
+		keymap.key_for(Action::PrevScreen)
+	) {
+		hints.push(format!("{next}/{prev}: move"));
+	}
+	hints.push("enter: jump".to_owned());
+	hints.push("esc: close".to_owned());
+	format!(" {} ", hints.join(", "))
+}
+
+// Most of the screen, but short enough to not leave a bunch of empty space if there are only a
+// few annotations
+fn centered(area: Rect, len: usize) -> Rect {
+	let height = u16::try_from(len.max(1))
+		.unwrap_or(u16::MAX)
+		.saturating_add(2);
+	let [_, area, _] = Layout::horizontal([
+		Constraint::Percentage(10),
+		Constraint::Percentage(80),
+		Constraint::Percentage(10)
+	])
+	.areas(area);
+	let [_, area, _] = Layout::vertical([
+		Constraint::Fill(1),
+		Constraint::Max(height),
+		Constraint::Fill(1)
+	])
+	.areas(area);
+	area
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::*;
+
+	fn note(author: Option<&str>, contents: Option<&str>) -> Annotation {
+		Annotation {
+			kind: "Note",
+			author: author.map(str::to_owned),
+			contents: contents.map(str::to_owned),
+			rect: PdfRect {
+				x0: 0.,
+				y0: 0.,
+				x1: 10.,
+				y1: 10.
+			}
+		}
+	}
+
+	#[test]
+	fn summary_shows_first_note() {
+		assert_eq!(summary(&[] as &[Annotation]), None);
+		assert_eq!(summary(&[note(Some("June"), Some("  "))]), None);
+		assert_eq!(
+			summary(&[
+				note(None, None),
+				note(Some("June"), Some("Typo\nhere")),
+				note(None, Some("Cite this"))
+			]),
+			Some("Note from June: Typo here (+1 more)".into())
+		);
+	}
+
+	#[test]
+	fn footer_follows_the_keymap() {
+		assert_eq!(
+			footer(&Keymap::default()),
+			" j/k: move, enter: jump, esc: close "
+		);
+
+		let keymap = Keymap::new(&HashMap::from([
+			(Action::NextScreen, vec!["ctrl+n".parse().unwrap()]),
+			(Action::PrevScreen, Vec::new())
+		]));
+		assert_eq!(footer(&keymap), " enter: jump, esc: close ");
+	}
+
+	#[test]
+	fn select_page_picks_first_at_or_after() {
+		let mut list = AnnotationList::default();
+		let pages = [0, 2, 2, 5];
+
+		list.select_page(2, pages.into_iter());
+		assert_eq!(list.selected(), 1);
+		list.select_page(3, pages.into_iter());
+		assert_eq!(list.selected(), 3);
+		// Past the last one, so it's the closest
+		list.select_page(9, pages.into_iter());
+		assert_eq!(list.selected(), 3);
+
+		list.select_next(pages.len());
+		assert_eq!(list.selected(), 3);
+		list.select_prev();
+		assert_eq!(list.selected(), 2);
+	}
+}
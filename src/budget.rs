@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageState {
+	Kept,
+	Evicted,
+	// It was evicted, but it's been asked for again and we're waiting on it to be rendered
+	Restoring
+}
+
+/// Which pages to let go of (and which to ask for again) so that the rendered pages don't take up
+/// more than a certain amount of memory. The pages closest to the current one are the ones that
+/// are kept.
+#[derive(Debug)]
+pub struct MemoryBudget {
+	max_bytes: usize,
+	// How much each page that's been rendered takes up, even if it's been evicted since, so that
+	// we know whether it'll fit again
+	pages: BTreeMap<usize, (usize, PageState)>,
+	// The page we last balanced around (and the pages that were on screen then), so that it isn't
+	// redone if nothing's changed
+	balanced_at: Option<(usize, Vec<usize>)>
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Rebalanced {
+	// The pages that need to be dropped, everywhere they're held onto
+	pub evict: Vec<usize>,
+	// The pages that were evicted but fit again, and so need to be rendered again
+	pub restore: Vec<usize>
+}
+
+impl MemoryBudget {
+	#[must_use]
+	pub fn new(max_bytes: usize) -> Self {
+		Self {
+			max_bytes,
+			pages: BTreeMap::new(),
+			balanced_at: None
+		}
+	}
+
+	/// Records that `page` has been rendered and now takes up `bytes`
+	pub fn add(&mut self, page: usize, bytes: usize) {
+		self.pages.insert(page, (bytes, PageState::Kept));
+		self.balanced_at = None;
+	}
+
+	/// Forgets about every page, since they're all going to be rendered again (e.g. because the
+	/// document was reloaded)
+	pub fn clear(&mut self) {
+		self.pages.clear();
+		self.balanced_at = None;
+	}
+
+	/// Whether `page` has been let go of. Anything for it that was already on its way (like a
+	/// page that was being converted when it was evicted) should be dropped too.
+	#[must_use]
+	pub fn is_evicted(&self, page: usize) -> bool {
+		self.pages
+			.get(&page)
+			.is_some_and(|(_, state)| *state == PageState::Evicted)
+	}
+
+	/// Keeps as many of the pages closest to `current` as fit in the budget, returning which ones
+	/// need to be let go of and which ones that were let go of before now fit again. However small
+	/// the budget is, `current` and the pages in `on_screen` are always kept, so that what's on
+	/// screen doesn't keep getting thrown out and rendered again.
+	pub fn rebalance(&mut self, current: usize, on_screen: &[usize]) -> Rebalanced {
+		let mut changes = Rebalanced::default();
+		if self
+			.balanced_at
+			.as_ref()
+			.is_some_and(|(page, shown)| *page == current && shown == on_screen)
+		{
+			return changes;
+		}
+		self.balanced_at = Some((current, on_screen.to_vec()));
+
+		// When two pages are just as far away, the one after the current page wins, since people
+		// usually read forwards
+		let mut by_distance = self.pages.keys().copied().collect::<Vec<_>>();
+		by_distance.sort_unstable_by_key(|page| (page.abs_diff(current), *page < current));
+
+		let mut used = 0_usize;
+		for page in by_distance {
+			let Some((bytes, state)) = self.pages.get_mut(&page) else {
+				continue;
+			};
+			used = used.saturating_add(*bytes);
+			let wanted = used <= self.max_bytes || page == current || on_screen.contains(&page);
+
+			match (wanted, *state) {
+				(true, PageState::Evicted) => {
+					*state = PageState::Restoring;
+					changes.restore.push(page);
+				}
+				(false, PageState::Kept) => {
+					*state = PageState::Evicted;
+					changes.evict.push(page);
+				}
+				// It's still going to be sent over once it's rendered, but it'll just be evicted
+				// again then
+				(false, PageState::Restoring) => *state = PageState::Evicted,
+				(true, PageState::Kept | PageState::Restoring) | (false, PageState::Evicted) => ()
+			}
+		}
+
+		changes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_the_closest_pages() {
+		// Room for 3 and a half pages
+		let mut budget = MemoryBudget::new(350);
+		for page in 0..10 {
+			budget.add(page, 100);
+		}
+
+		let changes = budget.rebalance(0, &[0]);
+		assert_eq!(changes.evict, vec![3, 4, 5, 6, 7, 8, 9]);
+		assert!(changes.restore.is_empty());
+		assert!(budget.is_evicted(5));
+		assert_eq!(budget.rebalance(0, &[0]), Rebalanced::default());
+
+		// Going to the middle brings back the pages around it, and the ones after it win ties
+		let changes = budget.rebalance(4, &[4]);
+		assert_eq!(changes.evict, vec![2, 1, 0]);
+		assert_eq!(changes.restore, vec![4, 5, 3]);
+		assert!(!budget.is_evicted(3));
+
+		// Once they've been rendered again, they're just kept like any other page
+		for page in [4, 5, 3] {
+			budget.add(page, 100);
+		}
+		assert_eq!(budget.rebalance(4, &[4]), Rebalanced::default());
+	}
+
+	#[test]
+	fn always_keeps_the_pages_on_screen() {
+		let mut budget = MemoryBudget::new(10);
+		for page in 0..6 {
+			budget.add(page, 100);
+		}
+
+		let changes = budget.rebalance(2, &[2, 3, 4]);
+		assert_eq!(changes.evict, vec![1, 0, 5]);
+
+		// More pages fitting on the screen brings them back, even without moving to another page
+		let changes = budget.rebalance(2, &[2, 3, 4, 5]);
+		assert_eq!(changes.restore, vec![5]);
+
+		// A page that was asked for again, but isn't needed by the time it's rendered
+		budget.rebalance(5, &[5]);
+		assert!(!budget.is_evicted(5));
+		budget.rebalance(0, &[0]);
+		assert!(budget.is_evicted(5));
+		budget.add(5, 100);
+		assert_eq!(budget.rebalance(0, &[0]).evict, vec![5]);
+	}
+}
@@ -0,0 +1,406 @@
+use std::{
+	fs::{self, File},
+	io::{self, Read as _},
+	path::{Path, PathBuf},
+	time::SystemTime
+};
+
+use crate::{
+	config::xdg_dir,
+	state::{FNV_OFFSET_BASIS, fnv1a}
+};
+
+// Pages of big scans can be a few megabytes each once they're rendered, so this fits a few hundred
+// of them
+const MAX_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Everything that changes how a page is rendered, so that a cached page is only used if it would
+/// look exactly the same as rendering it again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheKey {
+	pub page: usize,
+	// The size of the whole page once it's rendered, in pixels
+	pub width: u32,
+	pub height: u32,
+	pub black: i32,
+	pub white: i32,
+	pub invert: bool,
+	// Clockwise, in degrees
+	pub rotation: u16,
+	pub annotations: bool
+}
+
+/// Rendered pages that are kept on disk (in `$XDG_CACHE_HOME/tdf/pages`), so that opening a big
+/// document again doesn't mean rendering every page all over again. Every document shares the
+/// same directory, and the pages that were used the longest ago are thrown out once it gets too
+/// big.
+pub struct RenderCache {
+	dir: PathBuf,
+	// Which document (and which version of it) the pages are from
+	doc: DocHash,
+	max_bytes: u64,
+	// How much is in the directory, which is only counted once something is added to it
+	used: Option<u64>
+}
+
+// Hashing a big document takes a while, so it's done on another thread, and nothing is read from or
+// saved to the cache until it's done
+enum DocHash {
+	Known(u64),
+	Hashing(flume::Receiver<u64>),
+	// Nothing gets cached, since we couldn't read the document
+	Failed
+}
+
+fn cache_dir() -> Option<PathBuf> {
+	xdg_dir("XDG_CACHE_HOME", &[".cache"]).map(|dir| dir.join("pages"))
+}
+
+// Unlike the fingerprint that reading positions are saved under, this goes through the whole
+// file, since a change in the middle of it would otherwise get the old pages shown from here
+fn content_hash(path: &Path) -> io::Result<u64> {
+	let mut file = File::open(path)?;
+	let mut buf = vec![0; 64 * 1024];
+	let mut hash = FNV_OFFSET_BASIS;
+	loop {
+		match file.read(&mut buf)? {
+			0 => return Ok(hash),
+			read => hash = fnv1a(hash, &buf[..read])
+		}
+	}
+}
+
+impl RenderCache {
+	#[must_use]
+	pub fn new(dir: PathBuf, doc: u64, max_bytes: u64) -> Self {
+		Self {
+			dir,
+			doc: DocHash::Known(doc),
+			max_bytes,
+			used: None
+		}
+	}
+
+	/// The cache for the document at `path`, as it currently is. This needs to be called again
+	/// whenever the document changes, since its pages would look different.
+	#[must_use]
+	pub fn open(path: &Path) -> Option<Self> {
+		let dir = cache_dir()?;
+		let stamp = match stamp_file(&dir, path) {
+			Ok(stamp) => stamp,
+			Err(e) => {
+				log::warn!("Not caching pages of {}: {e}", path.display());
+				return None;
+			}
+		};
+
+		// If the document hasn't been touched since it was last hashed, we don't need to go
+		// through it all again
+		if let Some(doc) = fs::read_to_string(&stamp)
+			.ok()
+			.and_then(|hash| u64::from_str_radix(hash.trim(), 16).ok())
+		{
+			return Some(Self::new(dir, doc, MAX_CACHE_BYTES));
+		}
+
+		let (tx, rx) = flume::bounded(1);
+		let path = path.to_owned();
+		std::thread::spawn(move || match content_hash(&path) {
+			Ok(doc) => {
+				if let Err(e) = fs::write(&stamp, format!("{doc:016x}")) {
+					log::debug!("Couldn't save the hash of {}: {e}", path.display());
+				}
+				_ = tx.send(doc);
+			}
+			// Dropping `tx` lets the cache know that it won't be getting a hash
+			Err(e) => log::warn!("Not caching pages of {}: {e}", path.display())
+		});
+
+		Some(Self {
+			dir,
+			doc: DocHash::Hashing(rx),
+			max_bytes: MAX_CACHE_BYTES,
+			used: None
+		})
+	}
+
+	// The hash of the document, once it's been worked out
+	fn doc(&mut self) -> Option<u64> {
+		if let DocHash::Hashing(ref rx) = self.doc {
+			self.doc = match rx.try_recv() {
+				Ok(doc) => DocHash::Known(doc),
+				Err(flume::TryRecvError::Empty) => return None,
+				Err(flume::TryRecvError::Disconnected) => DocHash::Failed
+			};
+		}
+		match self.doc {
+			DocHash::Known(doc) => Some(doc),
+			DocHash::Hashing(_) | DocHash::Failed => None
+		}
+	}
+
+	fn file(&self, doc: u64, key: &CacheKey) -> PathBuf {
+		let CacheKey {
+			page,
+			width,
+			height,
+			black,
+			white,
+			invert,
+			rotation,
+			annotations
+		} = *key;
+		self.dir.join(format!(
+			"{doc:016x}-{page}-{width}x{height}-{black:08x}-{white:08x}-{}-{rotation}-{}.pnm",
+			u8::from(invert),
+			u8::from(annotations)
+		))
+	}
+
+	/// The pixels (as a PNM) that were saved for `key`, if there are any
+	#[must_use]
+	pub fn get(&mut self, key: &CacheKey) -> Option<Vec<u8>> {
+		let doc = self.doc()?;
+		let path = self.file(doc, key);
+		let pixels = fs::read(&path).ok()?;
+		// Eviction goes by when each page was last used, not when it was first saved
+		if let Err(e) = File::options()
+			.write(true)
+			.open(&path)
+			.and_then(|f| f.set_modified(SystemTime::now()))
+		{
+			log::debug!("Couldn't mark {} as used: {e}", path.display());
+		}
+		Some(pixels)
+	}
+
+	/// Saves the pixels of the page rendered for `key`, throwing out old pages if the cache has
+	/// gotten too big
+	pub fn put(&mut self, key: &CacheKey, pixels: &[u8]) {
+		if let Err(e) = self.try_put(key, pixels) {
+			log::warn!("Couldn't cache page {}: {e}", key.page);
+		}
+	}
+
+	fn try_put(&mut self, key: &CacheKey, pixels: &[u8]) -> io::Result<()> {
+		let Some(doc) = self.doc() else {
+			return Ok(());
+		};
+		fs::create_dir_all(&self.dir)?;
+
+		let used = match self.used {
+			Some(used) => used,
+			None => entries(&self.dir)?.iter().map(|entry| entry.len).sum()
+		};
+
+		// Written to a temporary file first so that another instance of tdf never reads a page
+		// that's only half-written
+		let path = self.file(doc, key);
+		let tmp_path = path.with_extension(format!("pnm.{}", std::process::id()));
+		fs::write(&tmp_path, pixels)?;
+		fs::rename(&tmp_path, &path)?;
+
+		let used = used + pixels.len() as u64;
+		self.used = Some(if used > self.max_bytes {
+			self.evict()?
+		} else {
+			used
+		});
+		Ok(())
+	}
+
+	// Deletes the least recently used pages until there's a good bit of room left, so that we
+	// don't have to go through the whole directory again for every page that's added. Returns
+	// how much is left.
+	fn evict(&self) -> io::Result<u64> {
+		let mut entries = entries(&self.dir)?;
+		entries.sort_unstable_by_key(|entry| entry.used);
+
+		let target = self.max_bytes / 4 * 3;
+		let mut used = entries.iter().map(|entry| entry.len).sum::<u64>();
+		for entry in entries {
+			if used <= target {
+				break;
+			}
+			match fs::remove_file(&entry.path) {
+				Ok(()) => used -= entry.len,
+				// Another instance might've just removed it
+				Err(e) if e.kind() == io::ErrorKind::NotFound => used -= entry.len,
+				Err(e) => return Err(e)
+			}
+		}
+		Ok(used)
+	}
+}
+
+// Where the hash of the document at `path` is kept, which is only found again if the document still
+// has the same size and modification time as when it was hashed
+fn stamp_file(dir: &Path, path: &Path) -> io::Result<PathBuf> {
+	let meta = fs::metadata(path)?;
+	let modified = meta
+		.modified()?
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_nanos();
+	let path = path.canonicalize()?;
+	let mut stamp = fnv1a(FNV_OFFSET_BASIS, path.as_os_str().as_encoded_bytes());
+	stamp = fnv1a(stamp, &meta.len().to_le_bytes());
+	stamp = fnv1a(stamp, &modified.to_le_bytes());
+	fs::create_dir_all(dir)?;
+	Ok(dir.join(format!("{stamp:016x}.hash")))
+}
+
+struct Entry {
+	path: PathBuf,
+	len: u64,
+	used: SystemTime
+}
+
+fn entries(dir: &Path) -> io::Result<Vec<Entry>> {
+	let mut entries = Vec::new();
+	for entry in fs::read_dir(dir)? {
+		let entry = entry?;
+		// It could've been evicted by another instance since we listed the directory
+		let Ok(meta) = entry.metadata() else {
+			continue;
+		};
+		if meta.is_file() {
+			entries.push(Entry {
+				path: entry.path(),
+				len: meta.len(),
+				used: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+			});
+		}
+	}
+	Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::state::fingerprint;
+
+	fn key(page: usize) -> CacheKey {
+		CacheKey {
+			page,
+			width: 600,
+			height: 800,
+			black: 0,
+			white: 0x00ff_ffff,
+			invert: false,
+			rotation: 0,
+			annotations: true
+		}
+	}
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("tdf-{name}-{}", std::process::id()));
+		_ = fs::remove_dir_all(&dir);
+		dir
+	}
+
+	#[test]
+	fn only_matches_the_same_render() {
+		let dir = temp_dir("cache-keys");
+		let mut cache = RenderCache::new(dir.clone(), 1, 1024);
+		cache.put(&key(0), b"page 0");
+
+		assert_eq!(cache.get(&key(0)).as_deref(), Some(&b"page 0"[..]));
+		assert_eq!(cache.get(&key(1)), None);
+		assert_eq!(
+			cache.get(&CacheKey {
+				invert: true,
+				..key(0)
+			}),
+			None
+		);
+		assert_eq!(
+			cache.get(&CacheKey {
+				rotation: 90,
+				..key(0)
+			}),
+			None
+		);
+		assert_eq!(
+			cache.get(&CacheKey {
+				width: 601,
+				..key(0)
+			}),
+			None
+		);
+		// A different version of the document
+		assert_eq!(RenderCache::new(dir.clone(), 2, 1024).get(&key(0)), None);
+
+		_ = fs::remove_dir_all(dir);
+	}
+
+	#[test]
+	fn waits_for_the_document_to_be_hashed() {
+		let dir = temp_dir("cache-hashing");
+		let (tx, rx) = flume::bounded(1);
+		let mut cache = RenderCache {
+			doc: DocHash::Hashing(rx),
+			..RenderCache::new(dir.clone(), 0, 1024)
+		};
+		cache.put(&key(0), b"page 0");
+		assert_eq!(cache.get(&key(0)), None);
+		assert!(!dir.exists());
+
+		tx.send(1).unwrap();
+		cache.put(&key(0), b"page 0");
+		assert_eq!(
+			RenderCache::new(dir.clone(), 1, 1024)
+				.get(&key(0))
+				.as_deref(),
+			Some(&b"page 0"[..])
+		);
+
+		_ = fs::remove_dir_all(dir);
+	}
+
+	#[test]
+	fn evicts_the_least_recently_used() {
+		let dir = temp_dir("cache-eviction");
+		let mut cache = RenderCache::new(dir.clone(), 1, 250);
+		cache.put(&key(0), &[0; 100]);
+		cache.put(&key(1), &[1; 100]);
+
+		// Page 1 was saved after page 0, but it hasn't been used in a while
+		let long_ago = SystemTime::now() - Duration::from_secs(60);
+		File::options()
+			.write(true)
+			.open(cache.file(1, &key(1)))
+			.and_then(|f| f.set_modified(long_ago))
+			.unwrap();
+
+		cache.put(&key(2), &[2; 60]);
+		assert!(cache.get(&key(0)).is_some());
+		assert_eq!(cache.get(&key(1)), None);
+		assert!(cache.get(&key(2)).is_some());
+		assert_eq!(cache.used, Some(160));
+
+		_ = fs::remove_dir_all(dir);
+	}
+
+	#[test]
+	fn notices_changes_in_the_middle_of_big_documents() {
+		let dir = temp_dir("cache-hash");
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("scan.pdf");
+
+		let mut contents = vec![0_u8; 1024 * 1024];
+		fs::write(&path, &contents).unwrap();
+		let (before, sampled_before) = (content_hash(&path).unwrap(), fingerprint(&path).unwrap());
+		contents[512 * 1024] = 1;
+		fs::write(&path, &contents).unwrap();
+		let (after, sampled_after) = (content_hash(&path).unwrap(), fingerprint(&path).unwrap());
+
+		// The fingerprint only looks at the start and end, so it can't tell the difference
+		assert_eq!(sampled_before, sampled_after);
+		assert_ne!(before, after);
+
+		_ = fs::remove_dir_all(dir);
+	}
+}
@@ -0,0 +1,130 @@
+use std::{collections::HashMap, fmt, io, num::NonZeroUsize, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+	keymap::{Action, KeyChord},
+	renderer::Trim
+};
+
+/// Persistent defaults for the command-line flags, read from `$XDG_CONFIG_HOME/tdf/config.toml`.
+/// Every key here is named the same as its flag, and the flag always wins if both are given.
+#[expect(clippy::struct_excessive_bools)]
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+	pub r_to_l: bool,
+	pub max_wide: Option<NonZeroUsize>,
+	pub spread: bool,
+	pub cover_page: Option<NonZeroUsize>,
+	pub fullscreen: bool,
+	pub reload_delay: Option<u64>,
+	pub prerender: Option<usize>,
+	pub memory_budget: Option<usize>,
+	pub white_color: Option<String>,
+	pub black_color: Option<String>,
+	pub terminal_colors: bool,
+	pub link_opener: Option<String>,
+	pub synctex_editor: Option<String>,
+	pub no_restore_position: bool,
+	// Left unset (rather than defaulting to false) so that the text view can still be picked
+	// automatically when the terminal can't show images
+	pub text_view: Option<bool>,
+	pub continuous: bool,
+	pub trim: Option<Trim>,
+	pub hide_annotations: bool,
+	pub no_cache: bool,
+	// Which keys each action should be bound to, e.g. `next-page = ["l", "ctrl+n"]`. Actions that
+	// aren't mentioned here keep their default keys.
+	pub keys: HashMap<Action, Vec<KeyChord>>
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+	Read(PathBuf, io::Error),
+	Parse(PathBuf, toml::de::Error)
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Read(path, e) => write!(f, "Couldn't read config file {}: {e}", path.display()),
+			// toml's errors already point out the line and what was wrong with it (including
+			// listing the valid keys if an unknown one was used)
+			Self::Parse(path, e) => write!(f, "Invalid config file {}:\n{e}", path.display())
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+/// tdf's directory within the XDG base directory in `var`, or within `fallback` (which is
+/// relative to the home directory) if that isn't set
+#[must_use]
+pub fn xdg_dir(var: &str, fallback: &[&str]) -> Option<PathBuf> {
+	std::env::var_os(var)
+		.map(PathBuf::from)
+		// The XDG spec says relative paths in these variables are invalid and should be ignored
+		.filter(|dir| dir.is_absolute())
+		.or_else(|| {
+			dirs::home_dir().map(|home| fallback.iter().fold(home, |dir, part| dir.join(part)))
+		})
+		.map(|dir| dir.join("tdf"))
+}
+
+#[must_use]
+pub fn config_dir() -> Option<PathBuf> {
+	xdg_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+impl Config {
+	/// Loads the config file, if it exists. Not having a config file is fine, but having one that
+	/// we can't understand is an error, since otherwise people would be left wondering why their
+	/// settings aren't doing anything.
+	pub fn load() -> Result<Self, ConfigError> {
+		let Some(path) = config_dir().map(|dir| dir.join("config.toml")) else {
+			return Ok(Self::default());
+		};
+
+		match std::fs::read_to_string(&path) {
+			Ok(contents) => toml::from_str(&contents).map_err(|e| ConfigError::Parse(path, e)),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+			Err(e) => Err(ConfigError::Read(path, e))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_keys() {
+		let config: Config = toml::from_str("[keys]\nquit = [\"ctrl+c\", \"Q\"]\n").unwrap();
+		assert_eq!(config.keys[&Action::Quit].len(), 2);
+
+		let err = toml::from_str::<Config>("[keys]\nexplode = [\"x\"]\n").unwrap_err();
+		assert!(err.to_string().contains("unknown variant `explode`"));
+
+		let err = toml::from_str::<Config>("[keys]\nquit = [\"ctrl+\"]\n").unwrap_err();
+		assert!(err.to_string().contains("Unknown key"));
+	}
+
+	#[test]
+	fn parses_kebab_case_and_rejects_unknown() {
+		let config: Config = toml::from_str(
+			"r-to-l = true\nmax-wide = 2\nblack-color = \"#101010\"\nreload-delay = 100\n"
+		)
+		.unwrap();
+		assert_eq!(config, Config {
+			r_to_l: true,
+			max_wide: NonZeroUsize::new(2),
+			black_color: Some("#101010".into()),
+			reload_delay: Some(100),
+			..Config::default()
+		});
+
+		let err = toml::from_str::<Config>("max_wide = 2\n").unwrap_err();
+		assert!(err.to_string().contains("unknown field `max_wide`"));
+	}
+}
@@ -1,12 +1,15 @@
 use std::{
+	collections::VecDeque,
 	io::Cursor,
 	num::{NonZeroU32, NonZeroUsize},
+	ops::Range,
+	slice,
 	time::{SystemTime, UNIX_EPOCH}
 };
 
 use flume::{Receiver, SendError, Sender, TryRecvError};
 use futures_util::stream::StreamExt as _;
-use image::{DynamicImage, codecs::pnm::PnmDecoder};
+use image::{DynamicImage, Rgb, RgbImage, codecs::pnm::PnmDecoder};
 use kittage::NumberOrId;
 use ratatui::prelude::Size;
 use ratatui_image::{
@@ -17,10 +20,14 @@ use ratatui_image::{
 use rayon::iter::ParallelIterator as _;
 
 use crate::{
-	renderer::{PageInfo, RenderError, fill_default},
+	renderer::{HighlightRect, ImageData, PageInfo, RenderError, fill_default},
 	skip::InterleavedAroundWithMax
 };
 
+/// Thumbnails for the overview get the upper half of each document's range of kitty ids, so that
+/// they don't replace the full-size pages
+pub const KITTY_THUMBNAIL_IDS: u32 = 1 << 23;
+
 #[derive(Debug)]
 pub enum MaybeTransferred {
 	NotYet(kittage::image::Image<'static>),
@@ -30,6 +37,10 @@ pub enum MaybeTransferred {
 // #[derive(Debug)]
 pub enum ConvertedImage {
 	Generic(Protocol),
+	// Only made in continuous mode, where pages that are partly scrolled off the screen have to be
+	// cropped to whatever part of them is still on it. This holds onto the page's image instead of
+	// a protocol for all of it, so that it isn't kept in memory twice.
+	Croppable(Box<CropSource>),
 	Kitty {
 		img: MaybeTransferred,
 		cell_w: u16,
@@ -45,6 +56,7 @@ impl ConvertedImage {
 				let Size { width, height } = prot.size();
 				(width, height)
 			}
+			Self::Croppable(src) => (src.cell_w, src.cell_h),
 			Self::Kitty {
 				img: _,
 				cell_w,
@@ -54,16 +66,71 @@ impl ConvertedImage {
 	}
 }
 
+/// The full image of a page, for making protocols that only show some of its rows
+pub struct CropSource {
+	img: DynamicImage,
+	picker: Picker,
+	cell_w: u16,
+	cell_h: u16,
+	// The last crop that was made, since we'd otherwise redo it every time the page is drawn
+	last: Option<(Range<u16>, Protocol)>
+}
+
+impl CropSource {
+	/// Returns a protocol that shows only the given rows (in terminal cells) of the page
+	pub fn cropped(&mut self, rows: Range<u16>) -> Option<&Protocol> {
+		if self
+			.last
+			.as_ref()
+			.is_none_or(|(last_rows, _)| *last_rows != rows)
+		{
+			let height = rows.end.saturating_sub(rows.start);
+			let font_h = u32::from(self.picker.font_size().height);
+			let cropped = self.img.crop_imm(
+				0,
+				u32::from(rows.start) * font_h,
+				self.img.width(),
+				u32::from(height) * font_h
+			);
+			let size = Size {
+				width: self.cell_w,
+				height
+			};
+			match self.picker.new_protocol(cropped, size, Resize::Crop(None)) {
+				Ok(prot) => self.last = Some((rows, prot)),
+				Err(e) => {
+					log::warn!("Couldn't crop page: {e}");
+					return None;
+				}
+			}
+		}
+
+		self.last.as_ref().map(|(_, prot)| prot)
+	}
+}
+
 pub struct ConvertedPage {
 	pub page: ConvertedImage,
 	pub num: usize,
-	pub num_results: usize
+	pub num_results: usize,
+	// Whether this is a thumbnail for the overview instead of the full-size page
+	pub thumbnail: bool
 }
 
 pub enum ConverterMsg {
 	NumPages(usize),
 	GoToPage(usize),
-	AddImg(PageInfo)
+	AddImg(PageInfo),
+	AddThumbnail {
+		page_num: usize,
+		img_data: ImageData
+	},
+	// Whether the pages are being shown in continuous mode, which needs generic images to keep
+	// their source around so they can be cropped
+	Continuous(bool),
+	// The page was thrown out to stay within the memory budget, so if we haven't converted it
+	// yet, we shouldn't bother
+	Evict(usize)
 }
 
 pub async fn run_conversion_loop(
@@ -71,12 +138,20 @@ pub async fn run_conversion_loop(
 	receiver: Receiver<ConverterMsg>,
 	picker: Picker,
 	prerender: usize,
-	shms_work: bool
+	shms_work: bool,
+	// Added to the id of every image we send to kitty, so that each open document can be given
+	// its own range of ids and they don't replace each other's images
+	kitty_id_base: u32
 ) -> Result<(), Box<SendError<Result<ConvertedPage, RenderError>>>> {
 	let mut images = vec![];
+	// These are all converted (in the order they came in) once the pages around the current one are
+	// done, since they're small enough that we don't need to worry about how many there are
+	let mut thumbnails = VecDeque::new();
 	let mut page: usize = 0;
+	let mut continuous = false;
 	let pid = std::process::id();
 
+	#[expect(clippy::too_many_arguments)]
 	fn next_page(
 		images: &mut [Option<PageInfo>],
 		picker: &Picker,
@@ -84,7 +159,9 @@ pub async fn run_conversion_loop(
 		iteration: &mut usize,
 		prerender: usize,
 		pid: u32,
-		shms_work: bool
+		shms_work: bool,
+		kitty_id_base: u32,
+		continuous: bool
 	) -> Result<Option<ConvertedPage>, RenderError> {
 		if images.is_empty() || *iteration >= prerender {
 			return Ok(None);
@@ -112,74 +189,55 @@ pub async fn run_conversion_loop(
 			return Ok(None);
 		};
 
-		let decoder = PnmDecoder::new(Cursor::new(&page_info.img_data.pixels)).map_err(|e| {
-			RenderError::Converting(format!(
-				"The image data provided from mupdf was not in pnm format ({e}); don't know how to convert"
-			))
-		})?;
-
-		// The image we get should always already be `ImageRgb8`, so this `into` shouldn't do any
-		// conversions or anything, but just in case some underlying detail of mupdf or image
-		// changes, we do the `into` instead of just `match + unreachable!()` to avoid panicking
-		let mut dyn_img = DynamicImage::from_decoder(decoder)
-			.map_err(|e| RenderError::Converting(format!("Can't load image: {e}")))?
-			.into_rgb8();
-
-		for quad in &*page_info.result_rects {
-			dyn_img
-				.par_enumerate_pixels_mut()
-				.filter(|(x, y, _)| {
-					*x > quad.ul_x && *x < quad.lr_x && *y > quad.ul_y && *y < quad.lr_y
-				})
-				.for_each(|(_, _, px)| px.0[2] = px.0[2].saturating_sub(u8::MAX / 2));
+		let mut dyn_img = decode_pnm(&page_info.img_data.pixels)?;
+
+		// If the margins are being trimmed, the whole page was still rendered, so we cut out just
+		// the part that's supposed to be shown (which is what all the rects below are relative to)
+		if let Some(crop) = &page_info.crop {
+			dyn_img = image::imageops::crop_imm(
+				&dyn_img,
+				crop.ul_x,
+				crop.ul_y,
+				crop.lr_x.saturating_sub(crop.ul_x),
+				crop.lr_y.saturating_sub(crop.ul_y)
+			)
+			.to_image();
+		}
+
+		// search results get tinted yellow...
+		highlight_results(&mut dyn_img, &page_info.result_rects);
+		// (with the one that the user is currently on tinted further, to orange)...
+		if let Some(active) = page_info
+			.active_result
+			.and_then(|idx| page_info.result_rects.get(idx))
+		{
+			highlight_rects(&mut dyn_img, slice::from_ref(active), |px| {
+				px.0[1] = px.0[1].saturating_sub(u8::MAX / 3);
+				px.0[2] = px.0[2].saturating_sub(u8::MAX / 4);
+			});
 		}
+		// and selected text gets tinted blue so that you can tell the two apart
+		highlight_rects(&mut dyn_img, &page_info.selection_rects, |px| {
+			px.0[0] = px.0[0].saturating_sub(u8::MAX / 2);
+			px.0[1] = px.0[1].saturating_sub(u8::MAX / 4);
+		});
 
 		let img_size = Size {
 			width: page_info.img_data.cell_w,
 			height: page_info.img_data.cell_h
 		};
 
-		let dyn_img = DynamicImage::ImageRgb8(dyn_img);
-
-		let txt_img = match picker.protocol_type() {
-			ProtocolType::Kitty => {
-				let rn = SystemTime::now()
-					.duration_since(UNIX_EPOCH)
-					.unwrap_or_default()
-					.as_nanos() % 1_000_000;
-
-				let mut img = if shms_work {
-					let shm_name = format!("/tdf_{pid}_{rn}_{page_num}");
-
-					#[cfg(unix)]
-					let shm_name = &*shm_name;
-
-					kittage::image::Image::shm_from(dyn_img, shm_name).map_err(|e| {
-						RenderError::Converting(format!("Couldn't create shm: {e:?}"))
-					})?
-				} else {
-					kittage::image::Image::from(dyn_img)
-				};
-
-				// if ur pdf has 4 billion pages then you deserve to suffer
-				img.num_or_id = NumberOrId::Id(NonZeroU32::MIN.saturating_add(page_num as u32));
-
-				ConvertedImage::Kitty {
-					img: MaybeTransferred::NotYet(img),
-					cell_w: page_info.img_data.cell_w,
-					cell_h: page_info.img_data.cell_h
-				}
-			}
-			_ => ConvertedImage::Generic(
-				picker
-					.new_protocol(dyn_img, img_size, Resize::Crop(None))
-					.map_err(|e| {
-						RenderError::Converting(format!(
-							"Couldn't convert DynamicImage to ratatui image: {e}"
-						))
-					})?
-			)
-		};
+		// if ur pdf has 4 billion pages then you deserve to suffer
+		let kitty_id = kitty_id_base.saturating_add(page_num as u32);
+		let txt_img = to_converted_image(
+			DynamicImage::ImageRgb8(dyn_img),
+			img_size,
+			picker,
+			kitty_id,
+			pid,
+			shms_work,
+			continuous
+		)?;
 
 		log::debug!(
 			"got converted page for num {} with results {:?}",
@@ -193,21 +251,61 @@ pub async fn run_conversion_loop(
 		Ok(Some(ConvertedPage {
 			page: txt_img,
 			num: page_info.page_num,
-			num_results: page_info.result_rects.len()
+			num_results: page_info.result_rects.len(),
+			thumbnail: false
 		}))
 	}
 
-	fn handle_notif(msg: ConverterMsg, images: &mut Vec<Option<PageInfo>>, page: &mut usize) {
+	fn convert_thumbnail(
+		page_num: usize,
+		img_data: &ImageData,
+		picker: &Picker,
+		pid: u32,
+		shms_work: bool,
+		kitty_id_base: u32
+	) -> Result<ConvertedPage, RenderError> {
+		let img = DynamicImage::ImageRgb8(decode_pnm(&img_data.pixels)?);
+		let img_size = Size {
+			width: img_data.cell_w,
+			height: img_data.cell_h
+		};
+		let kitty_id = kitty_id_base
+			.saturating_add(KITTY_THUMBNAIL_IDS)
+			.saturating_add(page_num as u32);
+
+		Ok(ConvertedPage {
+			page: to_converted_image(img, img_size, picker, kitty_id, pid, shms_work, false)?,
+			num: page_num,
+			num_results: 0,
+			thumbnail: true
+		})
+	}
+
+	fn handle_notif(
+		msg: ConverterMsg,
+		images: &mut Vec<Option<PageInfo>>,
+		thumbnails: &mut VecDeque<(usize, ImageData)>,
+		page: &mut usize,
+		continuous: &mut bool
+	) {
 		match msg {
 			ConverterMsg::AddImg(img) => {
 				let page_num = img.page_num;
 				images[page_num] = Some(img);
 			}
+			ConverterMsg::AddThumbnail { page_num, img_data } =>
+				thumbnails.push_back((page_num, img_data)),
 			ConverterMsg::NumPages(n_pages) => {
 				fill_default(images, n_pages);
+				thumbnails.clear();
 				*page = (*page).min(n_pages - 1);
 			}
-			ConverterMsg::GoToPage(new_page) => *page = new_page
+			ConverterMsg::GoToPage(new_page) => *page = new_page,
+			ConverterMsg::Continuous(new_continuous) => *continuous = new_continuous,
+			ConverterMsg::Evict(page_num) =>
+				if let Some(img) = images.get_mut(page_num) {
+					*img = None;
+				},
 		}
 	}
 
@@ -216,7 +314,13 @@ pub async fn run_conversion_loop(
 		loop {
 			match receiver.try_recv() {
 				Ok(msg) => {
-					handle_notif(msg, &mut images, &mut page);
+					handle_notif(
+						msg,
+						&mut images,
+						&mut thumbnails,
+						&mut page,
+						&mut continuous
+					);
 					continue 'outer;
 				}
 				Err(TryRecvError::Empty) => (),
@@ -224,15 +328,37 @@ pub async fn run_conversion_loop(
 				Err(TryRecvError::Disconnected) => return Ok(())
 			}
 
-			match next_page(
+			let converted = next_page(
 				&mut images,
 				&picker,
 				page,
 				&mut iteration,
 				prerender,
 				pid,
-				shms_work
-			) {
+				shms_work,
+				kitty_id_base,
+				continuous
+			)
+			.and_then(|converted| match converted {
+				Some(converted) => Ok(Some(converted)),
+				// Thumbnails are only converted once all the pages that could be shown are ready,
+				// since those are what someone's more likely to be waiting on
+				None => thumbnails
+					.pop_front()
+					.map(|(page_num, img_data)| {
+						convert_thumbnail(
+							page_num,
+							&img_data,
+							&picker,
+							pid,
+							shms_work,
+							kitty_id_base
+						)
+					})
+					.transpose()
+			});
+
+			match converted {
 				Ok(None) => break,
 				Ok(Some(img)) => sender.send(Ok(img))?,
 				Err(e) => sender.send(Err(e))?
@@ -243,8 +369,107 @@ pub async fn run_conversion_loop(
 			break;
 		};
 
-		handle_notif(msg, &mut images, &mut page);
+		handle_notif(
+			msg,
+			&mut images,
+			&mut thumbnails,
+			&mut page,
+			&mut continuous
+		);
 	}
 
 	Ok(())
 }
+
+fn decode_pnm(pixels: &[u8]) -> Result<RgbImage, RenderError> {
+	let decoder = PnmDecoder::new(Cursor::new(pixels)).map_err(|e| {
+		RenderError::Converting(format!(
+			"The image data provided from mupdf was not in pnm format ({e}); don't know how to convert"
+		))
+	})?;
+
+	// The image we get should always already be `ImageRgb8`, so this `into` shouldn't do any
+	// conversions or anything, but just in case some underlying detail of mupdf or image
+	// changes, we do the `into` instead of just `match + unreachable!()` to avoid panicking
+	Ok(DynamicImage::from_decoder(decoder)
+		.map_err(|e| RenderError::Converting(format!("Can't load image: {e}")))?
+		.into_rgb8())
+}
+
+// Turns the image into whatever the terminal can show. `kitty_id` is which id (past the first one)
+// kitty should store it under.
+fn to_converted_image(
+	dyn_img: DynamicImage,
+	img_size: Size,
+	picker: &Picker,
+	kitty_id: u32,
+	pid: u32,
+	shms_work: bool,
+	continuous: bool
+) -> Result<ConvertedImage, RenderError> {
+	Ok(match picker.protocol_type() {
+		ProtocolType::Kitty => {
+			let rn = SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.unwrap_or_default()
+				.as_nanos() % 1_000_000;
+
+			let mut img = if shms_work {
+				let shm_name = format!("/tdf_{pid}_{rn}_{kitty_id}");
+
+				#[cfg(unix)]
+				let shm_name = &*shm_name;
+
+				kittage::image::Image::shm_from(dyn_img, shm_name)
+					.map_err(|e| RenderError::Converting(format!("Couldn't create shm: {e:?}")))?
+			} else {
+				kittage::image::Image::from(dyn_img)
+			};
+
+			img.num_or_id = NumberOrId::Id(NonZeroU32::MIN.saturating_add(kitty_id));
+
+			ConvertedImage::Kitty {
+				img: MaybeTransferred::NotYet(img),
+				cell_w: img_size.width,
+				cell_h: img_size.height
+			}
+		}
+		_ if continuous => ConvertedImage::Croppable(Box::new(CropSource {
+			img: dyn_img,
+			picker: picker.clone(),
+			cell_w: img_size.width,
+			cell_h: img_size.height,
+			last: None
+		})),
+		_ => ConvertedImage::Generic(
+			picker
+				.new_protocol(dyn_img, img_size, Resize::Crop(None))
+				.map_err(|e| {
+					RenderError::Converting(format!(
+						"Couldn't convert DynamicImage to ratatui image: {e}"
+					))
+				})?
+		)
+	})
+}
+
+/// Tints the search results on a rendered page yellow
+pub(crate) fn highlight_results(img: &mut RgbImage, rects: &[HighlightRect]) {
+	highlight_rects(img, rects, |px| {
+		px.0[2] = px.0[2].saturating_sub(u8::MAX / 2);
+	});
+}
+
+fn highlight_rects(
+	img: &mut RgbImage,
+	rects: &[HighlightRect],
+	tint: impl Fn(&mut Rgb<u8>) + Sync
+) {
+	for quad in rects {
+		img.par_enumerate_pixels_mut()
+			.filter(|(x, y, _)| {
+				*x > quad.ul_x && *x < quad.lr_x && *y > quad.ul_y && *y < quad.lr_y
+			})
+			.for_each(|(_, _, px)| tint(px));
+	}
+}
@@ -0,0 +1,295 @@
+use std::{
+	fmt, fs,
+	io::{self, Cursor},
+	num::NonZeroUsize,
+	ops::RangeInclusive,
+	path::{Path, PathBuf},
+	str::FromStr
+};
+
+use image::{DynamicImage, ImageFormat, codecs::pnm::PnmDecoder};
+use mupdf::Document;
+
+use crate::{
+	FitOrFill,
+	converter::highlight_results,
+	renderer::{PrevRender, RotateDirection, render_single_page_to_ctx},
+	search::SearchQuery
+};
+
+// What pages are rendered at when no size is given; enough to be readable without the files
+// getting huge
+const DEFAULT_DPI: f32 = 150.;
+// PDF coordinates are in points, of which there are 72 in an inch
+const POINTS_PER_INCH: f32 = 72.;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+	Png,
+	Ppm
+}
+
+impl ExportFormat {
+	fn extension(self) -> &'static str {
+		match self {
+			Self::Png => "png",
+			Self::Ppm => "ppm"
+		}
+	}
+
+	fn image_format(self) -> ImageFormat {
+		match self {
+			Self::Png => ImageFormat::Png,
+			Self::Ppm => ImageFormat::Pnm
+		}
+	}
+}
+
+impl FromStr for ExportFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"png" => Ok(Self::Png),
+			"ppm" => Ok(Self::Ppm),
+			_ => Err(format!("Unknown format `{s}` (expected `png` or `ppm`)"))
+		}
+	}
+}
+
+/// A range of pages, numbered from 1 like they are in the viewer. Written as `3-7`, `3-` (from 3
+/// to the end), or just `3`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PageRange {
+	first: NonZeroUsize,
+	last: Option<NonZeroUsize>
+}
+
+impl PageRange {
+	// The (0-indexed) pages in this range, or None if none of them are in the document
+	fn indices(self, n_pages: usize) -> Option<RangeInclusive<usize>> {
+		let last = self.last.map_or(n_pages, |last| last.get().min(n_pages));
+		(self.first.get() <= last).then(|| self.first.get() - 1..=last - 1)
+	}
+}
+
+impl FromStr for PageRange {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let page = |num: &str| {
+			num.trim()
+				.parse::<NonZeroUsize>()
+				.map_err(|e| format!("Invalid page number `{num}` in `{s}`: {e}"))
+		};
+
+		match s.split_once('-') {
+			None => page(s).map(|page| Self {
+				first: page,
+				last: Some(page)
+			}),
+			Some((first, "")) => Ok(Self {
+				first: page(first)?,
+				last: None
+			}),
+			Some((first, last)) => {
+				let (first, last) = (page(first)?, page(last)?);
+				if last < first {
+					return Err(format!("The page range `{s}` ends before it starts"));
+				}
+				Ok(Self {
+					first,
+					last: Some(last)
+				})
+			}
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ExportSize {
+	// Scaled to fit within the given number of pixels in whichever directions are given
+	Fit {
+		width: Option<NonZeroUsize>,
+		height: Option<NonZeroUsize>
+	},
+	Dpi(f32)
+}
+
+impl Default for ExportSize {
+	fn default() -> Self {
+		Self::Dpi(DEFAULT_DPI)
+	}
+}
+
+pub struct ExportOptions {
+	pub pages: Option<PageRange>,
+	pub size: ExportSize,
+	pub format: ExportFormat,
+	pub out_dir: PathBuf,
+	pub invert: bool,
+	// Whether to draw annotations (like highlights and sticky notes) on the pages
+	pub annotations: bool,
+	pub rotate: RotateDirection,
+	pub black: i32,
+	pub white: i32,
+	pub search: Option<SearchQuery>,
+	pub password: Option<String>
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+	Doc(mupdf::error::Error),
+	WrongPassword,
+	NoPagesInRange { n_pages: usize },
+	CreateDir(PathBuf, io::Error),
+	Converting(String),
+	Write(PathBuf, image::ImageError)
+}
+
+impl From<mupdf::error::Error> for ExportError {
+	fn from(e: mupdf::error::Error) -> Self {
+		Self::Doc(e)
+	}
+}
+
+impl fmt::Display for ExportError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Doc(e) => write!(f, "Couldn't render the document: {e}"),
+			Self::WrongPassword => write!(
+				f,
+				"The document is encrypted; give its password in `--password-file` or the `TDF_PASSWORD` environment variable"
+			),
+			Self::NoPagesInRange { n_pages } => write!(
+				f,
+				"None of those pages are in the document (it has {n_pages})"
+			),
+			Self::CreateDir(dir, e) => write!(f, "Couldn't create {}: {e}", dir.display()),
+			Self::Converting(e) => write!(f, "Couldn't convert the rendered page: {e}"),
+			Self::Write(path, e) => write!(f, "Couldn't write {}: {e}", path.display())
+		}
+	}
+}
+
+impl std::error::Error for ExportError {}
+
+/// Renders pages of the document at `path` to image files in `options.out_dir`, the same way
+/// that the viewer would show them (but without needing a terminal to do it). Each file is named
+/// after the document and the page's number, e.g. `paper-03.png`, and the paths of all the files
+/// that were written are returned.
+pub fn export(path: &Path, options: &ExportOptions) -> Result<Vec<PathBuf>, ExportError> {
+	let name = path
+		.file_stem()
+		.map_or_else(|| "page".into(), |stem| stem.to_string_lossy());
+
+	// mupdf wants a `&str` on windows but a `&Path` everywhere else, like in the renderer
+	#[cfg(windows)]
+	let path = path.to_string_lossy();
+
+	#[cfg_attr(unix, expect(clippy::borrow_deref_ref))]
+	let mut doc = Document::open(&*path)?;
+	if doc.needs_password()? {
+		let unlocked = match options.password {
+			Some(ref password) => doc.authenticate(password)?,
+			None => false
+		};
+		if !unlocked {
+			return Err(ExportError::WrongPassword);
+		}
+	}
+
+	let n_pages = usize::try_from(doc.page_count()?).unwrap_or_default();
+	let pages = match options.pages {
+		Some(range) => range.indices(n_pages),
+		None => n_pages.checked_sub(1).map(|last| 0..=last)
+	}
+	.ok_or(ExportError::NoPagesInRange { n_pages })?;
+
+	fs::create_dir_all(&options.out_dir)
+		.map_err(|e| ExportError::CreateDir(options.out_dir.clone(), e))?;
+
+	// Padded so that the files sort in the same order as the pages
+	let digits = n_pages.to_string().len();
+
+	pages
+		.map(|page_num| {
+			let page = doc.load_page(page_num as i32)?;
+
+			let bounds = page.bounds()?;
+			let page_dim = match options.rotate {
+				RotateDirection::Deg0 | RotateDirection::Deg180 =>
+					(bounds.x1 - bounds.x0, bounds.y1 - bounds.y0),
+				RotateDirection::Deg90 | RotateDirection::Deg270 =>
+					(bounds.y1 - bounds.y0, bounds.x1 - bounds.x0),
+			};
+			// Leaving out one of the dimensions lets the page be as big as it needs to be in that
+			// direction to fill the other one
+			let area = match options.size {
+				ExportSize::Fit { width, height } => (
+					width.map_or(f32::INFINITY, |w| w.get() as f32),
+					height.map_or(f32::INFINITY, |h| h.get() as f32)
+				),
+				ExportSize::Dpi(dpi) => (
+					page_dim.0 * dpi / POINTS_PER_INCH,
+					page_dim.1 * dpi / POINTS_PER_INCH
+				)
+			};
+
+			let ctx = render_single_page_to_ctx(
+				&page,
+				options.search.as_ref(),
+				&[],
+				&PrevRender::default(),
+				options.invert,
+				options.black,
+				options.white,
+				FitOrFill::Fit,
+				options.rotate,
+				None,
+				options.annotations,
+				area,
+				// Unlike in the viewer, there's no terminal that would refuse to show big images
+				None
+			)?;
+
+			let mut pnm = Vec::new();
+			ctx.pixmap.write_to(&mut pnm, mupdf::ImageFormat::PNM)?;
+			let decoder = PnmDecoder::new(Cursor::new(pnm))
+				.map_err(|e| ExportError::Converting(e.to_string()))?;
+			let mut img = DynamicImage::from_decoder(decoder)
+				.map_err(|e| ExportError::Converting(e.to_string()))?
+				.into_rgb8();
+
+			highlight_results(&mut img, &ctx.layout.result_rects);
+
+			let file = options.out_dir.join(format!(
+				"{name}-{:0digits$}.{}",
+				page_num + 1,
+				options.format.extension()
+			));
+			img.save_with_format(&file, options.format.image_format())
+				.map_err(|e| ExportError::Write(file.clone(), e))?;
+			Ok(file)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_page_ranges() {
+		let range = |s: &str| s.parse::<PageRange>().map(|range| range.indices(10));
+
+		assert_eq!(range("3-7"), Ok(Some(2..=6)));
+		assert_eq!(range("4"), Ok(Some(3..=3)));
+		assert_eq!(range("8-"), Ok(Some(7..=9)));
+		assert_eq!(range("8-20"), Ok(Some(7..=9)));
+		assert_eq!(range("11-"), Ok(None));
+		range("7-3").unwrap_err();
+		range("0-3").unwrap_err();
+		range("three").unwrap_err();
+	}
+}
@@ -0,0 +1,188 @@
+// PDF points are 1/72 of an inch
+const MM_PER_POINT: f32 = 25.4 / 72.;
+
+/// Everything that mupdf can tell us about the document as a whole, for the info popup
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocInfo {
+	pub title: Option<String>,
+	pub author: Option<String>,
+	pub subject: Option<String>,
+	// The program that the document was originally made in, e.g. `LaTeX with hyperref`
+	pub creator: Option<String>,
+	// The program that turned it into a PDF, e.g. `pdfTeX-1.40.25`
+	pub producer: Option<String>,
+	// Still in the PDF's own format, like `D:20240131120000+01'00'`
+	pub created: Option<String>,
+	pub modified: Option<String>,
+	// e.g. `PDF 1.7`, or `EPUB` for documents that aren't PDFs
+	pub format: Option<String>,
+	// How it's encrypted, or None if it isn't
+	pub encryption: Option<String>,
+	pub pages: usize,
+	pub page_sizes: Vec<PageSize>,
+	// These are only known for PDFs, since other kinds of documents don't have them
+	pub fonts: Option<Vec<Font>>,
+	pub tagged: Option<bool>
+}
+
+/// A size that some of the pages are, in PDF points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSize {
+	pub width: f32,
+	pub height: f32,
+	// How many pages are this size
+	pub count: usize
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Font {
+	// The font's name as the PDF gives it, which starts with something like `ABCDEF+` if only the
+	// characters that are used were embedded
+	pub name: String,
+	pub embedded: bool
+}
+
+impl PageSize {
+	/// Adds a page of `width` by `height` to the sizes that have been seen so far, in the order
+	/// they were first seen. Sizes that are only different by rounding are counted as the same.
+	pub fn add_to(sizes: &mut Vec<Self>, width: f32, height: f32) {
+		let same = |a: f32, b: f32| (a - b).abs() < 0.05;
+		match sizes
+			.iter_mut()
+			.find(|size| same(size.width, width) && same(size.height, height))
+		{
+			Some(size) => size.count += 1,
+			None => sizes.push(Self {
+				width,
+				height,
+				count: 1
+			})
+		}
+	}
+
+	fn describe(&self) -> String {
+		format!(
+			"{} x {} pt ({} x {} mm)",
+			short_num(self.width),
+			short_num(self.height),
+			short_num(self.width * MM_PER_POINT),
+			short_num(self.height * MM_PER_POINT)
+		)
+	}
+}
+
+impl DocInfo {
+	/// Each line of the popup (except for the fonts), as a label and what it's set to
+	#[must_use]
+	pub fn fields(&self) -> Vec<(&'static str, String)> {
+		let or_unset = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".into());
+		let date =
+			|value: &Option<String>| value.as_deref().map_or_else(|| "-".into(), format_date);
+
+		let mut fields = vec![
+			("Title", or_unset(&self.title)),
+			("Author", or_unset(&self.author)),
+			("Subject", or_unset(&self.subject)),
+			("Creator", or_unset(&self.creator)),
+			("Producer", or_unset(&self.producer)),
+			("Created", date(&self.created)),
+			("Modified", date(&self.modified)),
+			("Format", or_unset(&self.format)),
+			(
+				"Encryption",
+				self.encryption.clone().unwrap_or_else(|| "None".into())
+			),
+			("Pages", self.pages.to_string()),
+		];
+
+		match self.page_sizes.as_slice() {
+			[size] => fields.push(("Page size", size.describe())),
+			sizes =>
+				for (idx, size) in sizes.iter().enumerate() {
+					let label = if idx == 0 { "Page sizes" } else { "" };
+					let pages = if size.count == 1 { "page" } else { "pages" };
+					fields.push((
+						label,
+						format!("{} ({} {pages})", size.describe(), size.count)
+					));
+				},
+		}
+
+		if let Some(tagged) = self.tagged {
+			fields.push(("Tagged", if tagged { "Yes" } else { "No" }.to_owned()));
+		}
+
+		fields
+	}
+}
+
+/// Turns a PDF date like `D:20240131120000+01'00'` into something more readable, like
+/// `2024-01-31 12:00:00 +01:00`. Everything after the year is optional, and anything that doesn't
+/// look like a date is returned as it is.
+#[must_use]
+pub fn format_date(raw: &str) -> String {
+	let date = raw.strip_prefix("D:").unwrap_or(raw);
+	let digits = date.chars().take_while(char::is_ascii_digit).count();
+	let (nums, zone) = date.split_at(digits);
+
+	let Some(year) = nums.get(..4) else {
+		return raw.to_owned();
+	};
+
+	let mut formatted = year.to_owned();
+	for (start, sep) in [(4, '-'), (6, '-'), (8, ' '), (10, ':'), (12, ':')] {
+		let Some(part) = nums.get(start..start + 2) else {
+			break;
+		};
+		formatted.push(sep);
+		formatted.push_str(part);
+	}
+
+	match zone {
+		"" => (),
+		"Z" => formatted.push_str(" UTC"),
+		offset => {
+			formatted.push(' ');
+			formatted.push_str(offset.replace('\'', ":").trim_end_matches(':'));
+		}
+	}
+	formatted
+}
+
+// Numbers with at most one decimal place, and without it if it's just `.0`
+fn short_num(num: f32) -> String {
+	let formatted = format!("{num:.1}");
+	match formatted.strip_suffix(".0") {
+		Some(whole) => whole.to_owned(),
+		None => formatted
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn formats_dates() {
+		assert_eq!(
+			format_date("D:20240131120000+01'00'"),
+			"2024-01-31 12:00:00 +01:00"
+		);
+		assert_eq!(format_date("D:20240131093000Z"), "2024-01-31 09:30:00 UTC");
+		assert_eq!(format_date("D:202401"), "2024-01");
+		assert_eq!(format_date("sometime last week"), "sometime last week");
+	}
+
+	#[test]
+	fn groups_page_sizes() {
+		let mut sizes = Vec::new();
+		PageSize::add_to(&mut sizes, 595.28, 841.89);
+		PageSize::add_to(&mut sizes, 612., 792.);
+		PageSize::add_to(&mut sizes, 595.3, 841.9);
+
+		assert_eq!(sizes.len(), 2);
+		assert_eq!(sizes[0].count, 2);
+		assert_eq!(sizes[0].describe(), "595.3 x 841.9 pt (210 x 297 mm)");
+		assert_eq!(sizes[1].describe(), "612 x 792 pt (215.9 x 279.4 mm)");
+	}
+}
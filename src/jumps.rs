@@ -0,0 +1,113 @@
+use crate::state::Mark;
+
+// Like vim, only the most recent jumps are remembered
+const MAX_JUMPS: usize = 100;
+
+/// Where we were before each jump (like going to a page, following a link, or jumping to a mark),
+/// so that we can go back there and then forward again, like vim's jump list
+#[derive(Debug, Default)]
+pub struct JumpList {
+	// Oldest first. While going back through these, the position that we went back from is kept
+	// at `idx`, so that we can go forward to it again.
+	positions: Vec<Mark>,
+	// Where we are in `positions`, which is past the end unless we've gone back
+	idx: usize
+}
+
+impl JumpList {
+	/// Records that we're jumping away from `from`. Anything that we had gone back past is
+	/// forgotten, like the forward history in a web browser.
+	pub fn push(&mut self, from: Mark) {
+		self.positions.truncate(self.idx);
+		if self.positions.last() != Some(&from) {
+			self.positions.push(from);
+		}
+		if self.positions.len() > MAX_JUMPS {
+			self.positions.remove(0);
+		}
+		self.idx = self.positions.len();
+	}
+
+	/// The position to go back to from `current`, if there is one
+	pub fn back(&mut self, current: Mark) -> Option<Mark> {
+		let prev = self.idx.checked_sub(1)?;
+		match self.positions.get_mut(self.idx) {
+			Some(pos) => *pos = current,
+			None => self.positions.push(current)
+		}
+		self.idx = prev;
+		self.positions.get(prev).copied()
+	}
+
+	/// The position to go forward to from `current`, if we've gone back before
+	pub fn forward(&mut self, current: Mark) -> Option<Mark> {
+		let next = self.idx + 1;
+		let target = *self.positions.get(next)?;
+		self.positions[self.idx] = current;
+		self.idx = next;
+		Some(target)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::state::SavedZoom;
+
+	fn at(page: usize) -> Mark {
+		Mark { page, zoom: None }
+	}
+
+	#[test]
+	fn goes_back_and_forward() {
+		let mut jumps = JumpList::default();
+		assert_eq!(jumps.back(at(0)), None);
+
+		// 0 -> 10 -> 20 -> 30, then scrolling ahead to 31
+		jumps.push(at(0));
+		jumps.push(at(10));
+		jumps.push(at(20));
+
+		assert_eq!(jumps.back(at(31)), Some(at(20)));
+		assert_eq!(jumps.back(at(20)), Some(at(10)));
+		assert_eq!(jumps.back(at(10)), Some(at(0)));
+		assert_eq!(jumps.back(at(0)), None);
+
+		assert_eq!(jumps.forward(at(0)), Some(at(10)));
+		assert_eq!(jumps.forward(at(10)), Some(at(20)));
+		// Back to where we were before we started going back
+		assert_eq!(jumps.forward(at(20)), Some(at(31)));
+		assert_eq!(jumps.forward(at(31)), None);
+	}
+
+	#[test]
+	fn jumping_forgets_the_forward_history() {
+		let mut jumps = JumpList::default();
+		jumps.push(at(0));
+		jumps.push(at(10));
+		assert_eq!(jumps.back(at(20)), Some(at(10)));
+
+		// Scrolled a bit from 10, and then jumped somewhere else
+		jumps.push(at(12));
+		assert_eq!(jumps.forward(at(50)), None);
+		assert_eq!(jumps.back(at(50)), Some(at(12)));
+		assert_eq!(jumps.back(at(12)), Some(at(0)));
+	}
+
+	#[test]
+	fn keeps_zoom_and_skips_repeats() {
+		let zoomed = Mark {
+			page: 4,
+			zoom: Some(SavedZoom {
+				level: 3,
+				pan_from_left: 10,
+				pan_from_top: 25
+			})
+		};
+		let mut jumps = JumpList::default();
+		jumps.push(zoomed);
+		jumps.push(zoomed);
+		assert_eq!(jumps.back(at(9)), Some(zoomed));
+		assert_eq!(jumps.back(zoomed), None);
+	}
+}
@@ -0,0 +1,515 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Everything that can be bound to a key. These are named (in kebab-case) in the `[keys]` table
+/// of the config file.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+	NextPage,
+	PrevPage,
+	NextScreen,
+	PrevScreen,
+	GoToPage,
+	Search,
+	NextResult,
+	PrevResult,
+	Invert,
+	Fullscreen,
+	FollowLink,
+	ToggleOutline,
+	ToggleTextView,
+	ToggleContinuous,
+	ToggleSpread,
+	ToggleThumbnails,
+	NextTab,
+	PrevTab,
+	CloseTab,
+	Select,
+	SetMark,
+	JumpToMark,
+	ShowMarks,
+	JumpBack,
+	JumpForward,
+	ShowAnnotations,
+	ToggleAnnotations,
+	ShowInfo,
+	Rotate,
+	CycleTrim,
+	Help,
+	Suspend,
+	Quit,
+	ToggleZoom,
+	ZoomIn,
+	ZoomOut,
+	PanLeft,
+	PanRight,
+	PanUp,
+	PanDown,
+	PanToTop,
+	PanToBottom,
+	PanToLeftEdge,
+	PanToRightEdge
+}
+
+impl Action {
+	// In the order that they're shown on the help page
+	const ALL: [Self; 44] = [
+		Self::NextPage,
+		Self::PrevPage,
+		Self::NextScreen,
+		Self::PrevScreen,
+		Self::GoToPage,
+		Self::Search,
+		Self::NextResult,
+		Self::PrevResult,
+		Self::Invert,
+		Self::Fullscreen,
+		Self::FollowLink,
+		Self::ToggleOutline,
+		Self::ToggleTextView,
+		Self::ToggleContinuous,
+		Self::ToggleSpread,
+		Self::ToggleThumbnails,
+		Self::NextTab,
+		Self::PrevTab,
+		Self::CloseTab,
+		Self::Select,
+		Self::SetMark,
+		Self::JumpToMark,
+		Self::ShowMarks,
+		Self::JumpBack,
+		Self::JumpForward,
+		Self::ShowAnnotations,
+		Self::ToggleAnnotations,
+		Self::ShowInfo,
+		Self::Rotate,
+		Self::CycleTrim,
+		Self::Help,
+		Self::Suspend,
+		Self::Quit,
+		Self::ToggleZoom,
+		Self::ZoomIn,
+		Self::ZoomOut,
+		Self::PanLeft,
+		Self::PanRight,
+		Self::PanUp,
+		Self::PanDown,
+		Self::PanToTop,
+		Self::PanToBottom,
+		Self::PanToLeftEdge,
+		Self::PanToRightEdge
+	];
+
+	fn description(self) -> &'static str {
+		match self {
+			Self::NextPage => "Go forward a single page",
+			Self::PrevPage => "Go backwards a single page",
+			Self::NextScreen =>
+				"Go forward a screen's worth of pages (or scroll, in text/continuous mode)",
+			Self::PrevScreen =>
+				"Go backwards a screen's worth of pages (or scroll, in text/continuous mode)",
+			Self::GoToPage =>
+				"Go to specific page (type its number or its label, like xiv, after it)",
+			Self::Search =>
+				r"Search (start with re: for regex or w: for whole words; \C for case-sensitive)",
+			Self::NextResult => "Next search result",
+			Self::PrevResult => "Previous search result",
+			Self::Invert => "Invert colors",
+			Self::Fullscreen => "Remove borders/fullscreen",
+			Self::FollowLink => "Follow a link on screen (type the label shown next to it)",
+			Self::ToggleOutline =>
+				"Toggle outline (use j/k to move, h/l to collapse/expand, enter to jump)",
+			Self::ToggleTextView => "Toggle between showing the pages and just their text",
+			Self::ToggleContinuous =>
+				"Toggle continuous mode, with the pages stacked and scrolled through (J/K by row)",
+			Self::ToggleSpread => "Toggle pairing the pages up into spreads, like a printed book",
+			Self::ToggleThumbnails =>
+				"Toggle an overview of every page's thumbnail (h/j/k/l to move, enter to open one)",
+			Self::NextTab => "Switch to the next document's tab",
+			Self::PrevTab => "Switch to the previous document's tab",
+			Self::CloseTab => "Close the current document's tab (quitting if it's the last one)",
+			Self::Select => "Select text on the current page (or drag with the mouse), y to copy",
+			Self::SetMark =>
+				"Set a mark (type a letter after it); uppercase marks are saved with the document",
+			Self::JumpToMark => "Jump to a mark (type its letter after it)",
+			Self::ShowMarks => "List the marks that are set, to pick one to jump to",
+			Self::JumpBack =>
+				"Go back to where you were before the last jump (to a page, result, link, mark, etc.)",
+			Self::JumpForward => "Go forward again to where you jumped back from",
+			Self::ShowAnnotations =>
+				"List the annotations (like notes and highlights), to pick one to jump to",
+			Self::ToggleAnnotations => "Toggle showing annotations on the pages",
+			Self::ShowInfo =>
+				"Show the document's info, like its author, the program that made it, and its fonts",
+			Self::Rotate => "Rotate by 90 degrees",
+			Self::CycleTrim =>
+				"Cycle trimming the margins off: off, per page, the same on every page",
+			Self::Help => "Show this page",
+			Self::Suspend => "Suspend & background tdf",
+			Self::Quit => "Quit",
+			Self::ToggleZoom => "Toggle between fill-screen and fit-screen",
+			Self::ZoomIn => "Zoom in (when on fill-screen)",
+			Self::ZoomOut => "Zoom out (when on fill-screen)",
+			Self::PanLeft => "Pan left (when zoomed in)",
+			Self::PanRight => "Pan right (when zoomed in)",
+			Self::PanUp => "Pan up (when zoomed in)",
+			Self::PanDown => "Pan down (when zoomed in)",
+			Self::PanToTop => "Scroll to top of page (when on fill-screen)",
+			Self::PanToBottom => "Scroll to bottom of page (when on fill-screen)",
+			Self::PanToLeftEdge => "Scroll to left side of page (when on fill-screen)",
+			Self::PanToRightEdge => "Scroll to right side of page (when on fill-screen)"
+		}
+	}
+
+	#[must_use]
+	pub fn kitty_only(self) -> bool {
+		matches!(
+			self,
+			Self::ToggleZoom
+				| Self::ZoomIn
+				| Self::ZoomOut
+				| Self::PanLeft
+				| Self::PanRight
+				| Self::PanUp
+				| Self::PanDown
+				| Self::PanToTop
+				| Self::PanToBottom
+				| Self::PanToLeftEdge
+				| Self::PanToRightEdge
+		)
+	}
+
+	fn default_keys(self) -> Vec<KeyChord> {
+		let keys: &[KeyChord] = match self {
+			Self::NextPage => &[KeyChord::char('l'), KeyChord::plain(KeyCode::Right)],
+			Self::PrevPage => &[KeyChord::char('h'), KeyChord::plain(KeyCode::Left)],
+			Self::NextScreen => &[
+				KeyChord::char('j'),
+				KeyChord::plain(KeyCode::Down),
+				KeyChord::plain(KeyCode::PageDown)
+			],
+			Self::PrevScreen => &[
+				KeyChord::char('k'),
+				KeyChord::plain(KeyCode::Up),
+				KeyChord::plain(KeyCode::PageUp)
+			],
+			Self::GoToPage => &[KeyChord::char('g')],
+			Self::Search => &[KeyChord::char('/')],
+			Self::NextResult => &[KeyChord::char('n')],
+			Self::PrevResult => &[KeyChord::char('N')],
+			Self::Invert => &[KeyChord::char('i')],
+			Self::Fullscreen => &[KeyChord::char('f')],
+			Self::FollowLink => &[KeyChord::char('F')],
+			Self::ToggleOutline => &[KeyChord::char('t')],
+			Self::ToggleTextView => &[KeyChord::char('T')],
+			Self::ToggleContinuous => &[KeyChord::char('c')],
+			Self::ToggleSpread => &[KeyChord::char('b')],
+			Self::ToggleThumbnails => &[KeyChord::char('p')],
+			Self::NextTab => &[KeyChord::plain(KeyCode::Tab)],
+			Self::PrevTab => &[KeyChord::plain(KeyCode::BackTab)],
+			Self::CloseTab => &[KeyChord::char('x')],
+			Self::Select => &[KeyChord::char('v')],
+			Self::SetMark => &[KeyChord::char('m')],
+			Self::JumpToMark => &[KeyChord::char('\''), KeyChord::char('`')],
+			Self::ShowMarks => &[KeyChord::char('M')],
+			Self::JumpBack => &[KeyChord {
+				code: KeyCode::Char('o'),
+				modifiers: KeyModifiers::CONTROL
+			}],
+			// Most terminals send this the same way as tab, unless they support the kitty keyboard
+			// protocol (which we ask for)
+			Self::JumpForward => &[KeyChord {
+				code: KeyCode::Char('i'),
+				modifiers: KeyModifiers::CONTROL
+			}],
+			Self::ShowAnnotations => &[KeyChord::char('a')],
+			Self::ToggleAnnotations => &[KeyChord::char('A')],
+			Self::ShowInfo => &[KeyChord::char('I')],
+			Self::Rotate => &[KeyChord::char('r')],
+			Self::CycleTrim => &[KeyChord::char('w')],
+			Self::Help => &[KeyChord::char('?')],
+			Self::Suspend => &[KeyChord {
+				code: KeyCode::Char('z'),
+				modifiers: KeyModifiers::CONTROL
+			}],
+			Self::Quit => &[KeyChord::char('q')],
+			Self::ToggleZoom => &[KeyChord::char('z')],
+			Self::ZoomIn => &[KeyChord::char('o')],
+			Self::ZoomOut => &[KeyChord::char('O')],
+			Self::PanLeft => &[KeyChord::char('H')],
+			Self::PanRight => &[KeyChord::char('L')],
+			Self::PanUp => &[KeyChord::char('K')],
+			Self::PanDown => &[KeyChord::char('J')],
+			// This is also available as `gg`, but that's handled specially since it's two keys
+			Self::PanToTop => &[],
+			Self::PanToBottom => &[KeyChord::char('G')],
+			Self::PanToLeftEdge => &[KeyChord::char('0')],
+			Self::PanToRightEdge => &[KeyChord::char('$')]
+		};
+		keys.to_vec()
+	}
+}
+
+/// A single key, plus the modifiers that need to be held with it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyChord {
+	code: KeyCode,
+	modifiers: KeyModifiers
+}
+
+impl KeyChord {
+	const fn plain(code: KeyCode) -> Self {
+		Self {
+			code,
+			modifiers: KeyModifiers::NONE
+		}
+	}
+
+	const fn char(c: char) -> Self {
+		Self::plain(KeyCode::Char(c))
+	}
+
+	fn matches(self, key: &KeyEvent) -> bool {
+		// Whether shift was held is already shown by the case of the char (or by the key being
+		// backtab instead of tab), and terminals aren't consistent about whether they also report
+		// it as a modifier, so we ignore it for those
+		let normalize = |modifiers: KeyModifiers| match self.code {
+			KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+			_ => modifiers
+		};
+		self.code == key.code && normalize(self.modifiers) == normalize(key.modifiers)
+	}
+}
+
+// The names of the non-char keys, as they're written in the config file and on the help page
+const KEY_NAMES: [(&str, KeyCode); 15] = [
+	("left", KeyCode::Left),
+	("right", KeyCode::Right),
+	("up", KeyCode::Up),
+	("down", KeyCode::Down),
+	("pageup", KeyCode::PageUp),
+	("pagedown", KeyCode::PageDown),
+	("home", KeyCode::Home),
+	("end", KeyCode::End),
+	("enter", KeyCode::Enter),
+	("tab", KeyCode::Tab),
+	("backtab", KeyCode::BackTab),
+	("backspace", KeyCode::Backspace),
+	("delete", KeyCode::Delete),
+	("insert", KeyCode::Insert),
+	("space", KeyCode::Char(' '))
+];
+
+const MODIFIER_NAMES: [(&str, KeyModifiers); 3] = [
+	("ctrl", KeyModifiers::CONTROL),
+	("alt", KeyModifiers::ALT),
+	("shift", KeyModifiers::SHIFT)
+];
+
+impl FromStr for KeyChord {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut modifiers = KeyModifiers::NONE;
+		let mut rest = s;
+		// We can't just split on every '+' since '+' itself can be bound
+		while let Some((prefix, after)) = rest.split_once('+')
+			&& !after.is_empty()
+			&& let Some((_, modifier)) = MODIFIER_NAMES
+				.iter()
+				.find(|(name, _)| name.eq_ignore_ascii_case(prefix))
+		{
+			modifiers |= *modifier;
+			rest = after;
+		}
+
+		let mut chars = rest.chars();
+		let code = match (chars.next(), chars.next()) {
+			(Some(c), None) => KeyCode::Char(c),
+			_ => KEY_NAMES
+				.iter()
+				.find(|(name, _)| name.eq_ignore_ascii_case(rest))
+				.map(|(_, code)| *code)
+				.or_else(|| {
+					rest.strip_prefix(['f', 'F'])
+						.and_then(|num| num.parse().ok())
+						.filter(|num| (1..=12).contains(num))
+						.map(KeyCode::F)
+				})
+				.ok_or_else(|| format!("Unknown key {rest:?} in {s:?}"))?
+		};
+
+		// Shift is ignored for chars when matching keys, since it's already shown by their case, so
+		// it has to be turned into that here
+		if let KeyCode::Char(c) = code
+			&& modifiers.contains(KeyModifiers::SHIFT)
+		{
+			if !c.is_ascii_alphabetic() {
+				return Err(format!(
+					"Shift can't be combined with {c:?} in {s:?}; use the character that shift+{c} types instead"
+				));
+			}
+			modifiers -= KeyModifiers::SHIFT;
+			return Ok(Self {
+				code: KeyCode::Char(c.to_ascii_uppercase()),
+				modifiers
+			});
+		}
+
+		Ok(Self { code, modifiers })
+	}
+}
+
+impl TryFrom<String> for KeyChord {
+	type Error = String;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl fmt::Display for KeyChord {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (name, modifier) in MODIFIER_NAMES {
+			if self.modifiers.contains(modifier) {
+				write!(f, "{name}+")?;
+			}
+		}
+
+		match KEY_NAMES.iter().find(|(_, code)| *code == self.code) {
+			Some((name, _)) => f.write_str(name),
+			None => match self.code {
+				KeyCode::Char(c) => write!(f, "{c}"),
+				KeyCode::F(num) => write!(f, "f{num}"),
+				other => write!(f, "{other:?}")
+			}
+		}
+	}
+}
+
+pub struct Keymap {
+	// In the same order as `Action::ALL`
+	bindings: Vec<(Action, Vec<KeyChord>)>
+}
+
+impl Default for Keymap {
+	fn default() -> Self {
+		Self::new(&HashMap::new())
+	}
+}
+
+impl Keymap {
+	/// Creates the default keymap, with every action in `overrides` bound to only the keys given
+	/// there instead. If one of those keys was also bound to something else by default, it's
+	/// removed from that other action so that the user's choice always wins.
+	#[must_use]
+	pub fn new(overrides: &HashMap<Action, Vec<KeyChord>>) -> Self {
+		let bindings = Action::ALL
+			.into_iter()
+			.map(|action| match overrides.get(&action) {
+				Some(keys) => (action, keys.clone()),
+				None => {
+					let mut keys = action.default_keys();
+					keys.retain(|key| !overrides.values().flatten().any(|o| o == key));
+					(action, keys)
+				}
+			})
+			.collect();
+
+		Self { bindings }
+	}
+
+	#[must_use]
+	pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+		self.bindings
+			.iter()
+			.find(|(_, keys)| keys.iter().any(|k| k.matches(key)))
+			.map(|(action, _)| *action)
+	}
+
+	/// The first key that `action` is bound to, for hints about what to press
+	#[must_use]
+	pub fn key_for(&self, action: Action) -> Option<&KeyChord> {
+		self.bindings
+			.iter()
+			.find(|(a, _)| *a == action)
+			.and_then(|(_, keys)| keys.first())
+	}
+
+	/// Returns (keys, description) for every action that's bound to something, for the help page
+	pub fn help_entries(&self, kitty_only: bool) -> impl Iterator<Item = (String, &'static str)> {
+		self.bindings
+			.iter()
+			.filter(move |(action, keys)| action.kitty_only() == kitty_only && !keys.is_empty())
+			.map(|(action, keys)| {
+				let keys = keys
+					.iter()
+					.map(ToString::to_string)
+					.collect::<Vec<_>>()
+					.join(", ");
+				(keys, action.description())
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_and_display_round_trip() {
+		for s in [
+			"l",
+			"ctrl+z",
+			"alt+shift+left",
+			"+",
+			"ctrl++",
+			"pagedown",
+			"f5",
+			"space"
+		] {
+			let chord = s.parse::<KeyChord>().unwrap();
+			assert_eq!(chord.to_string(), s);
+		}
+
+		// Shifted letters are bound the same way as the uppercase letter that they type
+		for (s, same_as) in [
+			("shift+j", "J"),
+			("ctrl+shift+j", "ctrl+J"),
+			("shift+J", "J")
+		] {
+			assert_eq!(
+				s.parse::<KeyChord>().unwrap(),
+				same_as.parse::<KeyChord>().unwrap()
+			);
+		}
+
+		"shift+1".parse::<KeyChord>().unwrap_err();
+		"ctrl+".parse::<KeyChord>().unwrap_err();
+		"hyper+x".parse::<KeyChord>().unwrap_err();
+		"f13".parse::<KeyChord>().unwrap_err();
+	}
+
+	#[test]
+	fn overrides_steal_keys() {
+		let overrides = HashMap::from([(Action::Quit, vec![KeyChord::char('l')])]);
+		let keymap = Keymap::new(&overrides);
+
+		let l = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
+		let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+		let right = KeyEvent::new(KeyCode::Right, KeyModifiers::NONE);
+		assert_eq!(keymap.action_for(&l), Some(Action::Quit));
+		assert_eq!(keymap.action_for(&q), None);
+		assert_eq!(keymap.action_for(&right), Some(Action::NextPage));
+
+		// shift shouldn't matter for chars, but the other modifiers should
+		let shifted_n = KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT);
+		let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+		assert_eq!(keymap.action_for(&shifted_n), Some(Action::PrevResult));
+		assert_eq!(keymap.action_for(&ctrl_n), None);
+	}
+}
@@ -3,22 +3,44 @@ use std::num::NonZeroUsize;
 #[global_allocator]
 static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum PrerenderLimit {
 	All,
 	Limited(NonZeroUsize)
 }
 
+pub mod annotations;
+pub mod budget;
+pub mod cache;
+pub mod config;
 pub mod converter;
+pub mod export;
+pub mod info;
+pub mod jumps;
+pub mod keymap;
 pub mod kitty;
+pub mod outline;
+pub mod page_labels;
+// It talks over a unix socket, so it only exists where those do
+#[cfg(unix)]
+pub mod remote;
 pub mod renderer;
+pub mod search;
+pub mod selection;
 pub mod skip;
+pub mod state;
+pub mod synctex;
+pub mod text_view;
+pub mod thumbnails;
 pub mod tui;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum FitOrFill {
 	Fit,
-	Fill
+	Fill,
+	// Fills the width of the area, no matter how tall that makes the page. Used in continuous
+	// mode, where the pages are scrolled through vertically
+	FitWidth
 }
 
 pub struct ScaledResult {
@@ -48,7 +70,8 @@ pub fn scale_img_for_area(
 	// scale the height to fit perfectly. The dimension that _is not_ scaled to fit perfectly
 	// is scaled by the same factor as the dimension that _is_ scaled perfectly.
 	let scale_factor = match (img_aspect_ratio > area_aspect_ratio, fit_or_fill) {
-		(true, FitOrFill::Fit) | (false, FitOrFill::Fill) => area_width / img_width,
+		(true, FitOrFill::Fit) | (false, FitOrFill::Fill) | (_, FitOrFill::FitWidth) =>
+			area_width / img_width,
 		(false, FitOrFill::Fit) | (true, FitOrFill::Fill) => area_height / img_height
 	};
 
@@ -58,3 +81,32 @@ pub fn scale_img_for_area(
 		scale_factor
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fit_width_ignores_the_height() {
+		// A tall page in a wide area
+		let page = (100.0, 200.0);
+		let area = (50.0, 25.0);
+
+		let fit = scale_img_for_area(page, area, FitOrFill::Fit);
+		assert_eq!(
+			(fit.width, fit.height, fit.scale_factor),
+			(12.5, 25.0, 0.125)
+		);
+
+		// It's as wide as the area, even though that makes it much taller than it
+		let fit_width = scale_img_for_area(page, area, FitOrFill::FitWidth);
+		assert_eq!(
+			(fit_width.width, fit_width.height, fit_width.scale_factor),
+			(50.0, 100.0, 0.5)
+		);
+
+		// And a wide page in a tall area is still fit to its width, just like `Fit` does
+		let fit_width = scale_img_for_area((256.0, 128.0), (16.0, 64.0), FitOrFill::FitWidth);
+		assert_eq!((fit_width.width, fit_width.height), (16.0, 8.0));
+	}
+}
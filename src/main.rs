@@ -4,16 +4,23 @@ use core::{
 };
 use std::{
 	borrow::Cow,
+	collections::HashMap,
 	ffi::OsString,
 	io::{BufReader, IsTerminal as _, Read as _, Stdout, Write as _, stdout},
-	mem,
-	path::PathBuf,
+	iter, mem,
+	ops::RangeInclusive,
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
 	sync::{Arc, Mutex},
 	time::Duration
 };
 
+use base64::{Engine as _, prelude::BASE64_STANDARD};
 use crossterm::{
-	event::EventStream,
+	event::{
+		EventStream, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+		PushKeyboardEnhancementFlags
+	},
 	execute,
 	terminal::{
 		EndSynchronizedUpdate, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
@@ -29,22 +36,37 @@ use kittage::{
 	delete::{ClearOrDelete, DeleteConfig, WhichToDelete},
 	error::{TerminalError, TransmitError}
 };
-use notify::{Event, EventKind, RecursiveMode, Watcher as _};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
 use ratatui::{Terminal, backend::CrosstermBackend};
 use ratatui_image::{
 	FontSize,
 	picker::{Picker, ProtocolType}
 };
+#[cfg(unix)]
+use tdf::remote::{self, RemoteCommand, RemoteRequest, RemoteResponse};
 use tdf::{
-	PrerenderLimit,
+	FitOrFill, PrerenderLimit,
+	budget::MemoryBudget,
+	config::Config,
 	converter::{ConvertedPage, ConverterMsg, run_conversion_loop},
+	export::{self, ExportFormat, ExportOptions, ExportSize},
+	keymap::{self, KeyChord, Keymap},
 	kitty::{
 		DisplayErr, DisplayErrSource, KittyDisplay, display_kitty_images, do_shms_work, run_action
 	},
-	renderer::{self, MUPDF_BLACK, MUPDF_WHITE, RenderError, RenderInfo, RenderNotif},
-	tui::{BottomMessage, InputAction, MessageSetting, Tui}
+	renderer::{
+		self, MUPDF_BLACK, MUPDF_WHITE, RenderError, RenderInfo, RenderNotif, RotateDirection, Trim
+	},
+	search::SearchQuery,
+	state::{self, DocKey, DocState},
+	synctex::synctex_paths,
+	tui::{BottomMessage, InputAction, MessageSetting, RenderLayout, Tabs, Tui}
 };
 
+// Commands only ever come in over a unix socket, so there's never one on other platforms
+#[cfg(not(unix))]
+type RemoteRequest = std::convert::Infallible;
+
 // Dummy struct for easy errors in main
 struct WrappedErr(Cow<'static, str>);
 
@@ -64,6 +86,9 @@ impl std::error::Error for WrappedErr {}
 
 fn reset_term() {
 	_ = disable_raw_mode();
+	// This is separate since it fails on terminals that can't do it (like the windows console),
+	// which would stop everything after it from running too
+	_ = execute!(std::io::stdout(), PopKeyboardEnhancementFlags);
 	_ = execute!(
 		std::io::stdout(),
 		LeaveAlternateScreen,
@@ -72,7 +97,82 @@ fn reset_term() {
 	);
 }
 
+// `tdf export` is parsed on its own instead of alongside the viewer's flags, since it doesn't
+// share any of them (and `parse_or_exit!` can't do subcommands)
+mod export_flags {
+	use std::{num::NonZeroUsize, path::PathBuf};
+
+	use tdf::{
+		export::{ExportFormat, PageRange},
+		renderer::RotateDirection
+	};
+
+	xflags::xflags! {
+		/// Render pages of a document to image files, without opening the viewer
+		cmd export {
+			/// The document to export pages from
+			required file: PathBuf
+			/// Which pages to export, e.g. `3-7`, `3-` (to the end), or `3`. Defaults to all of
+			/// them.
+			optional --pages pages: PageRange
+			/// The width to scale each page to, in pixels
+			optional --width width: NonZeroUsize
+			/// The height to scale each page to, in pixels. If `--width` is given too, pages are
+			/// scaled to fit within both.
+			optional --height height: NonZeroUsize
+			/// The resolution to render the pages at, if no width or height is given. Defaults
+			/// to 150.
+			optional --dpi dpi: f32
+			/// The format of the images, either `png` or `ppm`. Defaults to `png`.
+			optional --format format: ExportFormat
+			/// The directory to write the images to, which is created if it doesn't exist.
+			/// Defaults to the current directory.
+			optional --out out: PathBuf
+			/// Invert the colors of the pages
+			optional -i,--invert
+			/// Leave out annotations (like highlights and sticky notes) from the images
+			optional --hide-annotations
+			/// Rotate the pages by 0, 90, 180, or 270 degrees
+			optional --rotate rotate: RotateDirection
+			/// Custom white color, specified in css format (e.g. "FFFFFF" or "rgb(255, 255, 255)")
+			optional -w,--white-color white: String
+			/// Custom black color, specified in css format (e.g "000000" or "rgb(0, 0, 0)")
+			optional -b,--black-color black: String
+			/// Highlight everything that matches this search, written the same way as in the
+			/// viewer
+			optional --search search: String
+			/// A file containing the password to unlock the document with, if it's encrypted. The
+			/// password can also be given in the `TDF_PASSWORD` environment variable.
+			optional --password-file password_file: PathBuf
+		}
+	}
+}
+
+#[cfg(unix)]
+mod remote_flags {
+	use std::path::PathBuf;
+
+	xflags::xflags! {
+		/// Send a command to a viewer that was started with `--listen`, and print its response
+		cmd remote {
+			/// The socket that the viewer is listening on
+			required socket: PathBuf
+			/// The command to send: `goto <page>`, `search <term>`, `synctex <file>:<line>`,
+			/// `reload`, `rotate`, `invert`, `quit`, or `status`
+			repeated command: String
+		}
+	}
+}
+
 fn main() -> Result<(), WrappedErr> {
+	// Exporting and sending remote commands don't need a terminal (or anything async), so none of
+	// that gets set up for them
+	match std::env::args_os().nth(1) {
+		Some(arg) if arg == "export" => return export_pages(),
+		Some(arg) if arg == "--remote" => return send_remote_command(),
+		_ => ()
+	}
+
 	let rt = tokio::runtime::Builder::new_multi_thread()
 		.worker_threads(3)
 		.enable_time()
@@ -86,6 +186,24 @@ fn main() -> Result<(), WrappedErr> {
 	})
 }
 
+// Whether something that can be turned on in the config file should be on, given the flag that
+// turns it on and the one that turns it back off (along with their names, for the error if both
+// were given). `None` if neither flag was given, so that the caller can fall back to the config
+// file (or to something else if the config file doesn't set it either).
+fn flag_or_config(
+	(on_name, on): (&str, bool),
+	(off_name, off): (&str, bool)
+) -> Result<Option<bool>, WrappedErr> {
+	match (on, off) {
+		(true, true) => Err(WrappedErr(
+			format!("{on_name} cannot be combined with {off_name}").into()
+		)),
+		(true, false) => Ok(Some(true)),
+		(false, true) => Ok(Some(false)),
+		(false, false) => Ok(None)
+	}
+}
+
 async fn inner_main() -> Result<(), WrappedErr> {
 	let hook = std::panic::take_hook();
 	std::panic::set_hook(Box::new(move |info| {
@@ -98,30 +216,95 @@ async fn inner_main() -> Result<(), WrappedErr> {
 
 	const DEFAULT_DEBOUNCE_DELAY: Duration = Duration::from_millis(50);
 
+	// Every flag (besides `--version`, `--password-file`, `--listen`, and the files) can also be
+	// set in the config file, but anything given here takes precedence. Each of the ones that just
+	// turn something on or off has an opposite, so that what's set in the config file can be
+	// undone for a single run.
 	let flags = xflags::parse_or_exit! {
 		/// Display the pdf with the pages starting at the right hand size and moving left and
 		/// adjust input keys to match
 		optional -r,--r-to-l
+		/// Undo `r-to-l` from the config file
+		optional --no-r-to-l
 		/// The maximum number of pages to display together, horizontally, at a time
 		optional -m,--max-wide max_wide: NonZeroUsize
+		/// Pair the pages up into two-page spreads like a printed book, with the cover page shown
+		/// by itself. Overrides `--max-wide`.
+		optional --spread
+		/// Undo `spread` from the config file
+		optional --no-spread
+		/// Which page is the cover when showing spreads; it and every page before it are shown
+		/// alone. Defaults to 1.
+		optional --cover-page cover_page: NonZeroUsize
 		/// Fullscreen the pdf (hide document name, page count, etc)
 		optional -f,--fullscreen
+		/// Undo `fullscreen` from the config file
+		optional --no-fullscreen
 		/// The time to wait for the file to stop changing before reloading, in milliseconds.
 		/// Defaults to 50ms.
 		optional --reload-delay reload_delay: u64
 		/// The number of pages to prerender surrounding the currently-shown page; 0 means no
 		/// limit. By default, there is no limit.
 		optional -p,--prerender prerender: usize
+		/// How much memory (in MiB) the rendered pages can take up. Once they take up more than
+		/// this, the pages farthest from the current one are thrown out, and they're rendered again
+		/// when you get back to them. By default, there is no limit.
+		optional --memory-budget memory_budget: usize
 		/// Custom white color, specified in css format (e.g. "FFFFFF" or "rgb(255, 255, 255)")
 		optional -w,--white-color white: String
 		/// Custom black color, specified in css format (e.g "000000" or "rgb(0, 0, 0)")
 		optional -b,--black-color black: String
 		/// Use terminal foreground/background colors for the PDF
 		optional -t,--terminal-colors
+		/// Undo `terminal-colors` from the config file
+		optional --no-terminal-colors
+		/// The command to open external links with; the link is passed as the last argument.
+		/// Defaults to `xdg-open` (or `open` on macOS and `explorer` on Windows)
+		optional --link-opener link_opener: String
+		/// The command to open a TeX source file at a line with, for ctrl+clicking on a document
+		/// that has SyncTeX data. `%{input}` is replaced with the file and `%{line}` with the line,
+		/// e.g. `nvr --remote-silent +%{line} %{input}`.
+		optional --synctex-editor synctex_editor: String
+		/// Don't open the document at the page, zoom, rotation, etc. it was left at last time, and
+		/// don't remember them (or the uppercase marks) when quitting
+		optional --no-restore-position
+		/// Undo `no-restore-position` from the config file
+		optional --restore-position
+		/// Show just the text of each page instead of its image. This is turned on automatically
+		/// if your terminal can't display images.
+		optional --text-view
+		/// Undo `text-view` from the config file
+		optional --no-text-view
+		/// Stack the pages on top of each other at the width of the screen and scroll through
+		/// them continuously, instead of flipping between whole pages
+		optional --continuous
+		/// Undo `continuous` from the config file
+		optional --no-continuous
+		/// Trim the empty margins off of the pages: `per-page` crops each page to what's on it,
+		/// and `uniform` crops every page the same amount. Defaults to `off`.
+		optional --trim trim: Trim
+		/// Don't draw annotations (like highlights and sticky notes) on the pages. They can be
+		/// shown again while viewing with `A`.
+		optional --hide-annotations
+		/// Undo `hide-annotations` from the config file
+		optional --show-annotations
+		/// A file containing the password to unlock the document with, if it's encrypted. The
+		/// password can also be given in the `TDF_PASSWORD` environment variable. If neither is
+		/// given (or the password is wrong), you'll be asked for it.
+		optional --password-file password_file: PathBuf
+		/// Don't save rendered pages in `$XDG_CACHE_HOME/tdf/pages`, or use the ones that were
+		/// saved before. Reopening a big document is much faster with them.
+		optional --no-cache
+		/// Undo `no-cache` from the config file
+		optional --cache
+		/// Listen for commands (like `goto 12` or `search foo`) on a unix socket at this path, so
+		/// that other programs like your editor can control the viewer. Use
+		/// `tdf --remote <path> <command>` to send them.
+		optional --listen listen: PathBuf
 		/// Print the version and exit
 		optional --version
-		/// PDF file to read
-		optional file: PathBuf
+		/// PDF files to read; each one is opened in its own tab
+		repeated files: PathBuf
 	};
 
 	if flags.version {
@@ -129,58 +312,121 @@ async fn inner_main() -> Result<(), WrappedErr> {
 		return Ok(());
 	}
 
-	let Some(file) = flags.file else {
+	let config = Config::load().map_err(|e| WrappedErr(e.to_string().into()))?;
+
+	let r_to_l = flag_or_config(("--r-to-l", flags.r_to_l), ("--no-r-to-l", flags.no_r_to_l))?
+		.unwrap_or(config.r_to_l);
+	let fullscreen = flag_or_config(
+		("--fullscreen", flags.fullscreen),
+		("--no-fullscreen", flags.no_fullscreen)
+	)?
+	.unwrap_or(config.fullscreen);
+	let max_wide = flags.max_wide.or(config.max_wide);
+	let spread = flag_or_config(("--spread", flags.spread), ("--no-spread", flags.no_spread))?
+		.unwrap_or(config.spread);
+	let cover_page = flags
+		.cover_page
+		.or(config.cover_page)
+		.unwrap_or(NonZeroUsize::MIN);
+	let reload_delay = flags.reload_delay.or(config.reload_delay);
+	let prerender = flags.prerender.or(config.prerender);
+	let memory_budget = flags
+		.memory_budget
+		.or(config.memory_budget)
+		.map(|mib| mib.saturating_mul(1024 * 1024));
+	let link_opener = flags
+		.link_opener
+		.or(config.link_opener)
+		.unwrap_or_else(|| DEFAULT_LINK_OPENER.to_owned());
+	let synctex_editor = flags.synctex_editor.or(config.synctex_editor);
+	let restore_position = !flag_or_config(
+		("--no-restore-position", flags.no_restore_position),
+		("--restore-position", flags.restore_position)
+	)?
+	.unwrap_or(config.no_restore_position);
+	let text_view = flag_or_config(
+		("--text-view", flags.text_view),
+		("--no-text-view", flags.no_text_view)
+	)?
+	.or(config.text_view);
+	let continuous = flag_or_config(
+		("--continuous", flags.continuous),
+		("--no-continuous", flags.no_continuous)
+	)?
+	.unwrap_or(config.continuous);
+	let trim = flags.trim.or(config.trim).unwrap_or_default();
+	let annotations = !flag_or_config(
+		("--hide-annotations", flags.hide_annotations),
+		("--show-annotations", flags.show_annotations)
+	)?
+	.unwrap_or(config.hide_annotations);
+	let cache_pages = !flag_or_config(("--no-cache", flags.no_cache), ("--cache", flags.cache))?
+		.unwrap_or(config.no_cache);
+	let listen = flags.listen;
+	#[cfg(not(unix))]
+	if listen.is_some() {
 		return Err(WrappedErr(
-			"Please specify the file to open, e.g. `tdf ./my_example_pdf.pdf`".into()
+			"--listen is only supported on unix-like systems, since it needs a unix socket".into()
 		));
-	};
+	}
 
-	let path = file
-		.canonicalize()
-		.map_err(|e| WrappedErr(format!("Cannot canonicalize provided file: {e}").into()))?;
+	let password = read_password(flags.password_file.as_deref())?;
 
-	if flags.terminal_colors && (flags.black_color.is_some() || flags.white_color.is_some()) {
+	// The colors are all-or-nothing, so that e.g. passing `-t` on the command line overrides a
+	// `black-color` in the config file instead of conflicting with it
+	let colors_from_flags = flags.terminal_colors
+		|| flags.no_terminal_colors
+		|| flags.black_color.is_some()
+		|| flags.white_color.is_some();
+	if flags.terminal_colors && flags.no_terminal_colors {
 		return Err(WrappedErr(
-			"--terminal-colors cannot be combined with --black-color or --white-color".into()
+			"--terminal-colors cannot be combined with --no-terminal-colors".into()
 		));
 	}
-
-	let (black, white) = if flags.terminal_colors {
-		query_terminal_colors()
+	let (terminal_colors, black_color, white_color) = if colors_from_flags {
+		(flags.terminal_colors, flags.black_color, flags.white_color)
 	} else {
-		let black = flags
-			.black_color
-			.as_deref()
-			.map(|color| {
-				parse_color_to_i32(color).map_err(|e| {
-					WrappedErr(
-						format!(
-							"Couldn't parse black color {color:?}: {e} - is it formatted like a CSS color?"
-						)
-						.into()
-					)
-				})
-			})
-			.transpose()?
-			.unwrap_or(MUPDF_BLACK);
-
-		let white = flags
-			.white_color
-			.as_deref()
-			.map(|color| {
-				parse_color_to_i32(color).map_err(|e| {
-					WrappedErr(
-						format!(
-							"Couldn't parse white color {color:?}: {e} - is it formatted like a CSS color?"
-						)
-						.into()
-					)
-				})
+		(
+			config.terminal_colors,
+			config.black_color,
+			config.white_color
+		)
+	};
+
+	if flags.files.is_empty() {
+		return Err(WrappedErr(
+			"Please specify the file to open, e.g. `tdf ./my_example_pdf.pdf`".into()
+		));
+	}
+
+	let paths = flags
+		.files
+		.iter()
+		.map(|file| {
+			file.canonicalize().map_err(|e| {
+				WrappedErr(
+					format!("Cannot canonicalize provided file {}: {e}", file.display()).into()
+				)
 			})
-			.transpose()?
-			.unwrap_or(MUPDF_WHITE);
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	if terminal_colors && (black_color.is_some() || white_color.is_some()) {
+		return Err(WrappedErr(if colors_from_flags {
+			"--terminal-colors cannot be combined with --black-color or --white-color".into()
+		} else {
+			"terminal-colors cannot be combined with black-color or white-color in the config file"
+				.into()
+		}));
+	}
 
-		(black, white)
+	let (black, white) = if terminal_colors {
+		query_terminal_colors()
+	} else {
+		(
+			parse_color_or(black_color.as_deref(), "black", MUPDF_BLACK)?,
+			parse_color_or(white_color.as_deref(), "white", MUPDF_WHITE)?
+		)
 	};
 
 	// need to keep it around throughout the lifetime of the program, but don't rly need to use it.
@@ -199,39 +445,6 @@ async fn inner_main() -> Result<(), WrappedErr> {
 		None
 	};
 
-	let (watch_to_render_tx, render_rx) = flume::unbounded();
-	let to_renderer = watch_to_render_tx.clone();
-
-	let (render_tx, tui_rx) = flume::unbounded();
-	let watch_to_tui_tx = render_tx.clone();
-
-	let mut watcher = notify::recommended_watcher(on_notify_ev(
-		watch_to_tui_tx,
-		watch_to_render_tx,
-		path.file_name()
-			.ok_or_else(|| WrappedErr("Path does not have a last component??".into()))?
-			.to_owned(),
-		flags
-			.reload_delay
-			.map_or(DEFAULT_DEBOUNCE_DELAY, Duration::from_millis)
-	))
-	.map_err(|e| WrappedErr(format!("Couldn't start watching the provided file: {e}").into()))?;
-
-	// So we have to watch the parent directory of the file that we are interested in because the
-	// `notify` library works on inodes, and if the file is deleted, that inode is gone as well,
-	// and then the notify library just gives up on trying to watch for the file reappearing. Imo
-	// they should start watching the parent directory if the file is deleted, and then wait for it
-	// to reappear and then begin watching it again, but whatever. It seems they've made their
-	// opinion on this clear
-	// (https://github.com/notify-rs/notify/issues/113#issuecomment-281836995) so whatever, guess
-	// we have to do this annoying workaround.
-	watcher
-		.watch(
-			path.parent().expect("The root directory is not a PDF"),
-			RecursiveMode::NonRecursive
-		)
-		.map_err(|e| WrappedErr(format!("Can't watch the provided file: {e}").into()))?;
-
 	let mut window_size = window_size().map_err(|e| {
 		WrappedErr(format!("Can't get your current terminal window size: {e}").into())
 	})?;
@@ -282,49 +495,22 @@ async fn inner_main() -> Result<(), WrappedErr> {
 			e => Err(WrappedErr(format!("Couldn't get the necessary information to set up images: {e}").into()))
 		})?;
 
-	// then we want to spawn off the rendering task
-	// We need to use the thread::spawn API so that this exists in a thread not owned by tokio,
-	// since the methods we call in `start_rendering` will panic if called in an async context
-	let prerender = flags
-		.prerender
+	let prerender = prerender
 		.and_then(NonZeroUsize::new)
 		.map_or(PrerenderLimit::All, PrerenderLimit::Limited);
 
-	let file_path = path.clone();
-	std::thread::spawn(move || {
-		renderer::start_rendering(
-			&file_path,
-			render_tx,
-			render_rx,
-			cell_height_px,
-			cell_width_px,
-			prerender,
-			black,
-			white
-		)
-	});
-
 	let font_size = picker.font_size();
 
 	let mut ev_stream = crossterm::event::EventStream::new();
 
-	let (to_converter, from_main) = flume::unbounded();
-	let (to_main, from_converter) = flume::unbounded();
-
 	let is_kitty = picker.protocol_type() == ProtocolType::Kitty;
+	// Halfblocks is what ratatui_image falls back to when the terminal doesn't support any actual
+	// image protocol, and pages drawn with it are basically unreadable. This is only a default
+	// though, so it's still up to the user if they said which one they want.
+	let text_view = text_view.unwrap_or(picker.protocol_type() == ProtocolType::Halfblocks);
 
 	let shms_work = is_kitty && do_shms_work(&mut ev_stream).await;
 
-	tokio::spawn(run_conversion_loop(
-		to_main, from_main, picker, 20, shms_work
-	));
-
-	let file_name = path.file_name().map_or_else(
-		|| "Unknown file".into(),
-		|n| n.to_string_lossy().to_string()
-	);
-	let tui = Tui::new(file_name, flags.max_wide, flags.r_to_l, is_kitty);
-
 	let backend = CrosstermBackend::new(std::io::stdout());
 	let mut term = Terminal::new(backend).map_err(|e| {
 		WrappedErr(format!("Couldn't set up crossterm's terminal backend: {e}").into())
@@ -351,30 +537,78 @@ async fn inner_main() -> Result<(), WrappedErr> {
 		})?;
 	}
 
-	let fullscreen = flags.fullscreen;
-	let main_area = Tui::main_layout(&term.get_frame(), fullscreen);
-	to_renderer
-		.send(RenderNotif::Area(main_area.page_area))
-		.map_err(|e| {
-			WrappedErr(
-				format!("Couldn't inform the rendering thread of the available area: {e}").into()
-			)
-		})?;
+	// Without this, ctrl+i is sent the same way as tab, so it couldn't be used to jump forward.
+	// Terminals that don't support the kitty keyboard protocol just ignore it.
+	_ = execute!(
+		std::io::stdout(),
+		PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+	);
+
+	let settings = DocSettings {
+		reload_delay: reload_delay.map_or(DEFAULT_DEBOUNCE_DELAY, Duration::from_millis),
+		cell_height_px,
+		cell_width_px,
+		prerender,
+		black,
+		white,
+		trim,
+		annotations,
+		password,
+		cache_pages,
+		memory_budget,
+		picker,
+		shms_work,
+		restore_position,
+		max_wide,
+		r_to_l,
+		spread,
+		cover_page,
+		text_view,
+		continuous,
+		keys: config.keys,
+		fullscreen
+	};
+
+	// Every document's renderer and converter send their messages to us through these, tagged
+	// with which document they're about
+	let (renderers_tx, from_renderers) = flume::unbounded();
+	let (converters_tx, from_converters) = flume::unbounded();
+
+	let mut docs = Vec::with_capacity(paths.len());
+	let mut kitty_ids = KittyIds::new();
+	for (id, path) in paths.into_iter().enumerate() {
+		let (doc, from_renderer, from_converter) =
+			open_doc(id, &path, &settings, &mut kitty_ids, &mut term)?;
+		forward_tagged(id, from_renderer, renderers_tx.clone());
+		forward_tagged(id, from_converter, converters_tx.clone());
+		docs.push(doc);
+	}
 
-	let tui_rx = tui_rx.into_stream();
-	let from_converter = from_converter.into_stream();
+	// If we're not listening, this just never gets anything
+	#[cfg(unix)]
+	let (remote_tx, from_remote) = flume::unbounded();
+	#[cfg(unix)]
+	if let Some(ref socket) = listen {
+		let listener = remote::bind(socket).map_err(|e| {
+			WrappedErr(format!("Couldn't listen on {}: {e}", socket.display()).into())
+		})?;
+		tokio::spawn(remote::serve(listener, remote_tx));
+	}
+	#[cfg(not(unix))]
+	let (_, from_remote) = flume::unbounded();
 
 	enter_redraw_loop(
 		ev_stream,
-		to_renderer,
-		tui_rx,
-		to_converter,
-		from_converter,
+		&mut docs,
+		&mut kitty_ids,
+		from_renderers.into_stream(),
+		from_converters.into_stream(),
 		fullscreen,
-		tui,
 		&mut term,
-		main_area,
-		font_size
+		from_remote.into_stream(),
+		font_size,
+		&link_opener,
+		synctex_editor.as_deref()
 	)
 	.await
 	.map_err(|e| {
@@ -386,28 +620,476 @@ async fn inner_main() -> Result<(), WrappedErr> {
 		)
 	})?;
 
+	#[cfg(unix)]
+	if let Some(socket) = listen {
+		// Nothing's listening on it anymore, so it'd just be in the way of the next viewer
+		_ = std::fs::remove_file(socket);
+	}
+
+	for doc in docs {
+		doc.save_state()?;
+	}
+
 	drop(maybe_logger);
 	Ok(())
 }
 
+// Each document's kitty images get their own range of ids, so that they don't replace each
+// other's images in the terminal's memory. If ur pdf has more than 16 million pages then you
+// deserve to suffer
+const KITTY_IDS_PER_DOC: u32 = 1 << 24;
+
+/// Hands out the ranges of kitty ids, taking them back once their document is closed so that two
+/// documents that are open at the same time never share one
+struct KittyIds {
+	// The first id (minus one) of each range that isn't being used
+	free: Vec<u32>
+}
+
+impl KittyIds {
+	fn new() -> Self {
+		// Reversed so that they're handed out from the start
+		Self {
+			free: (0..=u32::MAX / KITTY_IDS_PER_DOC)
+				.rev()
+				.map(|n| n * KITTY_IDS_PER_DOC)
+				.collect()
+		}
+	}
+
+	fn take(&mut self) -> Result<u32, WrappedErr> {
+		self.free.pop().ok_or_else(|| {
+			WrappedErr(
+				format!(
+					"Can't show more than {} documents at once",
+					u32::MAX / KITTY_IDS_PER_DOC + 1
+				)
+				.into()
+			)
+		})
+	}
+
+	fn give_back(&mut self, ids: &RangeInclusive<NonZeroU32>) {
+		self.free.push(ids.start().get() - 1);
+	}
+}
+
+/// What every document is opened with
+#[expect(clippy::struct_excessive_bools)]
+struct DocSettings {
+	reload_delay: Duration,
+	cell_height_px: u16,
+	cell_width_px: u16,
+	prerender: PrerenderLimit,
+	black: i32,
+	white: i32,
+	trim: Trim,
+	annotations: bool,
+	password: Option<String>,
+	cache_pages: bool,
+	// In bytes
+	memory_budget: Option<usize>,
+	picker: Picker,
+	shms_work: bool,
+	restore_position: bool,
+	max_wide: Option<NonZeroUsize>,
+	r_to_l: bool,
+	spread: bool,
+	cover_page: NonZeroUsize,
+	text_view: bool,
+	continuous: bool,
+	keys: HashMap<keymap::Action, Vec<KeyChord>>,
+	fullscreen: bool
+}
+
+type FromRenderer = flume::Receiver<Result<RenderInfo, RenderError>>;
+type FromConverter = flume::Receiver<Result<ConvertedPage, RenderError>>;
+
+fn forward_tagged<T: Send + 'static>(id: usize, rx: flume::Receiver<T>, tx: Sender<(usize, T)>) {
+	tokio::spawn(async move {
+		// This stops once the document's been closed (and so its renderer or converter has
+		// stopped) or once we're quitting
+		while let Ok(msg) = rx.recv_async().await {
+			if tx.send((id, msg)).is_err() {
+				break;
+			}
+		}
+	});
+}
+
+/// Everything that's kept separately for each open document, so that they each have their own
+/// page, search, zoom, etc.
+struct OpenDoc {
+	// Which document the messages from the renderers and converters are about, since they all come
+	// through the same streams. This doesn't change when other documents are closed.
+	id: usize,
+	tui: Tui,
+	to_renderer: Sender<RenderNotif>,
+	to_converter: Sender<ConverterMsg>,
+	// The layout that the renderer was last told about
+	area: RenderLayout,
+	key: Option<DocKey>,
+	state: DocState,
+	// So that they can be deleted from the terminal's memory once this document is closed
+	kitty_ids: Option<RangeInclusive<NonZeroU32>>,
+	// Only set if the pages are limited in how much memory they can take up
+	budget: Option<MemoryBudget>,
+	// We never touch this, but we need to hold onto it since it stops watching once it's dropped
+	_watcher: RecommendedWatcher
+}
+
+impl OpenDoc {
+	fn save_state(self) -> Result<(), WrappedErr> {
+		let Some(mut key) = self.key else {
+			return Ok(());
+		};
+		// If it was deleted in the meantime, it might still come back as it was
+		if let Err(e) = key.refresh() {
+			log::warn!("Couldn't fingerprint document again, so saving it as it was: {e}");
+		}
+		state::save(key, self.state).map_err(|e| {
+			WrappedErr(
+				format!(
+					"Couldn't remember your position in {}: {e}",
+					self.tui.name()
+				)
+				.into()
+			)
+		})
+	}
+
+	// Lets go of the pages that don't fit in the memory budget anymore, everywhere that they're
+	// held onto, and asks for the ones that fit again to be rendered. Kitty's copies of them can
+	// only be deleted once the event stream is free, so their ids are added to `kitty_evicted`.
+	fn rebalance_pages(
+		&mut self,
+		kitty_evicted: &mut Vec<RangeInclusive<NonZeroU32>>
+	) -> Result<(), Box<dyn Error>> {
+		let Some(ref mut budget) = self.budget else {
+			return Ok(());
+		};
+
+		let changes = budget.rebalance(self.tui.page, &self.tui.pages_on_screen());
+		for page in changes.evict {
+			let on_terminal = self.tui.evict_page(page);
+			self.to_converter.send(ConverterMsg::Evict(page))?;
+			if on_terminal && let Some(ref ids) = self.kitty_ids {
+				// This is the same id that the converter gave it
+				let id = ids.start().saturating_add(page as u32);
+				kitty_evicted.push(id..=id);
+			}
+		}
+		for page in changes.restore {
+			self.to_renderer
+				.send(RenderNotif::PageNeedsReRender(page))?;
+		}
+		Ok(())
+	}
+}
+
+// Starts up everything that renders, converts, and watches this document
+fn open_doc(
+	id: usize,
+	path: &Path,
+	settings: &DocSettings,
+	kitty_ids: &mut KittyIds,
+	term: &mut Terminal<CrosstermBackend<Stdout>>
+) -> Result<(OpenDoc, FromRenderer, FromConverter), WrappedErr> {
+	// If we can't read the file to fingerprint it, the renderer's about to complain about that
+	// anyways, so we just don't remember anything about it
+	let key = settings
+		.restore_position
+		.then(|| DocKey::new(path))
+		.and_then(|key| {
+			key.inspect_err(|e| log::warn!("Couldn't fingerprint document: {e}"))
+				.ok()
+		});
+	let state = key.as_ref().and_then(state::load).unwrap_or_default();
+
+	let (watch_to_render_tx, render_rx) = flume::unbounded();
+	let to_renderer = watch_to_render_tx.clone();
+
+	let (render_tx, from_renderer) = flume::unbounded();
+	let watch_to_tui_tx = render_tx.clone();
+
+	let mut watcher = notify::recommended_watcher(on_notify_ev(
+		watch_to_tui_tx,
+		watch_to_render_tx,
+		path.file_name()
+			.ok_or_else(|| WrappedErr("Path does not have a last component??".into()))?
+			.to_owned(),
+		settings.reload_delay
+	))
+	.map_err(|e| WrappedErr(format!("Couldn't start watching the provided file: {e}").into()))?;
+
+	// So we have to watch the parent directory of the file that we are interested in because the
+	// `notify` library works on inodes, and if the file is deleted, that inode is gone as well,
+	// and then the notify library just gives up on trying to watch for the file reappearing. Imo
+	// they should start watching the parent directory if the file is deleted, and then wait for it
+	// to reappear and then begin watching it again, but whatever. It seems they've made their
+	// opinion on this clear
+	// (https://github.com/notify-rs/notify/issues/113#issuecomment-281836995) so whatever, guess
+	// we have to do this annoying workaround.
+	watcher
+		.watch(
+			path.parent().expect("The root directory is not a PDF"),
+			RecursiveMode::NonRecursive
+		)
+		.map_err(|e| WrappedErr(format!("Can't watch the provided file: {e}").into()))?;
+
+	// then we want to spawn off the rendering task
+	// We need to use the thread::spawn API so that this exists in a thread not owned by tokio,
+	// since the methods we call in `start_rendering` will panic if called in an async context
+	let file_path = path.to_owned();
+	let (cell_height_px, cell_width_px) = (settings.cell_height_px, settings.cell_width_px);
+	let (prerender, black, white) = (settings.prerender, settings.black, settings.white);
+	let (trim, annotations) = (settings.trim, settings.annotations);
+	let (password, cache_pages) = (settings.password.clone(), settings.cache_pages);
+	std::thread::spawn(move || {
+		renderer::start_rendering(
+			&file_path,
+			render_tx,
+			render_rx,
+			cell_height_px,
+			cell_width_px,
+			prerender,
+			black,
+			white,
+			trim,
+			annotations,
+			password,
+			cache_pages
+		)
+	});
+
+	let (to_converter, from_main) = flume::unbounded();
+	let (to_main, from_converter) = flume::unbounded();
+
+	let is_kitty = settings.picker.protocol_type() == ProtocolType::Kitty;
+	// The ids aren't used for anything but kitty's images
+	let kitty_id_base = if is_kitty { kitty_ids.take()? } else { 0 };
+	tokio::spawn(run_conversion_loop(
+		to_main,
+		from_main,
+		settings.picker.clone(),
+		20,
+		settings.shms_work,
+		kitty_id_base
+	));
+
+	let file_name = path.file_name().map_or_else(
+		|| "Unknown file".into(),
+		|n| n.to_string_lossy().to_string()
+	);
+	let keymap = Keymap::new(&settings.keys);
+	let mut tui = Tui::new(
+		file_name,
+		settings.max_wide,
+		settings.r_to_l,
+		settings.cover_page,
+		is_kitty,
+		keymap
+	);
+	if settings.text_view {
+		tui.use_text_view();
+	}
+	if settings.continuous {
+		tui.use_continuous();
+	}
+	if settings.spread {
+		tui.use_spreads();
+	}
+
+	let area = tui.main_layout(&term.get_frame(), settings.fullscreen);
+	to_renderer
+		.send(RenderNotif::Area(area.page_area))
+		.map_err(|e| {
+			WrappedErr(
+				format!("Couldn't inform the rendering thread of the available area: {e}").into()
+			)
+		})?;
+
+	// The renderer ignores everything until it knows the area, so we can only tell it how to
+	// restore the last position after sending that. And this all happens before we first draw so
+	// that the first page doesn't flash on screen before jumping to the right one.
+	let position = state.position;
+	let fit_or_fill = tui.restore_state(&state);
+	let restore_notifs = position
+		.inverted
+		.then_some(RenderNotif::Invert)
+		.into_iter()
+		.chain(iter::repeat_with(|| RenderNotif::Rotate).take(usize::from(position.rotations % 4)))
+		.chain([
+			RenderNotif::SwitchFitOrFill(fit_or_fill),
+			RenderNotif::JumpToPage(tui.page)
+		]);
+	for notif in restore_notifs {
+		to_renderer.send(notif).map_err(|e| {
+			WrappedErr(format!("Couldn't restore your last position in this document: {e}").into())
+		})?;
+	}
+	for msg in [
+		ConverterMsg::GoToPage(tui.page),
+		ConverterMsg::Continuous(fit_or_fill == FitOrFill::FitWidth)
+	] {
+		to_converter.send(msg).map_err(|e| {
+			WrappedErr(format!("Couldn't restore your last position in this document: {e}").into())
+		})?;
+	}
+
+	let doc = OpenDoc {
+		id,
+		tui,
+		to_renderer,
+		to_converter,
+		area,
+		key,
+		state,
+		kitty_ids: is_kitty.then(|| {
+			NonZeroU32::MIN.saturating_add(kitty_id_base)
+				..=NonZeroU32::MIN.saturating_add(kitty_id_base + (KITTY_IDS_PER_DOC - 1))
+		}),
+		budget: settings.memory_budget.map(MemoryBudget::new),
+		_watcher: watcher
+	};
+	Ok((doc, from_renderer, from_converter))
+}
+
 // oh shut up clippy who cares
 #[expect(clippy::too_many_arguments)]
 async fn enter_redraw_loop(
 	mut ev_stream: EventStream,
-	to_renderer: Sender<RenderNotif>,
-	mut tui_rx: RecvStream<'_, Result<RenderInfo, RenderError>>,
-	to_converter: Sender<ConverterMsg>,
-	mut from_converter: RecvStream<'_, Result<ConvertedPage, RenderError>>,
+	docs: &mut Vec<OpenDoc>,
+	kitty_ids: &mut KittyIds,
+	mut from_renderers: RecvStream<'_, (usize, Result<RenderInfo, RenderError>)>,
+	mut from_converters: RecvStream<'_, (usize, Result<ConvertedPage, RenderError>)>,
 	mut fullscreen: bool,
-	mut tui: Tui,
 	term: &mut Terminal<CrosstermBackend<Stdout>>,
-	mut main_area: tdf::tui::RenderLayout,
-	font_size: FontSize
+	mut from_remote: RecvStream<'_, RemoteRequest>,
+	font_size: FontSize,
+	link_opener: &str,
+	synctex_editor: Option<&str>
 ) -> Result<(), Box<dyn Error>> {
 	let mut kitty_z_idx = i32::MIN;
+	// The index (in `docs`) of the document that's currently shown
+	let mut active = 0;
 
 	loop {
 		let mut needs_redraw = true;
+		// If a document was closed, its images need to be deleted from kitty's memory, but we can't
+		// do that while `next_ev` is borrowing the event stream
+		let mut closed_kitty_ids = None;
+		// Same with the images of pages that were thrown out to stay within the memory budget
+		let mut kitty_evicted = Vec::new();
+		// Actions from the keyboard and from the remote-control socket are handled the same way
+		macro_rules! handle_action {
+			($action:expr) => {{
+				let doc = &mut docs[active];
+				match $action {
+					InputAction::Redraw => (),
+					InputAction::QuitApp => {
+						for doc in docs.iter_mut() {
+							doc.tui.record_state(&mut doc.state);
+						}
+						return Ok(());
+					}
+					InputAction::JumpingToPage(page) => {
+						doc.to_renderer.send(RenderNotif::JumpToPage(page))?;
+						doc.to_converter.send(ConverterMsg::GoToPage(page))?;
+					}
+					InputAction::GoToResult { page, idx } => {
+						doc.to_renderer.send(RenderNotif::JumpToPage(page))?;
+						doc.to_renderer
+							.send(RenderNotif::ActiveResult(Some((page, idx))))?;
+						doc.to_converter.send(ConverterMsg::GoToPage(page))?;
+					}
+					InputAction::Search(term) => doc.to_renderer.send(RenderNotif::Search(term))?,
+					InputAction::OpenLink(uri) =>
+						if let Err(e) = open_link(link_opener, &uri) {
+							doc.tui
+								.set_msg(MessageSetting::Some(BottomMessage::Error(format!(
+									"Couldn't open {uri} with `{link_opener}`: {e}"
+								))));
+						},
+					InputAction::ExtractText(page) =>
+						doc.to_renderer.send(RenderNotif::ExtractText(page))?,
+					InputAction::Select(selection) =>
+						doc.to_renderer.send(RenderNotif::Select(selection))?,
+					InputAction::CopySelection(text) => {
+						doc.to_renderer.send(RenderNotif::Select(None))?;
+						if let Err(e) = copy_to_clipboard(&text) {
+							doc.tui
+								.set_msg(MessageSetting::Some(BottomMessage::Error(format!(
+									"Couldn't copy the selection: {e}"
+								))));
+						}
+					}
+					InputAction::Invert => {
+						doc.state.position.inverted = !doc.state.position.inverted;
+						doc.to_renderer.send(RenderNotif::Invert)?;
+					}
+					InputAction::Rotate => {
+						doc.state.position.rotations = (doc.state.position.rotations + 1) % 4;
+						doc.to_renderer.send(RenderNotif::Rotate)?;
+					}
+					InputAction::CycleTrim => doc.to_renderer.send(RenderNotif::CycleTrim)?,
+					InputAction::ToggleAnnotations =>
+						doc.to_renderer.send(RenderNotif::ToggleAnnotations)?,
+					InputAction::LoadInfo => doc.to_renderer.send(RenderNotif::DocInfo)?,
+					InputAction::Fullscreen => fullscreen = !fullscreen,
+					InputAction::Password(password) =>
+						doc.to_renderer.send(RenderNotif::Password(password))?,
+					InputAction::NextTab => {
+						active = (active + 1) % docs.len();
+						docs[active].tui.mark_for_redraw();
+					}
+					InputAction::PrevTab => {
+						active = active.checked_sub(1).unwrap_or(docs.len() - 1);
+						docs[active].tui.mark_for_redraw();
+					}
+					InputAction::CloseTab => {
+						let mut closed = docs.remove(active);
+						closed.tui.record_state(&mut closed.state);
+						closed_kitty_ids = closed.kitty_ids.take();
+						// Dropping it also drops the channels to its renderer and converter,
+						// which makes them stop
+						let saved = closed.save_state();
+
+						if docs.is_empty() {
+							saved?;
+							return Ok(());
+						}
+						active = active.min(docs.len() - 1);
+						let doc = &mut docs[active];
+						doc.tui.mark_for_redraw();
+						if let Err(e) = saved {
+							doc.tui
+								.set_msg(MessageSetting::Some(BottomMessage::Error(e.to_string())));
+						}
+					}
+					InputAction::SwitchRenderZoom(f_or_f) => {
+						doc.to_renderer.send(RenderNotif::SwitchFitOrFill(f_or_f))?;
+						doc.to_converter
+							.send(ConverterMsg::Continuous(f_or_f == FitOrFill::FitWidth))?;
+					}
+					InputAction::InverseSearch { page, point } => doc
+						.to_renderer
+						.send(RenderNotif::InverseSearch { page, point })?,
+					InputAction::Thumbnails(size) => {
+						doc.to_renderer.send(RenderNotif::Thumbnails(size))?;
+						// The page might've been changed from the overview, and even if it
+						// wasn't, the pages around it should be rendered first again
+						if size.is_none() {
+							doc.to_renderer
+								.send(RenderNotif::JumpToPage(doc.tui.page))?;
+							doc.to_converter
+								.send(ConverterMsg::GoToPage(doc.tui.page))?;
+						}
+					}
+				}
+			}};
+		}
+
 		let next_ev = ev_stream.next().fuse();
 		tokio::select! {
 			// First we check if we have any keystrokes
@@ -415,67 +1097,200 @@ async fn enter_redraw_loop(
 				// If we can't get user input, just crash.
 				let ev = ev.expect("Couldn't get any user input");
 
-				match tui.handle_event(&ev) {
+				match docs[active].tui.handle_event(&ev) {
 					None => needs_redraw = false,
-					Some(action) => match action {
-						InputAction::Redraw => (),
-						InputAction::QuitApp => return Ok(()),
-						InputAction::JumpingToPage(page) => {
-							to_renderer.send(RenderNotif::JumpToPage(page))?;
-							to_converter.send(ConverterMsg::GoToPage(page))?;
+					Some(action) => handle_action!(action)
+				}
+			},
+			Some(request) = from_remote.next() => {
+				#[cfg(not(unix))]
+				match request {}
+				#[cfg(unix)]
+				{
+					let RemoteRequest { command, reply } = request;
+					let doc = &mut docs[active];
+					let action = match command {
+						RemoteCommand::GoTo(page) => doc.tui.go_to_page(page).map(Some),
+						RemoteCommand::Search(term) => doc.tui.search(&term).map(Some),
+						RemoteCommand::Reload => {
+							doc.to_renderer.send(RenderNotif::Reload)?;
+							Ok(None)
 						},
-						InputAction::Search(term) => to_renderer.send(RenderNotif::Search(term))?,
-						InputAction::Invert => to_renderer.send(RenderNotif::Invert)?,
-						InputAction::Rotate => to_renderer.send(RenderNotif::Rotate)?,
-						InputAction::Fullscreen => fullscreen = !fullscreen,
-						InputAction::SwitchRenderZoom(f_or_f) => {
-							to_renderer.send(RenderNotif::SwitchFitOrFill(f_or_f)).unwrap();
-						}
+						RemoteCommand::SyncTex { file, line } => {
+							doc.to_renderer.send(RenderNotif::ForwardSearch { file, line })?;
+							Ok(None)
+						},
+						RemoteCommand::Rotate => Ok(Some(InputAction::Rotate)),
+						RemoteCommand::Invert => Ok(Some(InputAction::Invert)),
+						RemoteCommand::Quit => Ok(Some(InputAction::QuitApp)),
+						RemoteCommand::Status => Ok(None)
+					};
+
+					// Quitting doesn't come back here afterwards, so it has to be answered first
+					if matches!(action, Ok(Some(InputAction::QuitApp))) {
+						_ = reply.send(RemoteResponse::new(Ok(()), doc.tui.status()));
 					}
+					let result = match action {
+						Ok(Some(action)) => {
+							handle_action!(action);
+							Ok(())
+						},
+						Ok(None) => Ok(()),
+						Err(e) => Err(e)
+					};
+					// If whoever sent it already hung up, there's nobody to tell
+					_ = reply.send(RemoteResponse::new(result, docs[active].tui.status()));
 				}
 			},
-			Some(renderer_msg) = tui_rx.next() => {
-				match renderer_msg {
-					Ok(render_info) => match render_info {
-						RenderInfo::NumPages(num) => {
-							tui.set_n_pages(num);
-							to_converter.send(ConverterMsg::NumPages(num))?;
-						},
-						RenderInfo::Page(info) => {
-							tui.got_num_results_on_page(info.page_num, info.result_rects.len());
-							to_converter.send(ConverterMsg::AddImg(info))?;
+			Some((id, renderer_msg)) = from_renderers.next() => {
+				let mut asked_for_password = false;
+				// If it's not there, it's for a document that was just closed
+				if let Some(doc) = docs.iter_mut().find(|doc| doc.id == id) {
+					let tui = &mut doc.tui;
+					match renderer_msg {
+						Ok(render_info) => match render_info {
+							RenderInfo::NumPages(num) => {
+								tui.set_n_pages(num);
+								doc.to_converter.send(ConverterMsg::NumPages(num))?;
+								if let Some(ref mut budget) = doc.budget {
+									budget.clear();
+								}
+							},
+							RenderInfo::Page(mut info) => {
+								tui.got_num_results_on_page(info.page_num, info.result_rects.len());
+								// the converter doesn't care about the links, so we can just take them
+								tui.got_page_details(
+									info.page_num,
+									mem::take(&mut info.links),
+									info.result_rects.clone(),
+									info.transform
+								);
+								let page_num = info.page_num;
+								if let Some(ref mut budget) = doc.budget {
+									budget.add(page_num, info.img_data.pixels.len());
+								}
+								doc.rebalance_pages(&mut kitty_evicted)?;
+								// If it's too far away to fit, there's no point in converting it
+								if !doc.budget.as_ref().is_some_and(|b| b.is_evicted(page_num)) {
+									doc.to_converter.send(ConverterMsg::AddImg(*info))?;
+								}
+							},
+							RenderInfo::Outline(outline) => tui.set_outline(outline),
+							RenderInfo::Annotations(annotations) => tui.set_annotations(annotations),
+							RenderInfo::DocInfo(info) => tui.got_doc_info(*info),
+							RenderInfo::PageLabels(labels) => tui.set_page_labels(labels),
+							RenderInfo::Thumbnail { page_num, img_data } => doc
+								.to_converter
+								.send(ConverterMsg::AddThumbnail { page_num, img_data })?,
+							RenderInfo::PageText { page_num, words } =>
+								if let Some(selection) = tui.got_page_text(page_num, words) {
+									doc.to_renderer.send(RenderNotif::Select(Some(selection)))?;
+								},
+							RenderInfo::NeedsPassword { wrong } => {
+								tui.ask_for_password(wrong);
+								// The password is asked for in the bottom bar, so that needs to be
+								// visible (and if this isn't the document that's shown, its tab
+								// says that it's waiting for it)
+								fullscreen = false;
+								asked_for_password = true;
+							},
+							RenderInfo::Reloaded => tui.set_msg(MessageSetting::Some(BottomMessage::Reloaded)),
+							RenderInfo::SearchResults { page_num, num_results } =>
+								tui.got_num_results_on_page(page_num, num_results),
+							RenderInfo::SyncTexTarget { page, rect } => {
+								tui.show_rect(page, rect);
+								doc.to_converter.send(ConverterMsg::GoToPage(page))?;
+							},
+							RenderInfo::SyncTexSource { file, line } => {
+								let source = format!("{}:{line}", file.display());
+								let opened = match synctex_editor {
+									Some(editor) => open_in_editor(editor, &file, line).map_err(|e| {
+										format!("Couldn't open {source} with `{editor}`: {e}")
+									}),
+									None => Err(format!(
+										"That's from {source} (set `synctex-editor` to open it in your editor)"
+									))
+								};
+								if let Err(e) = opened {
+									tui.set_msg(MessageSetting::Some(BottomMessage::Error(e)));
+								}
+							},
 						},
-						RenderInfo::Reloaded => tui.set_msg(MessageSetting::Some(BottomMessage::Reloaded)),
-						RenderInfo::SearchResults { page_num, num_results } =>
-							tui.got_num_results_on_page(page_num, num_results),
-					},
-					Err(e) => tui.show_error(e),
+						Err(e) => tui.show_error(e),
+					}
 				}
+				needs_redraw = docs[active].id == id || asked_for_password;
 			}
-			Some(img_res) = from_converter.next() => {
-				match img_res {
-					Ok(ConvertedPage { page, num, num_results }) => {
-						tui.page_ready(page, num, num_results);
-						if num == tui.page {
-							needs_redraw = true;
-						}
-					},
-					Err(e) => tui.show_error(e),
+			Some((id, img_res)) = from_converters.next() => {
+				if let Some(doc) = docs.iter_mut().find(|doc| doc.id == id) {
+					match img_res {
+						// It was evicted while it was being converted
+						Ok(ConvertedPage { num, thumbnail: false, .. })
+							if doc.budget.as_ref().is_some_and(|b| b.is_evicted(num)) => (),
+						Ok(ConvertedPage { page, num, num_results, thumbnail: false }) =>
+							doc.tui.page_ready(page, num, num_results),
+						Ok(ConvertedPage { page, num, thumbnail: true, .. }) =>
+							doc.tui.thumbnail_ready(page, num),
+						Err(e) => doc.tui.show_error(e),
+					}
 				}
+				needs_redraw = docs[active].id == id;
 			},
 		};
 
-		let new_area = Tui::main_layout(&term.get_frame(), fullscreen);
-		if new_area != main_area {
-			main_area = new_area;
-			to_renderer.send(RenderNotif::Area(main_area.page_area))?;
+		if let Some(ids) = closed_kitty_ids {
+			// Nothing's going to be shown with these anymore once they're deleted, so the next
+			// document that's opened can have them
+			kitty_ids.give_back(&ids);
+			// If this fails, the images just stick around in the terminal's memory until it
+			// decides to get rid of them, so it's not worth bothering the user about
+			let deleted = run_action(
+				Action::Delete(DeleteConfig {
+					effect: ClearOrDelete::Delete,
+					which: WhichToDelete::IdRange(ids)
+				}),
+				&mut ev_stream
+			)
+			.await;
+			if let Err(e) = deleted {
+				log::warn!("Couldn't delete a closed document's images: {e}");
+			}
+		}
+
+		let doc = &mut docs[active];
+		let new_area = doc.tui.main_layout(&term.get_frame(), fullscreen);
+		if new_area != doc.area {
+			doc.area = new_area;
+			doc.to_renderer
+				.send(RenderNotif::Area(doc.area.page_area))?;
 			needs_redraw = true;
 		}
 
+		// The text view only needs the text of the page it's showing, so we ask for it whenever it
+		// moves to a page that we don't have the text of yet
+		if let Some(page) = doc.tui.text_to_extract() {
+			doc.to_renderer.send(RenderNotif::ExtractText(page))?;
+		}
+
 		if needs_redraw {
+			let names = docs
+				.iter()
+				.map(|doc| doc.tui.name().to_owned())
+				.collect::<Vec<_>>();
+			let need_password = docs
+				.iter()
+				.map(|doc| doc.tui.needs_password())
+				.collect::<Vec<_>>();
+			let tabs = Tabs {
+				names: &names,
+				need_password: &need_password,
+				active
+			};
+			let doc = &mut docs[active];
+
 			let mut to_display = KittyDisplay::NoChange;
 			term.draw(|f| {
-				to_display = tui.render(f, &main_area, font_size);
+				to_display = doc.tui.render(f, &doc.area, font_size, tabs);
 			})?;
 
 			let maybe_err =
@@ -498,20 +1313,45 @@ async fn enter_redraw_loop(
 					DisplayErrSource::Transmission(TransmitError::Terminal(
 						TerminalError::NoEntity(_)
 					)) => (),
-					_ => tui.set_msg(MessageSetting::Some(BottomMessage::Error(format!(
-						"{user_facing_err}: {source}"
-					))))
+					_ => doc
+						.tui
+						.set_msg(MessageSetting::Some(BottomMessage::Error(format!(
+							"{user_facing_err}: {source}"
+						))))
 				}
 
 				for page_num in failed_pages {
-					tui.page_failed_display(page_num);
+					doc.tui.page_failed_display(page_num);
 					// So that they get re-rendered and sent over again
-					to_renderer.send(RenderNotif::PageNeedsReRender(page_num))?;
+					if doc.tui.showing_thumbnails() {
+						doc.to_renderer
+							.send(RenderNotif::ThumbnailNeedsReRender(page_num))?;
+					} else {
+						doc.to_renderer
+							.send(RenderNotif::PageNeedsReRender(page_num))?;
+					}
 				}
 			}
 
 			execute!(stdout().lock(), EndSynchronizedUpdate)?;
 		}
+
+		// Moving to another page can change which pages fit in the budget, and this is done after
+		// drawing so that every page that ended up on screen is kept
+		docs[active].rebalance_pages(&mut kitty_evicted)?;
+		for ids in kitty_evicted {
+			let deleted = run_action(
+				Action::Delete(DeleteConfig {
+					effect: ClearOrDelete::Delete,
+					which: WhichToDelete::IdRange(ids)
+				}),
+				&mut ev_stream
+			)
+			.await;
+			if let Err(e) = deleted {
+				log::warn!("Couldn't delete an evicted page's image: {e}");
+			}
+		}
 	}
 }
 
@@ -524,6 +1364,10 @@ fn on_notify_ev(
 	let last_event: Mutex<Result<(), RenderError>> = Mutex::new(Ok(()));
 	let last_event = Arc::new(last_event);
 
+	// The document's synctex file is rewritten whenever it's recompiled (sometimes a little after
+	// the document itself), so we reload when that changes too
+	let synctex_names = synctex_paths(Path::new(&file_name)).map(PathBuf::into_os_string);
+
 	let debouncer = EventDebouncer::new(debounce_delay, {
 		let last_event = last_event.clone();
 		move |()| {
@@ -548,20 +1392,21 @@ fn on_notify_ev(
 			// process know that too? Or should that be
 			Ok(ev) => {
 				// We only watch the parent directory (see the comment above `watcher.watch` in `fn
-				// main`) so we need to filter out events to only ones that pertain to the single file
-				// we care about
-				if !ev
-					.paths
-					.iter()
-					.any(|path| path.file_name().is_some_and(|f| f == file_name))
-				{
+				// main`) so we need to filter out events to only ones that pertain to the files we
+				// care about
+				let mut names = ev.paths.iter().filter_map(|path| path.file_name());
+				let about_doc = names.clone().any(|f| f == file_name);
+				if !about_doc && !names.any(|f| synctex_names.iter().any(|s| s == f)) {
 					return;
 				}
 
 				match ev.kind {
 					EventKind::Access(_) => return,
-					EventKind::Remove(_) => Err(RenderError::Converting("File was deleted".into())),
-					EventKind::Other
+					// TeX removes the old synctex file before writing the new one, so that's fine
+					EventKind::Remove(_) if about_doc =>
+						Err(RenderError::Converting("File was deleted".into())),
+					EventKind::Remove(_)
+					| EventKind::Other
 					| EventKind::Any
 					| EventKind::Create(_)
 					| EventKind::Modify(_) => Ok(())
@@ -573,12 +1418,179 @@ fn on_notify_ev(
 	}
 }
 
+#[cfg(target_os = "macos")]
+const DEFAULT_LINK_OPENER: &str = "open";
+#[cfg(windows)]
+const DEFAULT_LINK_OPENER: &str = "explorer";
+#[cfg(not(any(target_os = "macos", windows)))]
+const DEFAULT_LINK_OPENER: &str = "xdg-open";
+
+fn open_link(opener: &str, uri: &str) -> std::io::Result<()> {
+	let mut args = opener.split_whitespace();
+	let program = args
+		.next()
+		.ok_or_else(|| std::io::Error::other("the link opener command is empty"))?;
+
+	spawn_detached(Command::new(program).args(args).arg(uri))
+}
+
+// These are the same placeholders that other viewers use, so that people can copy their editor
+// command over as-is
+#[expect(clippy::literal_string_with_formatting_args)]
+fn open_in_editor(editor: &str, file: &Path, line: u32) -> std::io::Result<()> {
+	let (file, line) = (file.to_string_lossy(), line.to_string());
+	let mut args = editor
+		.split_whitespace()
+		.map(|arg| arg.replace("%{input}", &file).replace("%{line}", &line));
+	let program = args
+		.next()
+		.ok_or_else(|| std::io::Error::other("the synctex editor command is empty"))?;
+
+	spawn_detached(Command::new(program).args(args))
+}
+
+fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+	// We don't want the program to write all over our screen, so it gets no stdio at all
+	let mut child = command
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.spawn()?;
+
+	// And we don't want to block on it, but we do need to reap it once it's done so that it
+	// doesn't stick around as a zombie
+	std::thread::spawn(move || child.wait());
+	Ok(())
+}
+
+// This uses OSC 52, which most terminals support (sometimes behind a setting), so that copying
+// works even over ssh and without needing to shell out to some clipboard program
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+	let mut stdout = stdout().lock();
+	write!(stdout, "\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text))?;
+	stdout.flush()
+}
+
 fn parse_color_to_i32(cs: &str) -> Result<i32, csscolorparser::ParseColorError> {
 	let color = csscolorparser::parse(cs)?;
 	let [r, g, b, _] = color.to_rgba8();
 	Ok(i32::from_be_bytes([0, r, g, b]))
 }
 
+// `which` is just for the error message, to say which of the colors couldn't be parsed
+fn parse_color_or(color: Option<&str>, which: &str, default: i32) -> Result<i32, WrappedErr> {
+	color.map_or(Ok(default), |color| {
+		parse_color_to_i32(color).map_err(|e| {
+			WrappedErr(
+				format!(
+					"Couldn't parse {which} color {color:?}: {e} - is it formatted like a CSS color?"
+				)
+				.into()
+			)
+		})
+	})
+}
+
+fn read_password(password_file: Option<&Path>) -> Result<Option<String>, WrappedErr> {
+	let Some(password_file) = password_file else {
+		return Ok(std::env::var("TDF_PASSWORD").ok());
+	};
+
+	let contents = std::fs::read_to_string(password_file).map_err(|e| {
+		WrappedErr(
+			format!(
+				"Couldn't read password file {}: {e}",
+				password_file.display()
+			)
+			.into()
+		)
+	})?;
+	// Files pretty much always end with a newline, but passwords basically never do
+	Ok(Some(contents.trim_end_matches(['\n', '\r']).to_owned()))
+}
+
+#[cfg(not(unix))]
+fn send_remote_command() -> Result<(), WrappedErr> {
+	Err(WrappedErr(
+		"--remote is only supported on unix-like systems, since it needs a unix socket".into()
+	))
+}
+
+#[cfg(unix)]
+fn send_remote_command() -> Result<(), WrappedErr> {
+	let flags = remote_flags::Remote::from_vec(std::env::args_os().skip(2).collect())
+		.unwrap_or_else(|e| e.exit());
+
+	if flags.command.is_empty() {
+		return Err(WrappedErr(
+			"Please specify the command to send, e.g. `tdf --remote /tmp/tdf.sock goto 12`".into()
+		));
+	}
+
+	let response = remote::send(&flags.socket, &flags.command.join(" ")).map_err(|e| {
+		WrappedErr(
+			format!(
+				"Couldn't talk to the viewer at {}: {e}",
+				flags.socket.display()
+			)
+			.into()
+		)
+	})?;
+	if response.is_empty() {
+		return Err(WrappedErr("The viewer hung up without answering".into()));
+	}
+	println!("{response}");
+	Ok(())
+}
+
+fn export_pages() -> Result<(), WrappedErr> {
+	let flags = export_flags::Export::from_vec(std::env::args_os().skip(2).collect())
+		.unwrap_or_else(|e| e.exit());
+
+	let size = match (flags.width, flags.height, flags.dpi) {
+		(_, _, Some(dpi)) if !dpi.is_finite() || dpi <= 0. =>
+			return Err(WrappedErr(
+				format!("--dpi must be a number above 0, not {dpi}").into()
+			)),
+		(None, None, dpi) => dpi.map_or_else(ExportSize::default, ExportSize::Dpi),
+		(width, height, None) => ExportSize::Fit { width, height },
+		(_, _, Some(_)) =>
+			return Err(WrappedErr(
+				"--dpi cannot be combined with --width or --height".into()
+			)),
+	};
+
+	let search = flags
+		.search
+		.map(|search| {
+			SearchQuery::parse(&search)
+				.map_err(|e| WrappedErr(format!("Couldn't parse search {search:?}: {e}").into()))
+		})
+		.transpose()?;
+
+	let options = ExportOptions {
+		pages: flags.pages,
+		size,
+		format: flags.format.unwrap_or(ExportFormat::Png),
+		out_dir: flags.out.unwrap_or_else(|| PathBuf::from(".")),
+		invert: flags.invert,
+		annotations: !flags.hide_annotations,
+		rotate: flags.rotate.unwrap_or(RotateDirection::Deg0),
+		black: parse_color_or(flags.black_color.as_deref(), "black", MUPDF_BLACK)?,
+		white: parse_color_or(flags.white_color.as_deref(), "white", MUPDF_WHITE)?,
+		search,
+		password: read_password(flags.password_file.as_deref())?
+	};
+
+	let files =
+		export::export(&flags.file, &options).map_err(|e| WrappedErr(e.to_string().into()))?;
+	// One per line, so that scripts can easily pick up what was written
+	for file in files {
+		println!("{}", file.display());
+	}
+	Ok(())
+}
+
 fn query_terminal_colors() -> (i32, i32) {
 	if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
 		return (MUPDF_BLACK, MUPDF_WHITE);
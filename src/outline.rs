@@ -0,0 +1,284 @@
+use ratatui::{
+	Frame,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::{Line, Span},
+	widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph}
+};
+
+/// A single entry of the document's outline (a.k.a. its table of contents), as read from mupdf
+#[derive(Debug, Clone)]
+pub struct OutlineEntry {
+	pub title: String,
+	// None if this entry doesn't point to a page within this document
+	pub page: Option<usize>,
+	pub children: Vec<Self>
+}
+
+// We store the tree flattened (in the order that it would be displayed if everything was
+// expanded) since that makes it a lot easier to move the selection up and down
+struct OutlineItem {
+	title: String,
+	page: Option<usize>,
+	depth: usize,
+	has_children: bool,
+	expanded: bool
+}
+
+#[derive(Default)]
+pub struct Outline {
+	items: Vec<OutlineItem>,
+	// Index into `items`. This should always point to a visible item (if there are any items)
+	selected: usize,
+	// Only used to let ratatui keep track of the scroll offset of the list between renders
+	list_state: ListState
+}
+
+impl Outline {
+	#[must_use]
+	pub fn new(entries: Vec<OutlineEntry>) -> Self {
+		fn flatten(entries: Vec<OutlineEntry>, depth: usize, items: &mut Vec<OutlineItem>) {
+			for entry in entries {
+				items.push(OutlineItem {
+					title: entry.title,
+					page: entry.page,
+					depth,
+					has_children: !entry.children.is_empty(),
+					expanded: false
+				});
+				flatten(entry.children, depth + 1, items);
+			}
+		}
+
+		let mut items = Vec::new();
+		flatten(entries, 0, &mut items);
+
+		Self {
+			items,
+			selected: 0,
+			list_state: ListState::default()
+		}
+	}
+
+	/// Returns the indices (into `self.items`) of every item that isn't hidden inside a collapsed
+	/// parent
+	fn visible(&self) -> impl Iterator<Item = usize> + '_ {
+		// If this is Some, we're inside a collapsed item at the contained depth, and everything
+		// deeper than that should be skipped
+		let mut collapsed_at = None;
+		self.items
+			.iter()
+			.enumerate()
+			.filter_map(move |(idx, item)| {
+				if let Some(depth) = collapsed_at {
+					if item.depth > depth {
+						return None;
+					}
+					collapsed_at = None;
+				}
+
+				if item.has_children && !item.expanded {
+					collapsed_at = Some(item.depth);
+				}
+
+				Some(idx)
+			})
+	}
+
+	pub fn select_next(&mut self) {
+		let next = self.visible().find(|idx| *idx > self.selected);
+		if let Some(next) = next {
+			self.selected = next;
+		}
+	}
+
+	pub fn select_prev(&mut self) {
+		let prev = self.visible().take_while(|idx| *idx < self.selected).last();
+		if let Some(prev) = prev {
+			self.selected = prev;
+		}
+	}
+
+	/// Collapses the selected item if it's expanded, or else moves the selection to its parent
+	pub fn collapse(&mut self) {
+		let Some(item) = self.items.get_mut(self.selected) else {
+			return;
+		};
+
+		if item.has_children && item.expanded {
+			item.expanded = false;
+		} else {
+			let depth = item.depth;
+			if let Some(parent) = self.items[..self.selected]
+				.iter()
+				.rposition(|i| i.depth < depth)
+			{
+				self.selected = parent;
+			}
+		}
+	}
+
+	/// Expands the selected item if it's collapsed, or else moves the selection to its first child
+	pub fn expand(&mut self) {
+		let Some(item) = self.items.get_mut(self.selected) else {
+			return;
+		};
+
+		if item.has_children {
+			if item.expanded {
+				// the first child is always directly after its parent
+				self.selected += 1;
+			} else {
+				item.expanded = true;
+			}
+		}
+	}
+
+	#[must_use]
+	pub fn selected_page(&self) -> Option<usize> {
+		self.items.get(self.selected).and_then(|item| item.page)
+	}
+
+	/// Selects the last visible item that points to `page` or any page before it, so that opening
+	/// the outline shows you where you currently are
+	pub fn select_page(&mut self, page: usize) {
+		let last_before = self
+			.visible()
+			.filter(|idx| self.items[*idx].page.is_some_and(|p| p <= page))
+			.last();
+		if let Some(idx) = last_before {
+			self.selected = idx;
+		}
+	}
+
+	pub fn render(&mut self, frame: &mut Frame<'_>, area: Rect) {
+		let block = Block::new()
+			.title(Span::styled("Outline", Style::new().fg(Color::Cyan)))
+			.padding(Padding::right(1))
+			.borders(Borders::RIGHT);
+		let inner = block.inner(area);
+		frame.render_widget(block, area);
+
+		if self.items.is_empty() {
+			frame.render_widget(
+				Paragraph::new("This document has no outline").style(Color::DarkGray),
+				inner
+			);
+			return;
+		}
+
+		let width = usize::from(inner.width);
+		let mut selected_pos = 0;
+		let list_items = self
+			.visible()
+			.enumerate()
+			.map(|(pos, idx)| {
+				if idx == self.selected {
+					selected_pos = pos;
+				}
+
+				let item = &self.items[idx];
+				let marker = match (item.has_children, item.expanded) {
+					(false, _) => "  ",
+					(true, false) => "▸ ",
+					(true, true) => "▾ "
+				};
+				let page_str = item.page.map(|p| (p + 1).to_string()).unwrap_or_default();
+
+				let mut title = "  ".repeat(item.depth);
+				title.push_str(marker);
+				// leave at least one space between the title and the page number
+				let title_space = width.saturating_sub(page_str.len() + 1);
+				let prefix_len = title.chars().count();
+				title.extend(
+					item.title
+						.chars()
+						.take(title_space.saturating_sub(prefix_len))
+				);
+
+				let padding =
+					" ".repeat(width.saturating_sub(title.chars().count() + page_str.len()));
+
+				ListItem::new(Line::from(vec![
+					Span::raw(title),
+					Span::raw(padding),
+					Span::styled(page_str, Style::new().fg(Color::Cyan)),
+				]))
+			})
+			.collect::<Vec<_>>();
+
+		self.list_state.select(Some(selected_pos));
+
+		let list =
+			List::new(list_items).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, inner, &mut self.list_state);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entry(title: &str, page: usize, children: Vec<OutlineEntry>) -> OutlineEntry {
+		OutlineEntry {
+			title: title.to_owned(),
+			page: Some(page),
+			children
+		}
+	}
+
+	fn example() -> Outline {
+		Outline::new(vec![
+			entry("1", 0, vec![
+				entry("1.1", 1, vec![]),
+				entry("1.2", 3, vec![]),
+			]),
+			entry("2", 5, vec![entry("2.1", 6, vec![entry(
+				"2.1.1",
+				7,
+				vec![]
+			)])]),
+			entry("3", 9, vec![]),
+		])
+	}
+
+	#[test]
+	fn navigation_skips_collapsed() {
+		let mut outline = example();
+		assert_eq!(outline.visible().collect::<Vec<_>>(), vec![0, 3, 6]);
+
+		outline.select_next();
+		assert_eq!(outline.selected_page(), Some(5));
+
+		outline.expand();
+		assert_eq!(outline.visible().collect::<Vec<_>>(), vec![0, 3, 4, 6]);
+
+		// expanding an already-expanded item moves into it
+		outline.expand();
+		assert_eq!(outline.selected_page(), Some(6));
+
+		// and collapsing a collapsed item moves back out to the parent
+		outline.collapse();
+		assert_eq!(outline.selected_page(), Some(5));
+
+		outline.collapse();
+		outline.select_next();
+		assert_eq!(outline.selected_page(), Some(9));
+
+		outline.select_prev();
+		outline.select_prev();
+		assert_eq!(outline.selected_page(), Some(0));
+	}
+
+	#[test]
+	fn select_page_picks_last_visible_before() {
+		let mut outline = example();
+
+		outline.select_page(7);
+		assert_eq!(outline.selected_page(), Some(5));
+
+		outline.items[0].expanded = true;
+		outline.select_page(4);
+		assert_eq!(outline.selected_page(), Some(3));
+	}
+}
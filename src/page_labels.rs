@@ -0,0 +1,266 @@
+/// How the number part of a page label is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelStyle {
+	Decimal,
+	UpperRoman,
+	LowerRoman,
+	// A to Z, then AA to ZZ, then AAA to ZZZ, and so on
+	UpperLetters,
+	LowerLetters
+}
+
+impl LabelStyle {
+	/// The style that's named `name` in a PDF's page labels (its `/S` entry)
+	#[must_use]
+	pub fn from_name(name: &[u8]) -> Option<Self> {
+		Some(match name {
+			b"D" => Self::Decimal,
+			b"R" => Self::UpperRoman,
+			b"r" => Self::LowerRoman,
+			b"A" => Self::UpperLetters,
+			b"a" => Self::LowerLetters,
+			_ => return None
+		})
+	}
+
+	fn format(self, num: u32) -> String {
+		match self {
+			Self::Decimal => num.to_string(),
+			Self::UpperRoman => to_roman(num),
+			Self::LowerRoman => to_roman(num).to_lowercase(),
+			Self::UpperLetters => to_letters(num),
+			Self::LowerLetters => to_letters(num).to_lowercase()
+		}
+	}
+
+	// Case doesn't matter here, since whatever this finds is checked against the real label
+	fn parse(self, s: &str) -> Option<u32> {
+		match self {
+			Self::Decimal => s.parse().ok(),
+			Self::UpperRoman | Self::LowerRoman => from_roman(s),
+			Self::UpperLetters | Self::LowerLetters => from_letters(s)
+		}
+	}
+}
+
+/// A run of pages that are all labelled the same way, starting at `first_page` and going until
+/// the next range starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelRange {
+	pub first_page: usize,
+	// Without a style, every page in the range is labelled with just the prefix
+	pub style: Option<LabelStyle>,
+	pub prefix: String,
+	// The number that the first page in the range gets
+	pub start: u32
+}
+
+/// The labels that a document gives its pages (like `iv` for the fourth page of the preface),
+/// which are what's printed on them and so aren't always the same as their position in the
+/// document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageLabels {
+	// Sorted by their first page
+	ranges: Vec<LabelRange>,
+	n_pages: usize
+}
+
+impl PageLabels {
+	#[must_use]
+	pub fn new(mut ranges: Vec<LabelRange>, n_pages: usize) -> Self {
+		ranges.sort_by_key(|range| range.first_page);
+		Self { ranges, n_pages }
+	}
+
+	/// The label of `page`, if the document labels it
+	#[must_use]
+	pub fn label(&self, page: usize) -> Option<String> {
+		let range = self
+			.ranges
+			.iter()
+			.take_while(|range| range.first_page <= page)
+			.last()?;
+		let offset = u32::try_from(page - range.first_page).ok()?;
+		let num = range
+			.style
+			.map(|style| style.format(range.start.saturating_add(offset)))
+			.unwrap_or_default();
+		Some(format!("{}{num}", range.prefix))
+	}
+
+	/// The page that's labelled `label`. If no label matches it exactly, the first one that only
+	/// differs by case is used, so that (for example) `xiv` can be typed for a page labelled
+	/// `XIV`.
+	#[must_use]
+	pub fn find(&self, label: &str) -> Option<usize> {
+		let mut case_insensitive = None;
+		for (idx, range) in self.ranges.iter().enumerate() {
+			let end = self
+				.ranges
+				.get(idx + 1)
+				.map_or(self.n_pages, |next| next.first_page)
+				.min(self.n_pages);
+
+			let Some(num) = label
+				.get(..range.prefix.len())
+				.filter(|prefix| prefix.eq_ignore_ascii_case(&range.prefix))
+				.and_then(|_| label.get(range.prefix.len()..))
+			else {
+				continue;
+			};
+
+			let page = match range.style {
+				Some(style) => style
+					.parse(num)
+					.and_then(|num| num.checked_sub(range.start))
+					.and_then(|offset| range.first_page.checked_add(offset as usize)),
+				None => Some(range.first_page)
+			};
+			let Some(page) = page.filter(|page| *page < end) else {
+				continue;
+			};
+
+			match self.label(page) {
+				Some(found) if found == label => return Some(page),
+				Some(found) if case_insensitive.is_none() && found.eq_ignore_ascii_case(label) =>
+					case_insensitive = Some(page),
+				_ => ()
+			}
+		}
+		case_insensitive
+	}
+}
+
+const ROMAN: [(u32, &str); 13] = [
+	(1000, "M"),
+	(900, "CM"),
+	(500, "D"),
+	(400, "CD"),
+	(100, "C"),
+	(90, "XC"),
+	(50, "L"),
+	(40, "XL"),
+	(10, "X"),
+	(9, "IX"),
+	(5, "V"),
+	(4, "IV"),
+	(1, "I")
+];
+
+fn to_roman(mut num: u32) -> String {
+	let mut roman = String::new();
+	for (value, numeral) in ROMAN {
+		while num >= value {
+			roman.push_str(numeral);
+			num -= value;
+		}
+	}
+	roman
+}
+
+// This accepts some numerals that aren't written the usual way (like `IIII`), but that's fine
+// since the label it gives is checked afterwards
+fn from_roman(s: &str) -> Option<u32> {
+	let mut rest = s;
+	let mut num = 0_u32;
+	for (value, numeral) in ROMAN {
+		while let Some(after) = rest
+			.get(..numeral.len())
+			.filter(|start| start.eq_ignore_ascii_case(numeral))
+			.and_then(|_| rest.get(numeral.len()..))
+		{
+			num = num.checked_add(value)?;
+			rest = after;
+		}
+	}
+	(rest.is_empty() && num > 0).then_some(num)
+}
+
+fn to_letters(num: u32) -> String {
+	let Some(idx) = num.checked_sub(1) else {
+		return String::new();
+	};
+	let letter = char::from(b'A' + (idx % 26) as u8);
+	std::iter::repeat_n(letter, (idx / 26) as usize + 1).collect()
+}
+
+fn from_letters(s: &str) -> Option<u32> {
+	let first = s.chars().next()?.to_ascii_uppercase();
+	if !first.is_ascii_uppercase() || !s.chars().all(|c| c.eq_ignore_ascii_case(&first)) {
+		return None;
+	}
+	let repeats = u32::try_from(s.len()).ok()? - 1;
+	repeats
+		.checked_mul(26)?
+		.checked_add(u32::from(first) - u32::from('A') + 1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Like most books: a cover, some front matter in roman numerals, the main text, and then
+	// appendices that start over with a prefix
+	fn book() -> PageLabels {
+		PageLabels::new(
+			vec![
+				LabelRange {
+					first_page: 2,
+					style: Some(LabelStyle::LowerRoman),
+					prefix: String::new(),
+					start: 1
+				},
+				LabelRange {
+					first_page: 0,
+					style: None,
+					prefix: "Cover".into(),
+					start: 1
+				},
+				LabelRange {
+					first_page: 20,
+					style: Some(LabelStyle::Decimal),
+					prefix: String::new(),
+					start: 1
+				},
+				LabelRange {
+					first_page: 300,
+					style: Some(LabelStyle::Decimal),
+					prefix: "A-".into(),
+					start: 1
+				},
+			],
+			320
+		)
+	}
+
+	#[test]
+	fn labels_pages() {
+		let labels = book();
+		assert_eq!(labels.label(0).as_deref(), Some("Cover"));
+		assert_eq!(labels.label(1).as_deref(), Some("Cover"));
+		assert_eq!(labels.label(15).as_deref(), Some("xiv"));
+		assert_eq!(labels.label(20).as_deref(), Some("1"));
+		assert_eq!(labels.label(302).as_deref(), Some("A-3"));
+		assert_eq!(PageLabels::default().label(3), None);
+
+		assert_eq!(to_roman(1994), "MCMXCIV");
+		assert_eq!(LabelStyle::LowerLetters.format(28), "bb");
+	}
+
+	#[test]
+	fn finds_pages_by_label() {
+		let labels = book();
+		assert_eq!(labels.find("xiv"), Some(15));
+		assert_eq!(labels.find("XIV"), Some(15));
+		assert_eq!(labels.find("12"), Some(31));
+		assert_eq!(labels.find("A-3"), Some(302));
+		assert_eq!(labels.find("a-3"), Some(302));
+		assert_eq!(labels.find("Cover"), Some(0));
+		// The roman numerals end at page 19 (xviii), so this would be in the main text
+		assert_eq!(labels.find("xx"), None);
+		// There are only 20 pages in the appendix
+		assert_eq!(labels.find("A-21"), None);
+		assert_eq!(labels.find("iiii"), None);
+		assert_eq!(labels.find("nope"), None);
+	}
+}
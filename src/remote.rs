@@ -0,0 +1,242 @@
+use std::{
+	io::{self, BufRead as _, BufReader, Write as _},
+	os::unix::net::UnixStream as StdUnixStream,
+	path::{Path, PathBuf},
+	str::FromStr
+};
+
+use flume::Sender;
+use serde::Serialize;
+use tokio::{
+	io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader as AsyncBufReader},
+	net::{UnixListener, UnixStream}
+};
+
+/// Something that another program (like an editor) asked the viewer to do over its socket. Each
+/// one is sent as a single line, like `goto 12` or `search some words`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteCommand {
+	// 1-indexed, like the page numbers that are shown
+	GoTo(usize),
+	Search(String),
+	// A forward search, to show where this line of a TeX source file ended up
+	SyncTex { file: PathBuf, line: u32 },
+	Reload,
+	Rotate,
+	Invert,
+	Quit,
+	// Doesn't do anything, but still gets answered, so it can be used to check where the viewer is
+	Status
+}
+
+impl FromStr for RemoteCommand {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		let (name, arg) = s.split_once(' ').unwrap_or((s, ""));
+		let arg = arg.trim();
+
+		match (name, arg) {
+			("goto", page) => page
+				.parse()
+				.map(Self::GoTo)
+				.map_err(|e| format!("Invalid page number `{page}`: {e}")),
+			// Searching for nothing clears the search, like it does in the viewer
+			("search", term) => Ok(Self::Search(term.to_owned())),
+			("synctex", source) => {
+				let (file, line) = source
+					.rsplit_once(':')
+					.filter(|(file, _)| !file.is_empty())
+					.ok_or_else(|| {
+						format!("Expected `<file>:<line>` after synctex, not `{source}`")
+					})?;
+				Ok(Self::SyncTex {
+					file: file.into(),
+					line: line
+						.parse()
+						.map_err(|e| format!("Invalid line number `{line}`: {e}"))?
+				})
+			}
+			("reload", "") => Ok(Self::Reload),
+			("rotate", "") => Ok(Self::Rotate),
+			("invert", "") => Ok(Self::Invert),
+			("quit", "") => Ok(Self::Quit),
+			("status", "") => Ok(Self::Status),
+			("reload" | "rotate" | "invert" | "quit" | "status", _) =>
+				Err(format!("`{name}` doesn't take anything after it")),
+			_ => Err(format!(
+				"Unknown command `{name}` (expected goto, search, synctex, reload, rotate, invert, quit, or status)"
+			))
+		}
+	}
+}
+
+/// Where the viewer is at, which is sent back after every command
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct RemoteStatus {
+	// 1-indexed, like the page numbers that are shown
+	pub page: usize,
+	pub pages: usize,
+	// How many search results have been found so far, or None if nothing is being searched for
+	pub results: Option<usize>,
+	// Whether there are still pages that haven't been searched yet, so `results` could go up
+	pub searching: bool
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteResponse {
+	ok: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<String>,
+	#[serde(flatten)]
+	status: Option<RemoteStatus>
+}
+
+impl RemoteResponse {
+	#[must_use]
+	pub fn new(result: Result<(), String>, status: RemoteStatus) -> Self {
+		Self {
+			ok: result.is_ok(),
+			error: result.err(),
+			status: Some(status)
+		}
+	}
+
+	fn failed(error: String) -> Self {
+		Self {
+			ok: false,
+			error: Some(error),
+			status: None
+		}
+	}
+}
+
+/// A command that came in over the socket, along with where to send the answer to it
+pub struct RemoteRequest {
+	pub command: RemoteCommand,
+	pub reply: Sender<RemoteResponse>
+}
+
+/// Starts listening at `path`. If there's already a socket there that nothing is listening on
+/// anymore (e.g. because the last viewer that used it crashed), it's replaced.
+pub fn bind(path: &Path) -> io::Result<UnixListener> {
+	match UnixListener::bind(path) {
+		Err(e) if e.kind() == io::ErrorKind::AddrInUse && StdUnixStream::connect(path).is_err() => {
+			std::fs::remove_file(path)?;
+			UnixListener::bind(path)
+		}
+		res => res
+	}
+}
+
+/// Accepts connections until something goes wrong with the socket, sending every command that
+/// comes in to the main loop and writing its response back as a line of JSON
+pub async fn serve(listener: UnixListener, to_main: Sender<RemoteRequest>) {
+	loop {
+		match listener.accept().await {
+			Ok((stream, _)) => {
+				tokio::spawn(handle_connection(stream, to_main.clone()));
+			}
+			// This pretty much only happens if we're out of file descriptors, in which case
+			// trying again right away would just fail again
+			Err(e) => {
+				log::error!("Couldn't accept a remote connection, so no longer listening: {e}");
+				return;
+			}
+		}
+	}
+}
+
+async fn handle_connection(stream: UnixStream, to_main: Sender<RemoteRequest>) -> io::Result<()> {
+	let (read, mut write) = stream.into_split();
+	let mut lines = AsyncBufReader::new(read).lines();
+
+	while let Some(line) = lines.next_line().await? {
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		let response = match line.parse() {
+			Ok(command) => {
+				let (reply, response) = flume::bounded(1);
+				// If either of these fail, the viewer is shutting down, so there's nobody left to
+				// answer
+				if to_main
+					.send_async(RemoteRequest { command, reply })
+					.await
+					.is_err()
+				{
+					return Ok(());
+				}
+				match response.recv_async().await {
+					Ok(response) => response,
+					Err(_) => return Ok(())
+				}
+			}
+			Err(e) => RemoteResponse::failed(e)
+		};
+
+		let mut json = serde_json::to_vec(&response).map_err(io::Error::other)?;
+		json.push(b'\n');
+		write.write_all(&json).await?;
+	}
+
+	Ok(())
+}
+
+/// Sends a single command to the viewer listening at `path` and waits for its response, for
+/// `tdf --remote`
+pub fn send(path: &Path, command: &str) -> io::Result<String> {
+	let mut stream = StdUnixStream::connect(path)?;
+	writeln!(stream, "{command}")?;
+
+	let mut response = String::new();
+	BufReader::new(stream).read_line(&mut response)?;
+	Ok(response.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_commands() {
+		assert_eq!("goto 12".parse(), Ok(RemoteCommand::GoTo(12)));
+		assert_eq!(
+			"search  foo bar ".parse(),
+			Ok(RemoteCommand::Search("foo bar".into()))
+		);
+		assert_eq!("search".parse(), Ok(RemoteCommand::Search(String::new())));
+		assert_eq!(
+			"synctex chapters/intro.tex:42".parse(),
+			Ok(RemoteCommand::SyncTex {
+				file: "chapters/intro.tex".into(),
+				line: 42
+			})
+		);
+		assert_eq!("quit\n".parse(), Ok(RemoteCommand::Quit));
+		"synctex intro.tex".parse::<RemoteCommand>().unwrap_err();
+		"goto twelve".parse::<RemoteCommand>().unwrap_err();
+		"rotate 90".parse::<RemoteCommand>().unwrap_err();
+		"explode".parse::<RemoteCommand>().unwrap_err();
+	}
+
+	#[test]
+	fn serializes_responses() {
+		let status = RemoteStatus {
+			page: 3,
+			pages: 10,
+			results: None,
+			searching: false
+		};
+		assert_eq!(
+			serde_json::to_string(&RemoteResponse::new(Ok(()), status)).unwrap(),
+			r#"{"ok":true,"page":3,"pages":10,"results":null,"searching":false}"#
+		);
+		assert_eq!(
+			serde_json::to_string(&RemoteResponse::failed("nope".into())).unwrap(),
+			r#"{"ok":false,"error":"nope"}"#
+		);
+	}
+}
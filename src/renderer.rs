@@ -1,41 +1,118 @@
-use std::{collections::VecDeque, num::NonZeroUsize, path::Path, thread::sleep, time::Duration};
+use std::{
+	collections::{BTreeSet, VecDeque},
+	num::NonZeroUsize,
+	path::{Path, PathBuf},
+	str::FromStr,
+	thread::sleep,
+	time::Duration
+};
 
 use flume::{Receiver, SendError, Sender, TryRecvError};
 use mupdf::{
-	Colorspace, Document, Matrix, Page, Pixmap, Quad, TextPageFlags, text_page::SearchHitResponse
+	Colorspace, Document, Matrix, MetadataName, Page, Pixmap, Point, Quad, TextPageFlags,
+	pdf::{PdfAnnotationType, PdfDocument, PdfObject, PdfPage},
+	text_page::{SearchHitResponse, TextBlockType}
 };
 use ratatui::layout::Rect;
+use regex::Regex;
+use serde::Deserialize;
 
 use crate::{
-	FitOrFill, PrerenderLimit, ScaledResult, scale_img_for_area, skip::InterleavedAroundWithMax
+	FitOrFill, PrerenderLimit, ScaledResult,
+	annotations::Annotation,
+	cache::{CacheKey, RenderCache},
+	info::{DocInfo, Font, PageSize},
+	outline::OutlineEntry,
+	page_labels::{LabelRange, LabelStyle, PageLabels},
+	scale_img_for_area,
+	search::SearchQuery,
+	selection::{PageSelection, PdfRect, TextWord},
+	skip::InterleavedAroundWithMax,
+	synctex::SyncTex
 };
 
 const KITTY_MAX_W_OR_H: f32 = 10_000.0;
+const NO_SYNCTEX: &str =
+	"There's no SyncTeX data for this document (it needs to be compiled with `-synctex=1`)";
 
 #[derive(Debug)]
 pub enum RenderNotif {
 	Area(Rect),
 	JumpToPage(usize),
 	PageNeedsReRender(usize),
-	Search(String),
+	Search(SearchQuery),
+	// The search result that the user is currently on, as (page, index of the result within that
+	// page), so that it can be highlighted differently from the rest
+	ActiveResult(Option<(usize, usize)>),
+	ExtractText(usize),
+	Select(Option<PageSelection>),
 	SwitchFitOrFill(FitOrFill),
 	Reload,
 	Invert,
-	Rotate
+	Rotate,
+	CycleTrim,
+	ToggleAnnotations,
+	// What the user typed in when asked for the document's password
+	Password(String),
+	// Find where this line of a TeX source file ended up in the document
+	ForwardSearch { file: PathBuf, line: u32 },
+	// Find which line of which TeX source file produced what's at this point on the page
+	InverseSearch { page: usize, point: (f32, f32) },
+	// Gather up everything about the document itself, for the info popup
+	DocInfo,
+	// Start rendering thumbnails to fit in this many cells (width, height) for the overview, or
+	// stop if it's None since the overview was closed
+	Thumbnails(Option<(u16, u16)>),
+	ThumbnailNeedsReRender(usize)
 }
 
 #[derive(Debug)]
 pub enum RenderError {
 	Notify(notify::Error),
 	Doc(mupdf::error::Error),
-	Converting(String)
+	Converting(String),
+	SyncTex(String)
 }
 
 pub enum RenderInfo {
 	NumPages(usize),
-	Page(PageInfo),
-	SearchResults { page_num: usize, num_results: usize },
-	Reloaded
+	// Boxed since it's much bigger than the rest of the variants
+	Page(Box<PageInfo>),
+	SearchResults {
+		page_num: usize,
+		num_results: usize
+	},
+	Outline(Vec<OutlineEntry>),
+	// Every annotation in the document, along with the page it's on, in page order
+	Annotations(Vec<(usize, Annotation)>),
+	PageText {
+		page_num: usize,
+		words: Vec<TextWord>
+	},
+	// The document is encrypted, so it can't be shown until we get its password. `wrong` is set if
+	// we already tried a password and it didn't work.
+	NeedsPassword {
+		wrong: bool
+	},
+	Reloaded,
+	// Where a forward search ended up, so it can be scrolled to
+	SyncTexTarget {
+		page: usize,
+		rect: PdfRect
+	},
+	// What an inverse search found, to be opened in the user's editor
+	SyncTexSource {
+		file: PathBuf,
+		line: u32
+	},
+	// Boxed for the same reason as `Page`
+	DocInfo(Box<DocInfo>),
+	PageLabels(PageLabels),
+	// A small version of a page, for the overview
+	Thumbnail {
+		page_num: usize,
+		img_data: ImageData
+	}
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -46,11 +123,94 @@ pub enum RotateDirection {
 	Deg270
 }
 
+impl RotateDirection {
+	fn rotated(self) -> Self {
+		match self {
+			Self::Deg0 => Self::Deg90,
+			Self::Deg90 => Self::Deg180,
+			Self::Deg180 => Self::Deg270,
+			Self::Deg270 => Self::Deg0
+		}
+	}
+
+	fn degrees(self) -> u16 {
+		match self {
+			Self::Deg0 => 0,
+			Self::Deg90 => 90,
+			Self::Deg180 => 180,
+			Self::Deg270 => 270
+		}
+	}
+}
+
+/// Whether to cut the empty margins off of the pages before fitting them to the screen
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Trim {
+	#[default]
+	Off,
+	// Each page is cropped to just what's on it
+	PerPage,
+	// Every page is cropped to the same area (the smallest one that fits what's on all of them),
+	// so that things don't jump around when flipping between pages
+	Uniform
+}
+
+impl Trim {
+	fn cycled(self) -> Self {
+		match self {
+			Self::Off => Self::PerPage,
+			Self::PerPage => Self::Uniform,
+			Self::Uniform => Self::Off
+		}
+	}
+}
+
+impl FromStr for RotateDirection {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" => Ok(Self::Deg0),
+			"90" => Ok(Self::Deg90),
+			"180" => Ok(Self::Deg180),
+			"270" => Ok(Self::Deg270),
+			_ => Err(format!(
+				"Can't rotate by `{s}` degrees (expected `0`, `90`, `180`, or `270`)"
+			))
+		}
+	}
+}
+
+impl FromStr for Trim {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"off" => Ok(Self::Off),
+			"per-page" => Ok(Self::PerPage),
+			"uniform" => Ok(Self::Uniform),
+			_ => Err(format!(
+				"Unknown trim mode `{s}` (expected `off`, `per-page`, or `uniform`)"
+			))
+		}
+	}
+}
+
 #[derive(Clone)]
 pub struct PageInfo {
 	pub img_data: ImageData,
 	pub page_num: usize,
-	pub result_rects: Vec<HighlightRect>
+	pub result_rects: Vec<HighlightRect>,
+	// The index of the result within `result_rects` that the user is currently on, if any
+	pub active_result: Option<usize>,
+	pub selection_rects: Vec<HighlightRect>,
+	pub links: Vec<PageLink>,
+	// How the page's own coordinates map onto the shown part of this image
+	pub transform: PageTransform,
+	// The part of the image that should actually be shown, if the margins are being trimmed off.
+	// All the other rects in here are relative to its top-left corner.
+	pub crop: Option<HighlightRect>
 }
 
 #[derive(Clone)]
@@ -60,8 +220,21 @@ pub struct ImageData {
 	pub cell_h: u16
 }
 
+fn mark_for_rerender(
+	rendered: &mut [PrevRender],
+	need_rerender: &mut VecDeque<usize>,
+	pages: impl Iterator<Item = usize>
+) {
+	for page in pages {
+		if let Some(r) = rendered.get_mut(page) {
+			r.successful = false;
+			need_rerender.push_back(page);
+		}
+	}
+}
+
 #[derive(Default)]
-struct PrevRender {
+pub(crate) struct PrevRender {
 	successful: bool,
 	num_search_found: Option<usize>
 }
@@ -96,11 +269,19 @@ pub fn start_rendering(
 	col_w: u16,
 	prerender: PrerenderLimit,
 	black: i32,
-	white: i32
+	white: i32,
+	mut trim: Trim,
+	// Whether to draw the annotations (like highlights and sticky notes) on top of the pages
+	mut annotations: bool,
+	// The password to try first if the document is encrypted, before asking the user for one
+	mut password: Option<String>,
+	// Whether to keep the rendered pages on disk so that they don't need to be rendered again
+	cache_pages: bool
 ) -> Result<(), SendError<Result<RenderInfo, RenderError>>> {
 	// We want this outside of 'reload so that if the doc reloads, the search term that somebody
 	// set will still get highlighted in the reloaded doc
 	let mut search_term = None;
+	let mut active_result: Option<(usize, usize)> = None;
 
 	// And although the font size could theoretically change, we aren't accounting for that right
 	// now, so we just use the values passed in.
@@ -110,24 +291,38 @@ pub fn start_rendering(
 	let mut rotate = RotateDirection::Deg0;
 	let mut preserved_area = None;
 	let mut fit_or_fill = FitOrFill::Fit;
+	// Whether the overview is open, and the size (in cells) that its thumbnails are rendered to
+	// fit in. The size is kept after it's closed so we know if the thumbnails that were already
+	// made can still be used when it's opened again.
+	let mut thumbnails_shown = false;
+	let mut thumbnail_size = (0, 0);
 
 	let mut need_rerender = VecDeque::new();
 
+	// mupdf needs a string on windows (see below), but the synctex file is found from the path
+	let pdf_path = path;
 	#[cfg(windows)]
 	let path = path.to_string_lossy();
 
 	'reload: loop {
+		// This is opened again whenever the document is reloaded, since it's keyed on what's in it
+		let mut cache;
+
 		// Need to do this weird borrow thing so that we convert `Cow<'_, str>` -> `&str` on windows
 		// and keep unix a `&Path` -> `&Path` 'cause there are different requirements within mupdf
 		// about file paths per-platform
 		#[cfg_attr(unix, expect(clippy::borrow_deref_ref))]
-		let doc = match Document::open(&*path) {
+		let doc: &mut Document = match Document::open(&*path) {
 			Err(e) => {
 				// if there's an error, tell the main loop
 				sender.send(Err(RenderError::Doc(e)))?;
 
+				// The file on disk isn't what we're still showing, so the pages we render now
+				// can't be saved as if they were from it
+				cache = None;
+
 				match stored_doc {
-					Some(ref d) => d,
+					Some(ref mut d) => d,
 					None => {
 						// then wait for a reload notif (since what probably happened is that the file was
 						// temporarily removed to facilitate a save or something like that)
@@ -147,10 +342,71 @@ pub fn start_rendering(
 				if stored_doc.is_some() {
 					sender.send(Ok(RenderInfo::Reloaded))?;
 				}
-				&*stored_doc.insert(d)
+				cache = cache_pages.then(|| RenderCache::open(pdf_path)).flatten();
+				stored_doc.insert(d)
 			}
 		};
 
+		// Where to start rendering from. This is declared out here since we could be told to jump
+		// somewhere while we're waiting for the password.
+		let mut start_point = 0;
+
+		// Encrypted documents open just fine, but every page fails to load until they're unlocked.
+		// We hold onto the password that worked so that reloading doesn't ask for it again.
+		while doc.needs_password().unwrap_or(false) {
+			if let Some(ref attempt) = password {
+				match doc.authenticate(attempt) {
+					Ok(true) => break,
+					Ok(false) => (),
+					Err(e) => sender.send(Err(RenderError::Doc(e)))?
+				}
+			}
+
+			sender.send(Ok(RenderInfo::NeedsPassword {
+				wrong: password.take().is_some()
+			}))?;
+
+			// Nothing's been rendered yet, so we just need to remember what we're told until we
+			// get a password to try
+			loop {
+				let Ok(notif) = receiver.recv() else {
+					// the main thread is done, so we are too
+					return Ok(());
+				};
+				match notif {
+					RenderNotif::Password(attempt) => {
+						password = Some(attempt);
+						break;
+					}
+					RenderNotif::Reload => continue 'reload,
+					RenderNotif::Area(area) => preserved_area = Some(area),
+					RenderNotif::Invert => invert = !invert,
+					RenderNotif::Rotate => rotate = rotate.rotated(),
+					RenderNotif::CycleTrim => trim = trim.cycled(),
+					RenderNotif::ToggleAnnotations => annotations = !annotations,
+					// Nothing's been rendered at any size yet, so there's nothing to throw away
+					RenderNotif::Thumbnails(size) => {
+						thumbnails_shown = size.is_some();
+						thumbnail_size = size.unwrap_or(thumbnail_size);
+					}
+					RenderNotif::SwitchFitOrFill(f_or_f) => fit_or_fill = f_or_f,
+					RenderNotif::JumpToPage(page) => start_point = page,
+					RenderNotif::Search(query) =>
+						search_term = (!query.is_empty()).then_some(query),
+					RenderNotif::ActiveResult(new_active) => active_result = new_active,
+					RenderNotif::PageNeedsReRender(_)
+					| RenderNotif::ThumbnailNeedsReRender(_)
+					| RenderNotif::ExtractText(_)
+					| RenderNotif::Select(_)
+					| RenderNotif::ForwardSearch { .. }
+					| RenderNotif::InverseSearch { .. }
+					// There's nothing to say about the document until it's been unlocked
+					| RenderNotif::DocInfo => ()
+				}
+			}
+		}
+		let doc = &*doc;
+
 		let n_pages = match doc.page_count() {
 			Ok(n) => match NonZeroUsize::new(n as usize) {
 				Some(n) => n,
@@ -168,6 +424,49 @@ pub fn start_rendering(
 		};
 
 		sender.send(Ok(RenderInfo::NumPages(n_pages.get())))?;
+		start_point = start_point.min(n_pages.get() - 1);
+
+		// The outline could've changed if the document was reloaded, so we just send it over
+		// every time
+		match load_outline(doc) {
+			Ok(outline) => sender.send(Ok(RenderInfo::Outline(outline)))?,
+			Err(e) => sender.send(Err(RenderError::Doc(e)))?
+		}
+
+		// And the annotations, which are all loaded now (even if they aren't drawn) so that the
+		// list of them doesn't only have the pages that have been rendered so far
+		match load_all_annotations(doc, n_pages.get()) {
+			Ok(annotations) => sender.send(Ok(RenderInfo::Annotations(annotations)))?,
+			Err(e) => sender.send(Err(RenderError::Doc(e)))?
+		}
+
+		// Same with the page labels. Only PDFs have them, so if this isn't one, its pages are just
+		// left unlabelled.
+		#[cfg_attr(unix, expect(clippy::borrow_deref_ref))]
+		let pdf = Document::open(&*path)
+			.ok()
+			.and_then(|pdf| unlocked_pdf(pdf, password.as_deref()));
+		match pdf.map_or_else(
+			|| Ok(PageLabels::default()),
+			|pdf| load_page_labels(&pdf, n_pages.get())
+		) {
+			Ok(labels) => sender.send(Ok(RenderInfo::PageLabels(labels)))?,
+			Err(e) => sender.send(Err(RenderError::Doc(e)))?
+		}
+
+		// The synctex file is rewritten every time the document is recompiled, so it's reloaded
+		// along with it
+		let synctex = match SyncTex::for_pdf(pdf_path) {
+			Ok(synctex) => synctex,
+			Err(e) => {
+				sender.send(Err(RenderError::SyncTex(format!(
+					"Couldn't read the SyncTeX data: {e}"
+				))))?;
+				None
+			}
+		};
+		// The page and area that the last forward search found, which is highlighted
+		let mut synctex_target: Option<(usize, PdfRect)> = None;
 
 		// We're using this vec to indicate which page numbers have already been rendered, to
 		// support people jumping to specific pages and having quick rendering results. We
@@ -176,7 +475,17 @@ pub fn start_rendering(
 		// then we can split at that page and render at both sides of it
 		let mut rendered = Vec::new();
 		fill_default::<PrevRender>(&mut rendered, n_pages.get());
-		let mut start_point = 0;
+		// Which pages the overview has a thumbnail of. These are tracked separately from
+		// `rendered` so that opening the overview doesn't throw away the full-size pages.
+		let mut thumbnailed = vec![false; n_pages.get()];
+
+		// The text that the selection was made on may not exist anymore after a reload, so this
+		// is reset every time too
+		let mut selection: Option<PageSelection> = None;
+
+		// What's on every page put together, for `Trim::Uniform`. This is only figured out once
+		// it's needed, since it means going over the whole document.
+		let mut uniform_content: Option<Option<PdfRect>> = None;
 
 		// This is kinda a weird way of doing this, but if we get a notification that the area
 		// changed, we want to start re-rending all of the pages, but we don't want to reload the
@@ -209,6 +518,7 @@ pub fn start_rendering(
 							for page in &mut rendered {
 								page.successful = false;
 							}
+							thumbnailed.fill(false);
 							continue 'render_pages;
 						}
 						RenderNotif::Area(new_area) => {
@@ -223,7 +533,9 @@ pub fn start_rendering(
 								continue 'render_pages;
 							},
 						RenderNotif::JumpToPage(page) => {
-							start_point = page;
+							// This can be past the end if we're restoring the position from the
+							// last time this document was open and it's gotten shorter since then
+							start_point = page.min(n_pages.get() - 1);
 							continue 'render_pages;
 						}
 						RenderNotif::PageNeedsReRender(page) => {
@@ -231,8 +543,22 @@ pub fn start_rendering(
 							need_rerender.push_back(page);
 							continue 'render_pages;
 						}
-						RenderNotif::Search(term) => {
-							if term.is_empty() {
+						RenderNotif::ThumbnailNeedsReRender(page) => {
+							thumbnailed[page] = false;
+							continue 'render_pages;
+						}
+						RenderNotif::Thumbnails(size) => {
+							thumbnails_shown = size.is_some();
+							if let Some(size) = size
+								&& size != thumbnail_size
+							{
+								thumbnail_size = size;
+								thumbnailed.fill(false);
+							}
+							continue 'render_pages;
+						}
+						RenderNotif::Search(query) => {
+							if query.is_empty() {
 								// If the term is set to nothing, then we don't need to re-render
 								// the pages wherein there were already no search results. So this
 								// is a little optimization to allow that.
@@ -251,26 +577,175 @@ pub fn start_rendering(
 								for page in &mut rendered {
 									page.num_search_found = None;
 								}
-								search_term = Some(term);
+								search_term = Some(query);
 							}
+							active_result = None;
+							continue 'render_pages;
+						}
+						RenderNotif::ExtractText(page_num) => match doc
+							.load_page(page_num as i32)
+							.and_then(|page| extract_words(&page))
+						{
+							Ok(words) =>
+								sender.send(Ok(RenderInfo::PageText { page_num, words }))?,
+							Err(e) => sender.send(Err(RenderError::Doc(e)))?
+						},
+						RenderNotif::Select(new_selection) => {
+							// Both the page that used to have a selection and the page that now
+							// has it need to be redrawn
+							let changed = selection.iter().chain(&new_selection).map(|s| s.page);
+							mark_for_rerender(&mut rendered, &mut need_rerender, changed);
+							selection = new_selection;
+							continue 'render_pages;
+						}
+						RenderNotif::ActiveResult(new_active) => {
+							// Same as with the selection, both the old and new pages need redrawing
+							let changed = active_result.iter().chain(&new_active).map(|(p, _)| *p);
+							mark_for_rerender(&mut rendered, &mut need_rerender, changed);
+							active_result = new_active;
 							continue 'render_pages;
 						}
 						RenderNotif::Rotate => {
-							rotate = match rotate {
-								RotateDirection::Deg0 => RotateDirection::Deg90,
-								RotateDirection::Deg90 => RotateDirection::Deg180,
-								RotateDirection::Deg180 => RotateDirection::Deg270,
-								RotateDirection::Deg270 => RotateDirection::Deg0
-							};
+							rotate = rotate.rotated();
+							for page in &mut rendered {
+								page.successful = false;
+							}
+							thumbnailed.fill(false);
+							continue 'render_pages;
+						}
+						RenderNotif::CycleTrim => {
+							trim = trim.cycled();
 							for page in &mut rendered {
 								page.successful = false;
 							}
 							continue 'render_pages;
 						}
+						RenderNotif::ToggleAnnotations => {
+							annotations = !annotations;
+							for page in &mut rendered {
+								page.successful = false;
+							}
+							thumbnailed.fill(false);
+							continue 'render_pages;
+						}
+						// We only ask for this before we start rendering
+						RenderNotif::Password(_) => (),
+						RenderNotif::ForwardSearch { file, line } => {
+							let target = match synctex {
+								Some(ref synctex) =>
+									synctex.forward(&file, line).ok_or_else(|| {
+										format!(
+											"Nothing in the document came from line {line} of {}",
+											file.display()
+										)
+									}),
+								None => Err(NO_SYNCTEX.to_owned())
+							};
+							match target {
+								Ok((page, rect)) => {
+									// Both the page the last target was on and the new one need
+									// redrawing
+									let changed =
+										synctex_target.map(|(p, _)| p).into_iter().chain([page]);
+									mark_for_rerender(&mut rendered, &mut need_rerender, changed);
+									synctex_target = Some((page, rect));
+									start_point = page;
+									sender.send(Ok(RenderInfo::SyncTexTarget { page, rect }))?;
+									continue 'render_pages;
+								}
+								Err(e) => sender.send(Err(RenderError::SyncTex(e)))?
+							}
+						}
+						RenderNotif::InverseSearch { page, point } => {
+							let source = match synctex {
+								Some(ref synctex) =>
+									synctex.inverse(page, point).ok_or_else(|| {
+										"Nothing on this page came from any of the source files"
+											.to_owned()
+									}),
+								None => Err(NO_SYNCTEX.to_owned())
+							};
+							match source {
+								Ok((file, line)) => sender.send(Ok(RenderInfo::SyncTexSource {
+									file: file.to_owned(),
+									line
+								}))?,
+								Err(e) => sender.send(Err(RenderError::SyncTex(e)))?
+							}
+						}
+						RenderNotif::DocInfo => {
+							// mupdf only lets us look at what's specific to PDFs (like their fonts)
+							// through a document that's been turned into a `PdfDocument`, which
+							// would mean giving up `doc`, so it's opened again for that
+							let pdf = Document::open(&*path)
+								.ok()
+								.and_then(|pdf| unlocked_pdf(pdf, password.as_deref()));
+							match load_info(doc, pdf.as_ref()) {
+								Ok(info) => sender.send(Ok(RenderInfo::DocInfo(Box::new(info))))?,
+								Err(e) => sender.send(Err(RenderError::Doc(e)))?
+							}
+						}
 					}
 				}};
 			}
 
+			// While the overview is open, its thumbnails are what's being looked at, so they come
+			// before the full-size pages. They're always of the whole page (even if the margins are
+			// being trimmed) so that they're all the same shape in the grid.
+			if thumbnails_shown {
+				let thumbnail_area = (
+					f32::from(thumbnail_size.0) * f32::from(col_w),
+					f32::from(thumbnail_size.1) * f32::from(col_h)
+				);
+				for page_num in InterleavedAroundWithMax::new(start_point, 0, n_pages) {
+					if thumbnailed[page_num] {
+						continue;
+					}
+					// Even if it fails, we don't want to keep trying it over and over
+					thumbnailed[page_num] = true;
+
+					let ctx = doc.load_page(page_num as i32).and_then(|page| {
+						render_single_page_to_ctx(
+							&page,
+							None,
+							&[],
+							&PrevRender::default(),
+							invert,
+							black,
+							white,
+							FitOrFill::Fit,
+							rotate,
+							None,
+							annotations,
+							thumbnail_area,
+							Some(KITTY_MAX_W_OR_H)
+						)
+					});
+					let mut pixels = Vec::new();
+					match ctx.and_then(|ctx| {
+						ctx.pixmap
+							.write_to(&mut pixels, mupdf::ImageFormat::PNM)
+							.map(|()| ctx)
+					}) {
+						Ok(ctx) => sender.send(Ok(RenderInfo::Thumbnail {
+							page_num,
+							img_data: ImageData {
+								pixels,
+								cell_w: (ctx.layout.surface_w / f32::from(col_w)) as u16,
+								cell_h: (ctx.layout.surface_h / f32::from(col_h)) as u16
+							}
+						}))?,
+						Err(e) => sender.send(Err(RenderError::Doc(e)))?
+					}
+
+					match receiver.try_recv() {
+						Err(TryRecvError::Empty) => (),
+						Err(TryRecvError::Disconnected) => return Ok(()),
+						Ok(msg) => handle_notif!(msg)
+					}
+				}
+			}
+
 			let any_not_searched = rendered.iter().any(|r| r.num_search_found.is_none());
 
 			// This is our iterator over all the pages we want to look at and render. It uses this
@@ -321,44 +796,122 @@ pub fn start_rendering(
 					Ok(p) => p
 				};
 
-				// render the page
-				match render_single_page_to_ctx(
+				let selected = selection
+					.as_ref()
+					.filter(|s| s.page == page_num)
+					.map(|s| s.rects.as_slice())
+					.unwrap_or_default();
+
+				// The target of a forward search is highlighted just like the selection is
+				let with_target;
+				let selected = match synctex_target {
+					Some((page, rect)) if page == page_num => {
+						with_target = [selected, &[rect]].concat();
+						with_target.as_slice()
+					}
+					_ => selected
+				};
+
+				let content = match trim {
+					Trim::Off => None,
+					Trim::PerPage => match content_bounds(&page) {
+						Ok(content) => content,
+						Err(e) => {
+							sender.send(Err(RenderError::Doc(e)))?;
+							None
+						}
+					},
+					Trim::Uniform =>
+						*uniform_content.get_or_insert_with(|| all_content_bounds(doc, n_pages)),
+				};
+
+				let fit = match fit_page(
 					&page,
-					search_term.as_deref(),
-					rendered,
-					invert,
-					black,
-					white,
 					fit_or_fill,
 					rotate,
-					(area_w, area_h)
+					content,
+					(area_w, area_h),
+					Some(KITTY_MAX_W_OR_H)
 				) {
-					// If that fn returned Some, that means it needed to be re-rendered for some
-					// reason or another, so we're sending it here
-					Ok(ctx) => {
+					Ok(fit) => fit,
+					Err(e) => {
+						sender.send(Err(RenderError::Doc(e)))?;
+						continue;
+					}
+				};
+				let (width, height) = fit.pixel_size();
+				let cache_key = CacheKey {
+					page: page_num,
+					width,
+					height,
+					black,
+					white,
+					invert,
+					rotation: rotate.degrees(),
+					annotations
+				};
+
+				// If this page has been rendered the exact same way before (even the last time
+				// the document was opened), we can skip the expensive part and just figure out
+				// where everything is on it
+				let page_render = match cache.as_mut().and_then(|cache| cache.get(&cache_key)) {
+					Some(pixels) => {
+						log::debug!("got cached pixmap for page {page_num}");
+						lay_out_page(&page, search_term.as_ref(), selected, rendered, &fit)
+							.map(|layout| (pixels, layout))
+					}
+					None => render_single_page_to_ctx(
+						&page,
+						search_term.as_ref(),
+						selected,
+						rendered,
+						invert,
+						black,
+						white,
+						fit_or_fill,
+						rotate,
+						content,
+						annotations,
+						(area_w, area_h),
+						Some(KITTY_MAX_W_OR_H)
+					)
+					.and_then(|ctx| {
 						let w = ctx.pixmap.width();
 						let h = ctx.pixmap.height();
 						let cap = (w * h * u32::from(ctx.pixmap.n())) as usize + 16;
 						let mut pixels = Vec::with_capacity(cap);
-						if let Err(e) = ctx.pixmap.write_to(&mut pixels, mupdf::ImageFormat::PNM) {
-							sender.send(Err(RenderError::Doc(e)))?;
-							continue;
-						}
+						ctx.pixmap.write_to(&mut pixels, mupdf::ImageFormat::PNM)?;
 
 						log::debug!("got pixmap for page {page_num} with WxH {w}x{h}");
 
-						rendered.num_search_found = Some(ctx.result_rects.len());
+						if let Some(ref mut cache) = cache {
+							cache.put(&cache_key, &pixels);
+						}
+						Ok((pixels, ctx.layout))
+					})
+				};
+
+				match page_render {
+					Ok((pixels, layout)) => {
+						rendered.num_search_found = Some(layout.result_rects.len());
 						rendered.successful = true;
 
-						sender.send(Ok(RenderInfo::Page(PageInfo {
+						sender.send(Ok(RenderInfo::Page(Box::new(PageInfo {
 							img_data: ImageData {
 								pixels,
-								cell_w: (ctx.surface_w / f32::from(col_w)) as u16,
-								cell_h: (ctx.surface_h / f32::from(col_h)) as u16
+								cell_w: (layout.surface_w / f32::from(col_w)) as u16,
+								cell_h: (layout.surface_h / f32::from(col_h)) as u16
 							},
 							page_num,
-							result_rects: ctx.result_rects
-						})))?;
+							result_rects: layout.result_rects,
+							active_result: active_result
+								.filter(|(page, _)| *page == page_num)
+								.map(|(_, idx)| idx),
+							selection_rects: layout.selection_rects,
+							links: layout.links,
+							transform: layout.transform,
+							crop: layout.crop
+						}))))?;
 					}
 					// And if we got an error, then obviously we need to propagate that
 					Err(e) => sender.send(Err(RenderError::Doc(e)))?
@@ -469,38 +1022,70 @@ pub fn start_rendering(
 	}
 }
 
-struct RenderedContext {
-	pixmap: Pixmap,
+pub(crate) struct RenderedContext {
+	pub(crate) pixmap: Pixmap,
+	pub(crate) layout: PageLayout
+}
+
+/// Everything about a rendered page besides its pixels, which all depends on how it was scaled
+pub(crate) struct PageLayout {
 	surface_w: f32,
 	surface_h: f32,
-	result_rects: Vec<HighlightRect>
+	pub(crate) result_rects: Vec<HighlightRect>,
+	selection_rects: Vec<HighlightRect>,
+	links: Vec<PageLink>,
+	transform: PageTransform,
+	crop: Option<HighlightRect>
 }
 
-#[expect(clippy::too_many_arguments)]
-fn render_single_page_to_ctx(
+// How a page gets scaled and rotated to fit the area that it's being rendered for
+struct PageFit {
+	bounds: PdfRect,
+	// The part of the page that we're going to show, if its margins are being trimmed off
+	shown: Option<PdfRect>,
+	surface_w: f32,
+	surface_h: f32,
+	scale_factor: f32,
+	rotate: RotateDirection
+}
+
+impl PageFit {
+	// How big the whole page (not just the part that's shown) is once it's rendered, in pixels
+	fn pixel_size(&self) -> (u32, u32) {
+		let w = ((self.bounds.x1 - self.bounds.x0) * self.scale_factor).round() as u32;
+		let h = ((self.bounds.y1 - self.bounds.y0) * self.scale_factor).round() as u32;
+		match self.rotate {
+			RotateDirection::Deg0 | RotateDirection::Deg180 => (w, h),
+			RotateDirection::Deg90 | RotateDirection::Deg270 => (h, w)
+		}
+	}
+}
+
+fn fit_page(
 	page: &Page,
-	search_term: Option<&str>,
-	prev_render: &PrevRender,
-	invert: bool,
-	black: i32,
-	white: i32,
 	fit_or_fill: FitOrFill,
 	rotate: RotateDirection,
-	(area_w, area_h): (f32, f32)
-) -> Result<RenderedContext, mupdf::error::Error> {
-	let result_rects = match prev_render.num_search_found {
-		None => search_page(page, search_term, 0)?,
-		Some(0) => Vec::new(),
-		Some(count @ 1..) => search_page(page, search_term, count)?
-	};
-
-	// then, get the size of the page
+	content: Option<PdfRect>,
+	(area_w, area_h): (f32, f32),
+	// The most pixels the page can be in either direction, if it's limited
+	max_w_or_h: Option<f32>
+) -> Result<PageFit, mupdf::error::Error> {
+	// get the size of the page
 	let bounds = page.bounds()?;
+	let bounds = PdfRect {
+		x0: bounds.x0,
+		y0: bounds.y0,
+		x1: bounds.x1,
+		y1: bounds.y1
+	};
+	// and the part of it that we're going to show, which is what we fit to the area
+	let shown = content.and_then(|content| content.intersection(bounds));
+	let fitted = shown.unwrap_or(bounds);
 	let page_dim = match rotate {
 		RotateDirection::Deg0 | RotateDirection::Deg180 =>
-			(bounds.x1 - bounds.x0, bounds.y1 - bounds.y0),
+			(fitted.x1 - fitted.x0, fitted.y1 - fitted.y0),
 		RotateDirection::Deg90 | RotateDirection::Deg270 =>
-			(bounds.y1 - bounds.y0, bounds.x1 - bounds.x0),
+			(fitted.y1 - fitted.y0, fitted.x1 - fitted.x0),
 	};
 
 	let scaled = scale_img_for_area(page_dim, (area_w, area_h), fit_or_fill);
@@ -510,23 +1095,50 @@ fn render_single_page_to_ctx(
 		mut scale_factor
 	} = scaled;
 
-	if surface_w > KITTY_MAX_W_OR_H || surface_h > KITTY_MAX_W_OR_H {
-		let descale = (surface_w / KITTY_MAX_W_OR_H).max(surface_h / KITTY_MAX_W_OR_H);
+	if let Some(max) = max_w_or_h
+		&& (surface_w > max || surface_h > max)
+	{
+		let descale = (surface_w / max).max(surface_h / max);
 		surface_w /= descale;
 		surface_h /= descale;
 		scale_factor /= descale;
 	}
 
+	Ok(PageFit {
+		bounds,
+		shown,
+		surface_w,
+		surface_h,
+		scale_factor,
+		rotate
+	})
+}
+
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn render_single_page_to_ctx(
+	page: &Page,
+	search_term: Option<&SearchQuery>,
+	selection: &[PdfRect],
+	prev_render: &PrevRender,
+	invert: bool,
+	black: i32,
+	white: i32,
+	fit_or_fill: FitOrFill,
+	rotate: RotateDirection,
+	// What's actually on the page, if the margins around it should be trimmed off
+	content: Option<PdfRect>,
+	annotations: bool,
+	area: (f32, f32),
+	max_w_or_h: Option<f32>
+) -> Result<RenderedContext, mupdf::error::Error> {
+	let fit = fit_page(page, fit_or_fill, rotate, content, area, max_w_or_h)?;
+
 	let colorspace = Colorspace::device_rgb();
-	let mut matrix = Matrix::new_scale(scale_factor, scale_factor);
-	match rotate {
-		RotateDirection::Deg0 => matrix.rotate(0.0),
-		RotateDirection::Deg90 => matrix.rotate(90.0),
-		RotateDirection::Deg180 => matrix.rotate(180.0),
-		RotateDirection::Deg270 => matrix.rotate(270.0)
-	};
+	let mut matrix = Matrix::new_scale(fit.scale_factor, fit.scale_factor);
+	matrix.rotate(f32::from(rotate.degrees()));
 
-	let mut pixmap = page.to_pixmap(&matrix, &colorspace, false, false)?;
+	// mupdf calls the annotations (and form fields) 'extras'
+	let mut pixmap = page.to_pixmap(&matrix, &colorspace, false, annotations)?;
 	if invert {
 		pixmap.tint(white, black)?;
 	} else if black != MUPDF_BLACK || white != MUPDF_WHITE {
@@ -534,35 +1146,231 @@ fn render_single_page_to_ctx(
 	}
 
 	let (x_res, y_res) = pixmap.resolution();
-	let new_x = (x_res as f32 * scale_factor) as i32;
-	let new_y = (y_res as f32 * scale_factor) as i32;
+	let new_x = (x_res as f32 * fit.scale_factor) as i32;
+	let new_y = (y_res as f32 * fit.scale_factor) as i32;
 
 	pixmap.set_resolution(new_x, new_y);
 
+	let layout = lay_out_page(page, search_term, selection, prev_render, &fit)?;
+	Ok(RenderedContext { pixmap, layout })
+}
+
+// Finds where the search results, selection, and links are on the page once it's been rendered
+// to fit how it was
+fn lay_out_page(
+	page: &Page,
+	search_term: Option<&SearchQuery>,
+	selection: &[PdfRect],
+	prev_render: &PrevRender,
+	fit: &PageFit
+) -> Result<PageLayout, mupdf::error::Error> {
+	let result_rects = match prev_render.num_search_found {
+		None => search_page(page, search_term, 0)?,
+		Some(0) => Vec::new(),
+		Some(count @ 1..) => search_page(page, search_term, count)?
+	};
+
+	// The whole page still gets rendered, so the converter has to cut the shown part out of it
+	let crop = fit
+		.shown
+		.map(|shown| rect_in_pixmap(shown, fit.bounds, fit.scale_factor, fit.rotate));
+	// and everything we send along needs to be moved to match
+	let transform = PageTransform::new(
+		fit.bounds,
+		fit.scale_factor,
+		fit.rotate,
+		crop.as_ref().map_or((0, 0), |crop| (crop.ul_x, crop.ul_y))
+	);
+	let to_px = |rect: PdfRect| transform.to_px(rect);
+
 	let result_rects = result_rects
 		.into_iter()
 		.map(|quad| {
-			let ul_x = (quad.ul.x * scale_factor) as u32;
-			let ul_y = (quad.ul.y * scale_factor) as u32;
-			let lr_x = (quad.lr.x * scale_factor) as u32;
-			let lr_y = (quad.lr.y * scale_factor) as u32;
-			HighlightRect {
-				ul_x,
-				ul_y,
-				lr_x,
-				lr_y
-			}
+			to_px(PdfRect {
+				x0: quad.ul.x,
+				y0: quad.ul.y,
+				x1: quad.lr.x,
+				y1: quad.lr.y
+			})
 		})
 		.collect::<Vec<_>>();
 
-	Ok(RenderedContext {
-		pixmap,
-		surface_w,
-		surface_h,
-		result_rects
+	let selection_rects = selection.iter().copied().map(to_px).collect::<Vec<_>>();
+
+	let links = page
+		.links()?
+		.filter_map(|link| {
+			let target = match link.dest {
+				Some(dest) => LinkTarget::Page(dest.loc.page_number as usize),
+				// If it doesn't point anywhere within the document and doesn't have a uri,
+				// there's nothing we can do with it
+				None if link.uri.is_empty() => return None,
+				None => LinkTarget::External(link.uri)
+			};
+
+			Some(PageLink {
+				rect: to_px(PdfRect {
+					x0: link.bounds.x0,
+					y0: link.bounds.y0,
+					x1: link.bounds.x1,
+					y1: link.bounds.y1
+				}),
+				target
+			})
+		})
+		.collect::<Vec<_>>();
+
+	Ok(PageLayout {
+		surface_w: fit.surface_w,
+		surface_h: fit.surface_h,
+		result_rects,
+		selection_rects,
+		links,
+		transform,
+		crop
 	})
 }
 
+/// How the page's own coordinates map onto the pixels of the part of it that's shown, once it's
+/// been scaled, rotated, and had its margins trimmed off
+#[derive(Clone, Copy, Debug)]
+pub struct PageTransform {
+	bounds: PdfRect,
+	scale_factor: f32,
+	rotate: RotateDirection,
+	// Where the shown part of the page starts in the rendered image, if its margins were trimmed
+	// off
+	origin: (u32, u32)
+}
+
+impl PageTransform {
+	/// `origin` is where the shown part of the page starts in the image that the whole page was
+	/// rendered to, in pixels
+	#[must_use]
+	pub fn new(
+		bounds: PdfRect,
+		scale_factor: f32,
+		rotate: RotateDirection,
+		origin: (u32, u32)
+	) -> Self {
+		Self {
+			bounds,
+			scale_factor,
+			rotate,
+			origin
+		}
+	}
+
+	/// Where `rect` ends up on the shown part of the page
+	#[must_use]
+	pub fn to_px(self, rect: PdfRect) -> HighlightRect {
+		let px = rect_in_pixmap(rect, self.bounds, self.scale_factor, self.rotate);
+		let (origin_x, origin_y) = self.origin;
+		HighlightRect {
+			ul_x: px.ul_x.saturating_sub(origin_x),
+			ul_y: px.ul_y.saturating_sub(origin_y),
+			lr_x: px.lr_x.saturating_sub(origin_x),
+			lr_y: px.lr_y.saturating_sub(origin_y)
+		}
+	}
+
+	/// Which point on the page ends up at (`x`, `y`) on the shown part of it, i.e. the opposite of
+	/// `to_px`
+	#[must_use]
+	pub fn to_page(self, (x, y): (f32, f32)) -> (f32, f32) {
+		let Self {
+			bounds,
+			scale_factor,
+			origin: (origin_x, origin_y),
+			..
+		} = self;
+		let (w, h) = (bounds.x1 - bounds.x0, bounds.y1 - bounds.y0);
+		let x = (x + origin_x as f32) / scale_factor;
+		let y = (y + origin_y as f32) / scale_factor;
+		// This undoes the rotation in `rect_in_pixmap`
+		let (x, y) = match self.rotate {
+			RotateDirection::Deg0 => (x, y),
+			RotateDirection::Deg90 => (y, h - x),
+			RotateDirection::Deg180 => (w - x, h - y),
+			RotateDirection::Deg270 => (w - y, x)
+		};
+		(x + bounds.x0, y + bounds.y0)
+	}
+}
+
+// Where `shown` (a part of the page) ends up in the pixmap that mupdf renders the page to, once
+// it's been scaled and rotated
+fn rect_in_pixmap(
+	shown: PdfRect,
+	bounds: PdfRect,
+	scale_factor: f32,
+	rotate: RotateDirection
+) -> HighlightRect {
+	let (w, h) = (bounds.x1 - bounds.x0, bounds.y1 - bounds.y0);
+	let PdfRect { x0, y0, x1, y1 } = PdfRect {
+		x0: shown.x0 - bounds.x0,
+		y0: shown.y0 - bounds.y0,
+		x1: shown.x1 - bounds.x0,
+		y1: shown.y1 - bounds.y0
+	};
+	// Rotating turns the page around its top-left corner, and then the pixmap starts wherever
+	// the top-left corner of the rotated page ended up
+	let (ul_x, ul_y, lr_x, lr_y) = match rotate {
+		RotateDirection::Deg0 => (x0, y0, x1, y1),
+		RotateDirection::Deg90 => (h - y1, x0, h - y0, x1),
+		RotateDirection::Deg180 => (w - x1, h - y1, w - x0, h - y0),
+		RotateDirection::Deg270 => (y0, w - x1, y1, w - x0)
+	};
+	HighlightRect {
+		ul_x: (ul_x * scale_factor) as u32,
+		ul_y: (ul_y * scale_factor) as u32,
+		lr_x: (lr_x * scale_factor) as u32,
+		lr_y: (lr_y * scale_factor) as u32
+	}
+}
+
+// The smallest rect that fits everything that's drawn on the page (with a little bit of padding),
+// or None if there's nothing on it
+fn content_bounds(page: &Page) -> Result<Option<PdfRect>, mupdf::error::Error> {
+	// Just enough that the content doesn't touch the edges of the screen
+	const PADDING: f32 = 6.;
+
+	let bounds = page.bounds()?;
+	let text_page =
+		page.to_text_page(TextPageFlags::PRESERVE_IMAGES | TextPageFlags::COLLECT_VECTORS)?;
+
+	let content = text_page
+		.blocks()
+		.map(|block| block.bounds())
+		// Lots of pages have a background drawn behind everything, which would leave nothing to
+		// trim off
+		.filter(|rect| {
+			rect.x0 > bounds.x0 || rect.y0 > bounds.y0 || rect.x1 < bounds.x1 || rect.y1 < bounds.y1
+		})
+		.map(|rect| PdfRect {
+			x0: rect.x0 - PADDING,
+			y0: rect.y0 - PADDING,
+			x1: rect.x1 + PADDING,
+			y1: rect.y1 + PADDING
+		})
+		.reduce(PdfRect::union);
+
+	Ok(content)
+}
+
+// The union of what's on every page, so that every page can be trimmed the same amount. Pages we
+// can't look at are just skipped, since we'll complain about them when we try to render them.
+fn all_content_bounds(doc: &Document, n_pages: NonZeroUsize) -> Option<PdfRect> {
+	(0..n_pages.get())
+		.filter_map(|page_num| {
+			doc.load_page(page_num as i32)
+				.and_then(|page| content_bounds(&page))
+				.ok()
+				.flatten()
+		})
+		.reduce(PdfRect::union)
+}
+
 #[derive(Clone, Debug)]
 pub struct HighlightRect {
 	pub ul_x: u32,
@@ -571,17 +1379,31 @@ pub struct HighlightRect {
 	pub lr_y: u32
 }
 
+#[derive(Clone, Debug)]
+pub struct PageLink {
+	// In pixels of the rendered image, same as the search result rects
+	pub rect: HighlightRect,
+	pub target: LinkTarget
+}
+
+#[derive(Clone, Debug)]
+pub enum LinkTarget {
+	Page(usize),
+	External(String)
+}
+
 #[inline]
 fn search_page(
 	page: &Page,
-	search_term: Option<&str>,
+	search_term: Option<&SearchQuery>,
 	trusted_search_results: usize
 ) -> Result<Vec<Quad>, mupdf::error::Error> {
 	search_term
-		.map(|term| {
-			page.to_text_page(TextPageFlags::empty()).and_then(|page| {
+		.map(|query| match query.matcher() {
+			Some(matcher) => search_page_text(page, matcher),
+			None => page.to_text_page(TextPageFlags::empty()).and_then(|page| {
 				let mut v = Vec::with_capacity(trusted_search_results);
-				page.search_cb(term, &mut v, |v, results| {
+				page.search_cb(&query.term, &mut v, |v, results| {
 					v.extend(results.iter().cloned());
 					SearchHitResponse::ContinueSearch
 				})
@@ -593,10 +1415,14 @@ fn search_page(
 }
 
 #[inline]
-fn count_search_results(page: &Page, search_term: &str) -> Result<usize, mupdf::error::Error> {
+fn count_search_results(page: &Page, query: &SearchQuery) -> Result<usize, mupdf::error::Error> {
+	if let Some(matcher) = query.matcher() {
+		return search_page_text(page, matcher).map(|quads| quads.len());
+	}
+
 	page.to_text_page(TextPageFlags::empty()).and_then(|page| {
 		let mut count = 0;
-		page.search_cb(search_term, &mut count, |count, results| {
+		page.search_cb(&query.term, &mut count, |count, results| {
 			*count += results.len();
 			SearchHitResponse::ContinueSearch
 		})?;
@@ -604,6 +1430,408 @@ fn count_search_results(page: &Page, search_term: &str) -> Result<usize, mupdf::
 	})
 }
 
+// mupdf can only do plain, case-insensitive searches, so for everything else we piece together
+// the text of the page ourselves, run the regex over that, and then map each match back to the
+// characters it covers. Like mupdf, this gives one quad for each line that a match is on.
+fn search_page_text(page: &Page, matcher: &Regex) -> Result<Vec<Quad>, mupdf::error::Error> {
+	let text_page = page.to_text_page(TextPageFlags::empty())?;
+
+	let mut text = String::new();
+	// Where each char of `text` starts, which line it's on, and where it is on the page. The
+	// spaces and newlines we add between lines aren't in here, since they aren't on the page.
+	let mut chars: Vec<(usize, usize, PdfRect)> = Vec::new();
+	let mut line_idx = 0;
+	for block in text_page.blocks() {
+		if block.r#type() != TextBlockType::Text {
+			continue;
+		}
+
+		for line in block.lines() {
+			for ch in line.chars() {
+				let Some(c) = ch.char() else {
+					continue;
+				};
+				chars.push((text.len(), line_idx, char_rect(ch.quad())));
+				text.push(c);
+			}
+			// Lines within a block are joined with spaces so that phrases which wrap onto the
+			// next line can still be found
+			text.push(' ');
+			line_idx += 1;
+		}
+		text.push('\n');
+	}
+
+	let mut quads = Vec::new();
+	// Patterns like `a*` match the empty string everywhere, but there's nothing to highlight there
+	for found in matcher.find_iter(&text).filter(|m| !m.is_empty()) {
+		let first = chars.partition_point(|(offset, _, _)| *offset < found.start());
+		let mut line_rect: Option<(usize, PdfRect)> = None;
+		for (offset, line, rect) in &chars[first..] {
+			if *offset >= found.end() {
+				break;
+			}
+
+			line_rect = match line_rect {
+				Some((cur_line, cur_rect)) if cur_line == *line =>
+					Some((cur_line, cur_rect.union(*rect))),
+				prev => {
+					quads.extend(prev.map(|(_, rect)| rect_quad(rect)));
+					Some((*line, *rect))
+				}
+			};
+		}
+		quads.extend(line_rect.map(|(_, rect)| rect_quad(rect)));
+	}
+
+	Ok(quads)
+}
+
+fn char_rect(Quad { ul, ur, ll, lr }: Quad) -> PdfRect {
+	PdfRect {
+		x0: ul.x.min(ll.x),
+		y0: ul.y.min(ur.y),
+		x1: ur.x.max(lr.x),
+		y1: ll.y.max(lr.y)
+	}
+}
+
+fn rect_quad(rect: PdfRect) -> Quad {
+	Quad {
+		ul: Point {
+			x: rect.x0,
+			y: rect.y0
+		},
+		ur: Point {
+			x: rect.x1,
+			y: rect.y0
+		},
+		ll: Point {
+			x: rect.x0,
+			y: rect.y1
+		},
+		lr: Point {
+			x: rect.x1,
+			y: rect.y1
+		}
+	}
+}
+
+fn load_outline(doc: &Document) -> Result<Vec<OutlineEntry>, mupdf::error::Error> {
+	fn convert(outlines: Vec<mupdf::Outline>) -> Vec<OutlineEntry> {
+		outlines
+			.into_iter()
+			.map(|outline| OutlineEntry {
+				title: outline.title,
+				page: outline.dest.map(|dest| dest.loc.page_number as usize),
+				children: convert(outline.down)
+			})
+			.collect()
+	}
+
+	doc.outlines().map(convert)
+}
+
+fn unlocked_pdf(mut doc: Document, password: Option<&str>) -> Option<PdfDocument> {
+	if doc.needs_password().ok()? && !doc.authenticate(password?).ok()? {
+		return None;
+	}
+	PdfDocument::try_from(doc).ok()
+}
+
+fn load_info(doc: &Document, pdf: Option<&PdfDocument>) -> Result<DocInfo, mupdf::error::Error> {
+	// mupdf gives back an empty string for anything that isn't set
+	let metadata = |name| -> Result<_, mupdf::error::Error> {
+		let value = doc.metadata(name)?;
+		Ok((!value.is_empty()).then_some(value))
+	};
+
+	let pages = usize::try_from(doc.page_count()?).unwrap_or_default();
+	let mut page_sizes = Vec::new();
+	for page_num in 0..pages {
+		let bounds = doc.load_page(page_num as i32)?.bounds()?;
+		PageSize::add_to(&mut page_sizes, bounds.width(), bounds.height());
+	}
+
+	let (fonts, tagged) = match pdf {
+		Some(pdf) => {
+			let marked = match pdf.catalog()?.get_dict("MarkInfo")? {
+				Some(mark_info) => mark_info.get_dict("Marked")?,
+				None => None
+			};
+			(
+				Some(load_fonts(pdf, pages)?),
+				Some(
+					marked
+						.map(|marked| marked.as_bool())
+						.transpose()?
+						.unwrap_or(false)
+				)
+			)
+		}
+		None => (None, None)
+	};
+
+	Ok(DocInfo {
+		title: metadata(MetadataName::Title)?,
+		author: metadata(MetadataName::Author)?,
+		subject: metadata(MetadataName::Subject)?,
+		creator: metadata(MetadataName::Creator)?,
+		producer: metadata(MetadataName::Producer)?,
+		created: metadata(MetadataName::CreationDate)?,
+		modified: metadata(MetadataName::ModDate)?,
+		format: metadata(MetadataName::Format)?,
+		// This is the only way mupdf tells us that a document isn't encrypted
+		encryption: metadata(MetadataName::Encryption)?.filter(|enc| enc != "None"),
+		pages,
+		page_sizes,
+		fonts,
+		tagged
+	})
+}
+
+fn load_page_labels(pdf: &PdfDocument, n_pages: usize) -> Result<PageLabels, mupdf::error::Error> {
+	let mut ranges = Vec::new();
+	if let Some(tree) = pdf.catalog()?.get_dict("PageLabels")? {
+		collect_label_ranges(&tree, &mut ranges, 0)?;
+	}
+	Ok(PageLabels::new(ranges, n_pages))
+}
+
+// The labels are kept in a number tree, where each leaf has an array of pairs of the page that a
+// range starts on and how that range is labelled
+fn collect_label_ranges(
+	node: &PdfObject,
+	ranges: &mut Vec<LabelRange>,
+	depth: u8
+) -> Result<(), mupdf::error::Error> {
+	// A broken document could have a tree that loops back on itself
+	const MAX_DEPTH: u8 = 32;
+	if depth > MAX_DEPTH {
+		return Ok(());
+	}
+
+	if let Some(kids) = node.get_dict("Kids")? {
+		for idx in 0..kids.len()? {
+			if let Some(kid) = kids.get_array(idx as i32)? {
+				collect_label_ranges(&kid, ranges, depth + 1)?;
+			}
+		}
+	}
+
+	let Some(nums) = node.get_dict("Nums")? else {
+		return Ok(());
+	};
+	for idx in (0..nums.len()?).step_by(2) {
+		let (Some(first_page), Some(label)) =
+			(nums.get_array(idx as i32)?, nums.get_array(idx as i32 + 1)?)
+		else {
+			continue;
+		};
+
+		let style = label.get_dict("S")?;
+		let prefix = label.get_dict("P")?;
+		let start = label.get_dict("St")?;
+		ranges.push(LabelRange {
+			first_page: usize::try_from(first_page.as_int()?).unwrap_or_default(),
+			style: style
+				.as_ref()
+				.map(PdfObject::as_name)
+				.transpose()?
+				.and_then(LabelStyle::from_name),
+			prefix: prefix
+				.as_ref()
+				.map(PdfObject::as_string)
+				.transpose()?
+				.unwrap_or_default()
+				.to_owned(),
+			start: start
+				.map(|start| start.as_int())
+				.transpose()?
+				.and_then(|start| u32::try_from(start).ok())
+				.unwrap_or(1)
+		});
+	}
+	Ok(())
+}
+
+// Every font that's used directly on the pages, sorted by name. Fonts that are only used inside of
+// other objects (like form xobjects) aren't found, but those are pretty rare.
+fn load_fonts(pdf: &PdfDocument, pages: usize) -> Result<Vec<Font>, mupdf::error::Error> {
+	let mut fonts = BTreeSet::new();
+	for page_num in 0..pages {
+		let page = pdf.find_page(page_num as i32)?;
+		let Some(resources) = page.get_dict_inheritable("Resources")? else {
+			continue;
+		};
+		let Some(page_fonts) = resources.get_dict("Font")? else {
+			continue;
+		};
+
+		for idx in 0..page_fonts.dict_len()? {
+			let Some(font) = page_fonts.get_dict_val(idx as i32)? else {
+				continue;
+			};
+			let name = match font.get_dict("BaseFont")? {
+				Some(name) => String::from_utf8_lossy(name.as_name()?).into_owned(),
+				// Type3 fonts don't need a name, since they're drawn by the PDF itself
+				None => "(unnamed)".to_owned()
+			};
+			fonts.insert(Font {
+				name,
+				embedded: is_embedded(&font)?
+			});
+		}
+	}
+	Ok(fonts.into_iter().collect())
+}
+
+fn is_embedded(font: &PdfObject) -> Result<bool, mupdf::error::Error> {
+	let subtype = font.get_dict("Subtype")?;
+	let subtype = subtype.as_ref().map(PdfObject::as_name).transpose()?;
+
+	// The glyphs of a Type3 font are part of the PDF itself, so it's always embedded
+	if subtype == Some(b"Type3") {
+		return Ok(true);
+	}
+
+	// And Type0 fonts are just a wrapper around the font that actually has the glyphs
+	let descendant = if subtype == Some(b"Type0") {
+		match font.get_dict("DescendantFonts")? {
+			Some(descendants) => descendants.get_array(0)?,
+			None => None
+		}
+	} else {
+		None
+	};
+
+	let Some(descriptor) = descendant
+		.as_ref()
+		.unwrap_or(font)
+		.get_dict("FontDescriptor")?
+	else {
+		return Ok(false);
+	};
+	for file in ["FontFile", "FontFile2", "FontFile3"] {
+		if descriptor.get_dict(file)?.is_some() {
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+// Everything that's been added on top of the page (which only PDFs can have)
+fn load_annotations(page: Page) -> Result<Vec<Annotation>, mupdf::error::Error> {
+	let Ok(page) = PdfPage::try_from(page) else {
+		return Ok(Vec::new());
+	};
+
+	let mut annotations = Vec::new();
+	for annot in page.annotations() {
+		let kind = match annot.r#type()? {
+			// Links can already be followed and form fields aren't really something that was
+			// added to the page, and popups are just where another annotation's note is shown
+			PdfAnnotationType::Link | PdfAnnotationType::Widget | PdfAnnotationType::Popup =>
+				continue,
+			PdfAnnotationType::Text => "Note",
+			PdfAnnotationType::FreeText => "Text box",
+			PdfAnnotationType::Highlight => "Highlight",
+			PdfAnnotationType::Underline => "Underline",
+			PdfAnnotationType::Squiggly => "Squiggly underline",
+			PdfAnnotationType::StrikeOut => "Strikeout",
+			PdfAnnotationType::Caret => "Insertion",
+			PdfAnnotationType::Ink => "Drawing",
+			PdfAnnotationType::Stamp => "Stamp",
+			PdfAnnotationType::Line
+			| PdfAnnotationType::Square
+			| PdfAnnotationType::Circle
+			| PdfAnnotationType::Polygon
+			| PdfAnnotationType::PolyLine => "Shape",
+			PdfAnnotationType::FileAttachment => "Attachment",
+			_ => "Annotation"
+		};
+
+		let rect = annot.rect()?;
+		annotations.push(Annotation {
+			kind,
+			author: annot.author()?.map(str::to_owned),
+			contents: annot.contents()?.map(str::to_owned),
+			rect: PdfRect {
+				x0: rect.x0,
+				y0: rect.y0,
+				x1: rect.x1,
+				y1: rect.y1
+			}
+		});
+	}
+	Ok(annotations)
+}
+
+fn load_all_annotations(
+	doc: &Document,
+	n_pages: usize
+) -> Result<Vec<(usize, Annotation)>, mupdf::error::Error> {
+	let mut annotations = Vec::new();
+	for page_num in 0..n_pages {
+		let page = doc.load_page(page_num as i32)?;
+		annotations.extend(
+			load_annotations(page)?
+				.into_iter()
+				.map(|annotation| (page_num, annotation))
+		);
+	}
+	Ok(annotations)
+}
+
+// Splits the text of the page up into words, keeping track of which line and block each word is in
+// so that the selection can move between them
+fn extract_words(page: &Page) -> Result<Vec<TextWord>, mupdf::error::Error> {
+	let text_page = page.to_text_page(TextPageFlags::empty())?;
+
+	let mut words = Vec::new();
+	let mut line_idx = 0;
+	for (block_idx, block) in text_page.blocks().enumerate() {
+		// image blocks and such don't have any text for us to select
+		if block.r#type() != TextBlockType::Text {
+			continue;
+		}
+
+		for line in block.lines() {
+			let mut word: Option<TextWord> = None;
+			for ch in line.chars() {
+				let Some(c) = ch.char() else {
+					continue;
+				};
+
+				if c.is_whitespace() {
+					words.extend(word.take());
+					continue;
+				}
+
+				let rect = char_rect(ch.quad());
+
+				match word {
+					Some(ref mut word) => {
+						word.text.push(c);
+						word.rect = word.rect.union(rect);
+					}
+					None =>
+						word = Some(TextWord {
+							text: c.to_string(),
+							rect,
+							line: line_idx,
+							block: block_idx
+						}),
+				}
+			}
+			words.extend(word);
+			line_idx += 1;
+		}
+	}
+
+	Ok(words)
+}
+
 struct PopOnNext<'a> {
 	inner: &'a mut VecDeque<usize>
 }
@@ -625,3 +1853,51 @@ impl ExactSizeIterator for PopOnNext<'_> {
 		self.inner.len()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn maps_rects_onto_rotated_and_trimmed_pages() {
+		let bounds = PdfRect {
+			x0: 0.,
+			y0: 0.,
+			x1: 100.,
+			y1: 200.
+		};
+		// Everything before (5, 10) is trimmed off
+		let shown = PdfRect {
+			x0: 5.,
+			y0: 10.,
+			x1: 100.,
+			y1: 200.
+		};
+		let word = PdfRect {
+			x0: 10.,
+			y0: 20.,
+			x1: 30.,
+			y1: 40.
+		};
+
+		for (rotate, expected) in [
+			(RotateDirection::Deg0, (10, 20, 50, 60)),
+			(RotateDirection::Deg90, (320, 10, 360, 50)),
+			(RotateDirection::Deg180, (140, 320, 180, 360)),
+			(RotateDirection::Deg270, (20, 140, 60, 180))
+		] {
+			let crop = rect_in_pixmap(shown, bounds, 2., rotate);
+			let transform = PageTransform::new(bounds, 2., rotate, (crop.ul_x, crop.ul_y));
+
+			let px = transform.to_px(word);
+			assert_eq!((px.ul_x, px.ul_y, px.lr_x, px.lr_y), expected, "{rotate:?}");
+
+			// And the middle of where it ended up goes back to the middle of the word
+			let middle = (
+				(px.ul_x + px.lr_x) as f32 / 2.,
+				(px.ul_y + px.lr_y) as f32 / 2.
+			);
+			assert_eq!(transform.to_page(middle), (20., 30.), "{rotate:?}");
+		}
+	}
+}
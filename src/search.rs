@@ -0,0 +1,151 @@
+use regex::{Regex, RegexBuilder};
+
+/// What was typed into the `/` prompt, split up into the term itself and the modifiers that were
+/// typed along with it:
+/// - `re:` at the start treats the rest of the term as a regular expression
+/// - `w:` at the start only matches whole words
+/// - `\c` or `\C` anywhere makes the search case-insensitive or case-sensitive (like in vim)
+///
+/// The prefixes can be combined in either order, e.g. `re:w:colou?r`.
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+	pub term: String,
+	pub regex: bool,
+	pub case_sensitive: bool,
+	pub whole_word: bool,
+	// The whole query (including the modifiers) as a regex, for searching through text that we
+	// already have (like in the text view)
+	pattern: Regex
+}
+
+impl SearchQuery {
+	pub fn parse(input: &str) -> Result<Self, regex::Error> {
+		let mut rest = input;
+		let (mut regex, mut whole_word) = (false, false);
+		loop {
+			if let Some(after) = rest.strip_prefix("re:") {
+				regex = true;
+				rest = after;
+			} else if let Some(after) = rest.strip_prefix("w:") {
+				whole_word = true;
+				rest = after;
+			} else {
+				break;
+			}
+		}
+
+		let mut case_sensitive = false;
+		let mut term = String::with_capacity(rest.len());
+		let mut chars = rest.chars();
+		while let Some(c) = chars.next() {
+			if c != '\\' {
+				term.push(c);
+				continue;
+			}
+
+			// If both are given, the last one wins
+			match chars.next() {
+				Some('c') => case_sensitive = false,
+				Some('C') => case_sensitive = true,
+				// This also keeps an escaped backslash (e.g. `\\c` in a regex) from having its
+				// second half mistaken for the start of a modifier
+				next => {
+					term.push(c);
+					term.extend(next);
+				}
+			}
+		}
+
+		let pattern = if regex {
+			term.clone()
+		} else {
+			regex::escape(&term)
+		};
+		let pattern = if whole_word {
+			format!(r"\b(?:{pattern})\b")
+		} else {
+			pattern
+		};
+		let pattern = RegexBuilder::new(&pattern)
+			.case_insensitive(!case_sensitive)
+			.build()?;
+
+		Ok(Self {
+			term,
+			regex,
+			case_sensitive,
+			whole_word,
+			pattern
+		})
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.term.is_empty()
+	}
+
+	/// The regex to search pages with, if mupdf can't do this search by itself. Plain
+	/// case-insensitive searches are left to mupdf, since that's all it can do.
+	#[must_use]
+	pub fn matcher(&self) -> Option<&Regex> {
+		(self.regex || self.case_sensitive || self.whole_word).then_some(&self.pattern)
+	}
+
+	#[must_use]
+	pub fn pattern(&self) -> &Regex {
+		&self.pattern
+	}
+
+	/// The modifiers that are turned on, for showing alongside the results. None if this is just a
+	/// plain search.
+	#[must_use]
+	pub fn modes(&self) -> Option<String> {
+		let modes = [
+			(self.regex, "regex"),
+			(self.case_sensitive, "case-sensitive"),
+			(self.whole_word, "whole word")
+		]
+		.into_iter()
+		.filter_map(|(on, name)| on.then_some(name))
+		.collect::<Vec<_>>();
+
+		(!modes.is_empty()).then(|| modes.join(", "))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_modifiers() {
+		let plain = SearchQuery::parse("hello world").unwrap();
+		assert_eq!(plain.term, "hello world");
+		assert!(plain.matcher().is_none());
+		assert!(plain.pattern().is_match("Hello World"));
+		assert_eq!(plain.modes(), None);
+
+		let query = SearchQuery::parse(r"w:re:colou?r\C").unwrap();
+		assert_eq!(query.term, "colou?r");
+		assert_eq!(
+			query.modes().as_deref(),
+			Some("regex, case-sensitive, whole word")
+		);
+		let matcher = query.matcher().unwrap();
+		assert!(matcher.is_match("the color red"));
+		assert!(!matcher.is_match("the Colour red"));
+		assert!(!matcher.is_match("discolored"));
+
+		// the last case modifier wins, and escaped backslashes aren't modifiers
+		let query = SearchQuery::parse(r"re:a\\c\C\c").unwrap();
+		assert_eq!(query.term, r"a\\c");
+		assert!(!query.case_sensitive);
+		assert!(query.matcher().unwrap().is_match(r"A\C"));
+
+		// without `re:`, the term is matched literally
+		let query = SearchQuery::parse(r"a.b\C").unwrap();
+		assert!(!query.matcher().unwrap().is_match("axb"));
+
+		SearchQuery::parse("re:(").unwrap_err();
+	}
+}
@@ -0,0 +1,343 @@
+/// A rect in the coordinate space of the page itself (i.e. before it's been scaled to fit on the
+/// screen), so that it stays valid even if the page is re-rendered at a different size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfRect {
+	pub x0: f32,
+	pub y0: f32,
+	pub x1: f32,
+	pub y1: f32
+}
+
+impl PdfRect {
+	#[must_use]
+	pub fn union(self, other: Self) -> Self {
+		Self {
+			x0: self.x0.min(other.x0),
+			y0: self.y0.min(other.y0),
+			x1: self.x1.max(other.x1),
+			y1: self.y1.max(other.y1)
+		}
+	}
+
+	/// The part of this rect that's also inside `other`, if there is any
+	#[must_use]
+	pub fn intersection(self, other: Self) -> Option<Self> {
+		let rect = Self {
+			x0: self.x0.max(other.x0),
+			y0: self.y0.max(other.y0),
+			x1: self.x1.min(other.x1),
+			y1: self.y1.min(other.y1)
+		};
+		(rect.x0 < rect.x1 && rect.y0 < rect.y1).then_some(rect)
+	}
+
+	// 0 if the point is inside of this rect
+	pub(crate) fn distance_to(self, (x, y): (f32, f32)) -> f32 {
+		let dx = (self.x0 - x).max(x - self.x1).max(0.);
+		let dy = (self.y0 - y).max(y - self.y1).max(0.);
+		dx.hypot(dy)
+	}
+}
+
+/// A single whitespace-separated word on a page, as extracted by mupdf
+#[derive(Debug, Clone)]
+pub struct TextWord {
+	pub text: String,
+	pub rect: PdfRect,
+	// These are indices of the line and block that this word is in, counting from the start of the
+	// page, so two words are on the same line iff their `line`s are equal
+	pub line: usize,
+	pub block: usize
+}
+
+/// What the renderer needs to know to draw a selection onto a page
+#[derive(Debug, Clone)]
+pub struct PageSelection {
+	pub page: usize,
+	pub rects: Vec<PdfRect>
+}
+
+#[derive(Clone, Copy)]
+pub enum Motion {
+	NextWord,
+	PrevWord,
+	NextLine,
+	PrevLine,
+	LineStart,
+	LineEnd,
+	NextBlock,
+	PrevBlock
+}
+
+pub struct Selection {
+	pub page: usize,
+	// None until the renderer has sent over the text of this page
+	words: Option<Vec<TextWord>>,
+	// Both of these are indices into `words`. The selection is everything between them, inclusive,
+	// and only `cursor` moves when the selection is extended
+	anchor: usize,
+	cursor: usize,
+	// If the user started dragging the mouse before we had the text of the page, we store where
+	// they started and where they are now (in page coordinates) so that we can apply it once the
+	// text shows up
+	pending_drag: Option<((f32, f32), (f32, f32))>
+}
+
+impl Selection {
+	#[must_use]
+	pub fn new(page: usize) -> Self {
+		Self {
+			page,
+			words: None,
+			anchor: 0,
+			cursor: 0,
+			pending_drag: None
+		}
+	}
+
+	pub fn set_words(&mut self, words: Vec<TextWord>) {
+		self.words = Some(words);
+		self.anchor = 0;
+		self.cursor = 0;
+
+		if let Some((start, end)) = self.pending_drag.take() {
+			self.drag(start, true);
+			self.drag(end, false);
+		}
+	}
+
+	/// Moves the cursor (and thus extends or shrinks the selection) according to `motion`
+	pub fn move_cursor(&mut self, motion: Motion) {
+		let Some(words) = self.words.as_deref().filter(|w| !w.is_empty()) else {
+			return;
+		};
+
+		let current = &words[self.cursor];
+		let on_line = |line: usize| {
+			words
+				.iter()
+				.enumerate()
+				.filter(move |(_, w)| w.line == line)
+		};
+		// When moving between lines, we want to end up at the word that's closest horizontally to
+		// where we currently are, like moving a cursor up and down in a text editor
+		let closest_on_line = |line: usize| {
+			on_line(line)
+				.min_by(|(_, a), (_, b)| {
+					(a.rect.x0 - current.rect.x0)
+						.abs()
+						.total_cmp(&(b.rect.x0 - current.rect.x0).abs())
+				})
+				.map(|(idx, _)| idx)
+		};
+
+		let new_cursor = match motion {
+			Motion::NextWord => Some((self.cursor + 1).min(words.len() - 1)),
+			Motion::PrevWord => Some(self.cursor.saturating_sub(1)),
+			Motion::NextLine => words[self.cursor..]
+				.iter()
+				.find(|w| w.line > current.line)
+				.and_then(|w| closest_on_line(w.line)),
+			Motion::PrevLine => words[..self.cursor]
+				.iter()
+				.rfind(|w| w.line < current.line)
+				.and_then(|w| closest_on_line(w.line)),
+			Motion::LineStart => on_line(current.line).next().map(|(idx, _)| idx),
+			Motion::LineEnd => on_line(current.line).next_back().map(|(idx, _)| idx),
+			Motion::NextBlock => words[self.cursor..]
+				.iter()
+				.position(|w| w.block > current.block)
+				.map(|offset| self.cursor + offset),
+			Motion::PrevBlock => {
+				let block_start = words[..self.cursor]
+					.iter()
+					.rposition(|w| w.block < current.block)
+					.map_or(0, |idx| idx + 1);
+
+				// If we're already at the start of this block, we go to the start of the one
+				// before it instead
+				if block_start == self.cursor && self.cursor > 0 {
+					let prev_block = words[self.cursor - 1].block;
+					Some(
+						words[..self.cursor]
+							.iter()
+							.rposition(|w| w.block < prev_block)
+							.map_or(0, |idx| idx + 1)
+					)
+				} else {
+					Some(block_start)
+				}
+			}
+		};
+
+		if let Some(new_cursor) = new_cursor {
+			self.cursor = new_cursor;
+		}
+	}
+
+	/// Starts a new selection at the current cursor
+	pub fn reanchor(&mut self) {
+		self.anchor = self.cursor;
+	}
+
+	/// Extends the selection to the word closest to `point` (which is in page coordinates), or
+	/// starts a new selection there if `starting`
+	pub fn drag(&mut self, point: (f32, f32), starting: bool) {
+		let Some(ref words) = self.words else {
+			let start = match self.pending_drag {
+				Some((start, _)) if !starting => start,
+				_ => point
+			};
+			self.pending_drag = Some((start, point));
+			return;
+		};
+
+		let Some(closest) = words
+			.iter()
+			.enumerate()
+			.min_by(|(_, a), (_, b)| {
+				a.rect
+					.distance_to(point)
+					.total_cmp(&b.rect.distance_to(point))
+			})
+			.map(|(idx, _)| idx)
+		else {
+			return;
+		};
+
+		self.cursor = closest;
+		if starting {
+			self.anchor = closest;
+		}
+	}
+
+	fn selected(&self) -> &[TextWord] {
+		let start = self.anchor.min(self.cursor);
+		let end = self.anchor.max(self.cursor);
+		self.words
+			.as_deref()
+			.and_then(|words| words.get(start..=end))
+			.unwrap_or_default()
+	}
+
+	/// The rects that should be highlighted to show this selection, with one rect per line (since
+	/// that looks a lot nicer than a bunch of separate little rects per word)
+	#[must_use]
+	pub fn to_page_selection(&self) -> PageSelection {
+		let mut rects: Vec<(usize, PdfRect)> = Vec::new();
+		for word in self.selected() {
+			match rects.last_mut() {
+				Some((line, rect)) if *line == word.line => *rect = rect.union(word.rect),
+				_ => rects.push((word.line, word.rect))
+			}
+		}
+
+		PageSelection {
+			page: self.page,
+			rects: rects.into_iter().map(|(_, rect)| rect).collect()
+		}
+	}
+
+	/// The selected text, with words on the same line separated by spaces, lines separated by
+	/// newlines, and blocks separated by an empty line
+	#[must_use]
+	pub fn text(&self) -> String {
+		let mut text = String::new();
+		let mut prev: Option<&TextWord> = None;
+		for word in self.selected() {
+			if let Some(prev) = prev {
+				text.push_str(if prev.block != word.block {
+					"\n\n"
+				} else if prev.line != word.line {
+					"\n"
+				} else {
+					" "
+				});
+			}
+			text.push_str(&word.text);
+			prev = Some(word);
+		}
+		text
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn word(text: &str, x: f32, line: usize, block: usize) -> TextWord {
+		TextWord {
+			text: text.to_owned(),
+			rect: PdfRect {
+				x0: x,
+				y0: line as f32 * 10.,
+				x1: x + 8.,
+				y1: (line as f32).mul_add(10., 8.)
+			},
+			line,
+			block
+		}
+	}
+
+	fn example() -> Selection {
+		let mut sel = Selection::new(0);
+		sel.set_words(vec![
+			word("the", 0., 0, 0),
+			word("quick", 10., 0, 0),
+			word("brown", 20., 0, 0),
+			word("fox", 0., 1, 0),
+			word("jumps", 10., 1, 0),
+			word("over", 0., 2, 1),
+			word("it", 10., 2, 1),
+		]);
+		sel
+	}
+
+	#[test]
+	fn motions_and_text() {
+		let mut sel = example();
+
+		sel.move_cursor(Motion::NextWord);
+		assert_eq!(sel.text(), "the quick");
+
+		sel.move_cursor(Motion::NextLine);
+		assert_eq!(sel.text(), "the quick brown\nfox jumps");
+
+		sel.move_cursor(Motion::LineStart);
+		sel.reanchor();
+		sel.move_cursor(Motion::NextBlock);
+		assert_eq!(sel.text(), "fox jumps\n\nover");
+
+		sel.move_cursor(Motion::LineEnd);
+		sel.move_cursor(Motion::PrevBlock);
+		assert_eq!(sel.text(), "fox jumps\n\nover");
+
+		sel.move_cursor(Motion::PrevBlock);
+		assert_eq!(sel.text(), "the quick brown\nfox");
+		assert_eq!(sel.to_page_selection().rects.len(), 2);
+	}
+
+	#[test]
+	fn drag_before_text_loaded() {
+		let mut sel = Selection::new(0);
+		sel.drag((11., 1.), true);
+		sel.drag((12., 11.), false);
+		assert_eq!(sel.text(), "");
+
+		let words = example().words.unwrap();
+		sel.set_words(words);
+		assert_eq!(sel.text(), "quick brown\nfox jumps");
+	}
+
+	#[test]
+	fn intersects_rects() {
+		let rect = |x0, y0, x1, y1| PdfRect { x0, y0, x1, y1 };
+		let page = rect(0., 0., 100., 200.);
+
+		assert_eq!(
+			rect(-5., 10., 50., 250.).intersection(page),
+			Some(rect(0., 10., 50., 200.))
+		);
+		assert_eq!(rect(120., 10., 150., 20.).intersection(page), None);
+	}
+}
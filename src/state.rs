@@ -0,0 +1,234 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt,
+	fs::File,
+	io::{self, Read as _, Seek as _, SeekFrom},
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::xdg_dir;
+
+// We only remember this many documents, forgetting about the ones that were opened the longest
+// ago, so that the state file doesn't just grow forever
+const MAX_DOCS: usize = 500;
+
+/// How a document was being viewed, so that it can be restored the next time it's opened
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct ViewPosition {
+	pub page: usize,
+	// The number of times the pages were rotated by 90 degrees
+	pub rotations: u8,
+	pub inverted: bool,
+	// None if the page was fit to the screen instead of filling it
+	pub zoom: Option<SavedZoom>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SavedZoom {
+	pub level: i16,
+	pub pan_from_left: u16,
+	pub pan_from_top: u16
+}
+
+/// A spot in the document that was marked with `m<letter>`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Mark {
+	pub page: usize,
+	// Only set if the page was zoomed in when the mark was set, so that jumping to it can pan to
+	// the same part of the page
+	pub zoom: Option<SavedZoom>
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct DocState {
+	// seconds since the unix epoch
+	last_opened: u64,
+	pub position: ViewPosition,
+	// Only the uppercase marks are saved, like vim does with its file marks. This is defaulted so
+	// that state files from before marks existed can still be read.
+	#[serde(default)]
+	pub marks: BTreeMap<char, Mark>
+}
+
+/// Identifies a document by both where it is and what's in it, so that a different file that's
+/// been moved to the same path doesn't get opened at some random page
+pub struct DocKey {
+	path: PathBuf,
+	key: String
+}
+
+impl DocKey {
+	pub fn new(canonical_path: &Path) -> io::Result<Self> {
+		Ok(Self {
+			key: format!(
+				"{}#{:016x}",
+				canonical_path.display(),
+				fingerprint(canonical_path)?
+			),
+			path: canonical_path.to_owned()
+		})
+	}
+
+	/// Fingerprints the document again, since it could've been rebuilt since it was opened, and
+	/// it'll be looked up by what's in it now the next time it's opened
+	pub fn refresh(&mut self) -> io::Result<()> {
+		*self = Self::new(&self.path)?;
+		Ok(())
+	}
+}
+
+// Hashing the whole file could take a while for really big documents, so we just hash its length
+// along with its start and end. For PDFs, the end contains the xref table and trailer (with the
+// document ID), so pretty much any change to the document will change this.
+pub fn fingerprint(path: &Path) -> io::Result<u64> {
+	const SAMPLE_LEN: u64 = 64 * 1024;
+
+	let mut file = File::open(path)?;
+	let len = file.metadata()?.len();
+
+	let mut sample = Vec::from(len.to_le_bytes());
+	(&mut file).take(SAMPLE_LEN).read_to_end(&mut sample)?;
+	if len > SAMPLE_LEN {
+		file.seek(SeekFrom::Start((len - SAMPLE_LEN).max(SAMPLE_LEN)))?;
+		file.take(SAMPLE_LEN).read_to_end(&mut sample)?;
+	}
+
+	Ok(fnv1a(FNV_OFFSET_BASIS, &sample))
+}
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+// FNV-1a, since it's simple and (unlike std's hasher) guaranteed to stay the same between
+// versions. `hash` is either `FNV_OFFSET_BASIS` or what this returned for the bytes before these.
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+	bytes.iter().fold(hash, |hash, byte| {
+		(hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+#[derive(Debug)]
+pub enum StateError {
+	NoStateDir,
+	Io(PathBuf, io::Error),
+	Serialize(serde_json::Error)
+}
+
+impl fmt::Display for StateError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NoStateDir =>
+				f.write_str("Couldn't find a directory to save state in; is $HOME set?"),
+			Self::Io(path, e) => write!(f, "Couldn't write to {}: {e}", path.display()),
+			Self::Serialize(e) => write!(f, "Couldn't serialize state: {e}")
+		}
+	}
+}
+
+impl std::error::Error for StateError {}
+
+fn state_file() -> Option<PathBuf> {
+	xdg_dir("XDG_STATE_HOME", &[".local", "state"]).map(|dir| dir.join("documents.json"))
+}
+
+fn read_all(path: &Path) -> HashMap<String, DocState> {
+	// If it doesn't exist yet, that's fine, and if it's unreadable or corrupted, there's nothing
+	// the user could really do about it, so we just start over instead of refusing to open the
+	// document
+	std::fs::read(path)
+		.ok()
+		.and_then(|contents| match serde_json::from_slice(&contents) {
+			Ok(docs) => Some(docs),
+			Err(e) => {
+				log::warn!("Ignoring corrupted state file at {}: {e}", path.display());
+				None
+			}
+		})
+		.unwrap_or_default()
+}
+
+/// Loads whatever we last saved for this document
+#[must_use]
+pub fn load(key: &DocKey) -> Option<DocState> {
+	let path = state_file()?;
+	read_all(&path).remove(&key.key)
+}
+
+/// Saves `state` for this document, keeping everything saved for other documents intact
+pub fn save(key: DocKey, mut state: DocState) -> Result<(), StateError> {
+	let path = state_file().ok_or(StateError::NoStateDir)?;
+	let io_err = |e| StateError::Io(path.clone(), e);
+
+	state.last_opened = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs();
+
+	// We read this again right before writing (instead of holding onto what we read on startup)
+	// so that we don't clobber anything that other instances of tdf have saved in the meantime
+	let mut docs = read_all(&path);
+	docs.insert(key.key, state);
+
+	if docs.len() > MAX_DOCS {
+		let mut by_age = docs
+			.iter()
+			.map(|(key, state)| (state.last_opened, key.clone()))
+			.collect::<Vec<_>>();
+		by_age.sort_unstable();
+		for (_, key) in by_age.into_iter().take(docs.len() - MAX_DOCS) {
+			docs.remove(&key);
+		}
+	}
+
+	if let Some(dir) = path.parent() {
+		std::fs::create_dir_all(dir).map_err(io_err)?;
+	}
+
+	// Write to a temporary file and then move it over the real one so that the file is never
+	// half-written if we're killed (or if two instances save at the same time)
+	let contents = serde_json::to_vec(&docs).map_err(StateError::Serialize)?;
+	let tmp_path = path.with_extension(format!("json.{}", std::process::id()));
+	std::fs::write(&tmp_path, contents).map_err(io_err)?;
+	std::fs::rename(&tmp_path, &path).map_err(io_err)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_state_from_before_marks() {
+		let state: DocState = serde_json::from_str(
+			r#"{"last_opened":1,"position":{"page":4,"rotations":1,"inverted":false,"zoom":null}}"#
+		)
+		.unwrap();
+		assert_eq!(state.position.page, 4);
+		assert!(state.marks.is_empty());
+
+		let mut state = state;
+		state.marks.insert('A', Mark {
+			page: 2,
+			zoom: None
+		});
+		let round_tripped: DocState =
+			serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+		assert_eq!(round_tripped.marks, state.marks);
+	}
+
+	#[test]
+	fn refreshing_picks_up_rebuilt_documents() {
+		let path = std::env::temp_dir().join(format!("tdf-doc-key-{}.pdf", std::process::id()));
+		std::fs::write(&path, "before").unwrap();
+		let mut key = DocKey::new(&path).unwrap();
+		let old = key.key.clone();
+
+		std::fs::write(&path, "after").unwrap();
+		key.refresh().unwrap();
+		let new = key.key.clone();
+		std::fs::remove_file(&path).unwrap();
+
+		assert_ne!(old, new);
+	}
+}
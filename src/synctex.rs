@@ -0,0 +1,345 @@
+use std::{
+	collections::HashMap,
+	fs::{self, File},
+	io::{self, Read as _},
+	path::{Component, Path, PathBuf}
+};
+
+use flate2::read::GzDecoder;
+
+use crate::selection::PdfRect;
+
+// TeX's scaled points are 1/65536 of a TeX point, which is 1/72.27 of an inch, while PDF points
+// are 1/72 of an inch
+const SP_PER_PDF_POINT: f32 = 65536. * 72.27 / 72.;
+
+/// Something that TeX put on a page, along with where in the source it came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SyncRecord {
+	tag: u32,
+	line: u32,
+	rect: PdfRect,
+	// Whether this is a whole box of content, as opposed to something that's just a point (like a
+	// kern or a glue), which can't tell us how much of the page is taken up by that line
+	sized: bool
+}
+
+/// The SyncTeX data for a document, which maps between lines of the TeX source files and where
+/// the things they produced ended up in the PDF
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncTex {
+	inputs: HashMap<u32, PathBuf>,
+	pages: Vec<Vec<SyncRecord>>
+}
+
+/// The files that SyncTeX data could be in for the PDF at `pdf`, in the order they're checked
+#[must_use]
+pub fn synctex_paths(pdf: &Path) -> [PathBuf; 2] {
+	[
+		pdf.with_extension("synctex.gz"),
+		pdf.with_extension("synctex")
+	]
+}
+
+impl SyncTex {
+	/// Loads the SyncTeX data that was generated alongside the PDF at `pdf`, if there is any
+	pub fn for_pdf(pdf: &Path) -> io::Result<Option<Self>> {
+		for path in synctex_paths(pdf) {
+			let contents = if path.extension().is_some_and(|ext| ext == "gz") {
+				File::open(&path).and_then(|file| {
+					let mut contents = Vec::new();
+					GzDecoder::new(file)
+						.read_to_end(&mut contents)
+						.map(|_| contents)
+				})
+			} else {
+				fs::read(&path)
+			};
+			let contents = match contents {
+				Ok(contents) => contents,
+				Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+				Err(e) => return Err(e)
+			};
+
+			// The input files are relative to wherever TeX was run, which is almost always where
+			// the PDF ended up
+			let dir = path.parent().unwrap_or_else(|| Path::new(""));
+			return Ok(Some(Self::parse(&String::from_utf8_lossy(&contents), dir)));
+		}
+		Ok(None)
+	}
+
+	/// Parses the (uncompressed) contents of a `.synctex` file. Anything that we don't understand
+	/// is skipped over, since the worst that happens without it is that a search is a bit off.
+	#[must_use]
+	pub fn parse(contents: &str, dir: &Path) -> Self {
+		let mut synctex = Self::default();
+
+		let mut unit = 1.;
+		let mut magnification = 1000.;
+		let mut offset = (0., 0.);
+		let mut in_content = false;
+		let mut page = None;
+
+		for line in contents.lines() {
+			if let Some(input) = line.strip_prefix("Input:") {
+				if let Some((tag, file)) = input.split_once(':')
+					&& let Ok(tag) = tag.parse()
+				{
+					synctex.inputs.insert(tag, normalize(&dir.join(file)));
+				}
+				continue;
+			}
+
+			if !in_content {
+				let setting = |name: &str, value: &mut f32| {
+					if let Some(new) = line.strip_prefix(name).and_then(|v| v.trim().parse().ok()) {
+						*value = new;
+					}
+				};
+				setting("Unit:", &mut unit);
+				setting("Magnification:", &mut magnification);
+				setting("X Offset:", &mut offset.0);
+				setting("Y Offset:", &mut offset.1);
+				in_content = line == "Content:";
+				continue;
+			}
+
+			let mut chars = line.chars();
+			let Some(kind) = chars.next() else {
+				continue;
+			};
+			let rest = chars.as_str();
+
+			match kind {
+				'{' => {
+					page = rest
+						.parse::<usize>()
+						.ok()
+						.and_then(|num| num.checked_sub(1));
+					if let Some(page) = page
+						&& synctex.pages.len() <= page
+					{
+						synctex.pages.resize_with(page + 1, Vec::new);
+					}
+				}
+				'}' => page = None,
+				// vboxes are left out, since they're usually a whole paragraph or page and so
+				// don't say much about any single line
+				'(' | 'h' | 'x' | 'k' | 'g' | '$' => {
+					let Some(page) = page else {
+						continue;
+					};
+					let scale = unit / SP_PER_PDF_POINT * magnification / 1000.;
+					let sized = matches!(kind, '(' | 'h');
+					if let Some(record) = parse_record(rest, sized, scale, offset) {
+						synctex.pages[page].push(record);
+					}
+				}
+				_ => ()
+			}
+		}
+
+		synctex
+	}
+
+	/// Finds where `line` of `file` ended up, as the page it's on and the area it takes up on that
+	/// page. If nothing came from that exact line (e.g. it's a comment or blank), the closest line
+	/// that did produce something is used instead.
+	#[must_use]
+	pub fn forward(&self, file: &Path, line: u32) -> Option<(usize, PdfRect)> {
+		let file = normalize(file);
+		let tags = self
+			.inputs
+			.iter()
+			.filter(|(_, input)| same_file(input, &file))
+			.map(|(tag, _)| *tag)
+			.collect::<Vec<_>>();
+
+		let from_file = || {
+			self.pages.iter().enumerate().flat_map(|(page, records)| {
+				records
+					.iter()
+					.filter(|r| r.sized && tags.contains(&r.tag))
+					.map(move |r| (page, r))
+			})
+		};
+
+		let closest = from_file()
+			.map(|(_, r)| r.line)
+			.min_by_key(|l| (l.abs_diff(line), *l))?;
+
+		let mut matching = from_file().filter(|(_, r)| r.line == closest);
+		let (page, first) = matching.next()?;
+		let rect = matching
+			.take_while(|(p, _)| *p == page)
+			.fold(first.rect, |rect, (_, r)| rect.union(r.rect));
+		Some((page, rect))
+	}
+
+	/// Finds the file and line that produced what's at `point` on `page`, preferring the smallest
+	/// box that contains the point and otherwise falling back to whatever's closest to it
+	#[must_use]
+	pub fn inverse(&self, page: usize, point: (f32, f32)) -> Option<(&Path, u32)> {
+		let records = self.pages.get(page)?;
+
+		let area = |r: &SyncRecord| (r.rect.x1 - r.rect.x0) * (r.rect.y1 - r.rect.y0);
+		let record = records
+			.iter()
+			.filter(|r| r.sized && r.rect.distance_to(point) == 0.)
+			.min_by(|a, b| area(a).total_cmp(&area(b)))
+			.or_else(|| {
+				records.iter().min_by(|a, b| {
+					a.rect
+						.distance_to(point)
+						.total_cmp(&b.rect.distance_to(point))
+				})
+			})?;
+
+		let file = self.inputs.get(&record.tag)?;
+		Some((file, record.line))
+	}
+}
+
+// Records look like `tag,line[,column]:h,v` with `:width,height,depth` after that for boxes, all
+// in TeX's units. `v` is the baseline, so the box goes up by its height and down by its depth.
+fn parse_record(record: &str, sized: bool, scale: f32, offset: (f32, f32)) -> Option<SyncRecord> {
+	let mut parts = record.split(':');
+	let mut source = parts.next()?.split(',');
+	let tag = source.next()?.parse().ok()?;
+	let line = source.next()?.parse().ok()?;
+
+	let (h, v) = parts.next()?.split_once(',')?;
+	let x = (h.parse::<f32>().ok()? + offset.0) * scale;
+	let y = (v.parse::<f32>().ok()? + offset.1) * scale;
+
+	let rect = match parts.next() {
+		Some(size) if sized => {
+			let mut size = size.split(',').map(|num| num.parse::<f32>().ok());
+			let (width, height, depth) = (size.next()??, size.next()??, size.next()??);
+			PdfRect {
+				x0: x,
+				y0: height.mul_add(-scale, y),
+				x1: width.mul_add(scale, x),
+				y1: depth.mul_add(scale, y)
+			}
+		}
+		_ => PdfRect {
+			x0: x,
+			y0: y,
+			x1: x,
+			y1: y
+		}
+	};
+
+	Some(SyncRecord {
+		tag,
+		line,
+		rect,
+		sized: sized && rect.x1 > rect.x0
+	})
+}
+
+// TeX often records paths like `/some/dir/./chapter.tex`, which wouldn't otherwise match
+fn normalize(path: &Path) -> PathBuf {
+	path.components()
+		.filter(|c| !matches!(c, Component::CurDir))
+		.collect()
+}
+
+// The editor might give us an absolute path while TeX only recorded a relative one (or the other
+// way around), so a file also matches if it's just missing some of the directories
+fn same_file(input: &Path, file: &Path) -> bool {
+	input.ends_with(file)
+		|| file.ends_with(input)
+		|| input
+			.canonicalize()
+			.is_ok_and(|input| file.canonicalize().is_ok_and(|file| input == file))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A cut-down version of what pdflatex writes for a document with a title and two paragraphs
+	// across two pages
+	const SAMPLE: &str = "SyncTeX Version:1
+Input:1:/home/me/paper/./paper.tex
+Input:2:/home/me/paper/intro.tex
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+!180
+{1
+[1,3:4736286,50000000:30000000,45000000,0
+(1,5:6578176,6578176:6578176,657817,65781
+x1,5:6578176,6578176
+k1,5:9000000,6578176:100000
+)
+(2,10:6578176,13156352:13156352,657817,0
+g2,10:7000000,13156352
+)
+]
+}1
+{2
+[1,3:4736286,50000000:30000000,45000000,0
+(2,12:6578176,6578176:3289088,657817,0
+)
+(2,12:6578176,7236000:6578176,657817,0
+)
+]
+}2
+Postamble:
+Count:12
+";
+
+	fn sample() -> SyncTex {
+		SyncTex::parse(SAMPLE, Path::new("/home/me/paper"))
+	}
+
+	#[test]
+	fn forward_finds_the_line() {
+		let synctex = sample();
+
+		let (page, rect) = synctex.forward(Path::new("paper.tex"), 5).unwrap();
+		assert_eq!(page, 0);
+		assert!((rect.x0 - 100.).abs() < 0.01, "{rect:?}");
+		assert!((rect.y0 - 90.).abs() < 0.01, "{rect:?}");
+		assert!((rect.x1 - 200.).abs() < 0.01, "{rect:?}");
+		assert!((rect.y1 - 101.).abs() < 0.01, "{rect:?}");
+
+		// Both boxes from line 12 are on page 2, so the highlight covers both of them
+		let (page, rect) = synctex
+			.forward(Path::new("/home/me/paper/intro.tex"), 12)
+			.unwrap();
+		assert_eq!(page, 1);
+		assert!((rect.x1 - 200.).abs() < 0.01, "{rect:?}");
+		assert!((rect.y1 - 110.).abs() < 0.01, "{rect:?}");
+
+		// Nothing came from line 11, so it goes to 10 (the closest one)
+		assert_eq!(
+			synctex
+				.forward(Path::new("intro.tex"), 11)
+				.map(|(page, _)| page),
+			Some(0)
+		);
+		assert_eq!(synctex.forward(Path::new("other.tex"), 5), None);
+	}
+
+	#[test]
+	fn inverse_finds_the_source() {
+		let synctex = sample();
+		let paper = Path::new("/home/me/paper/paper.tex");
+		let intro = Path::new("/home/me/paper/intro.tex");
+
+		assert_eq!(synctex.inverse(0, (150., 95.)), Some((paper, 5)));
+		assert_eq!(synctex.inverse(0, (150., 195.)), Some((intro, 10)));
+		// Outside of every box, so it's whatever's closest
+		assert_eq!(synctex.inverse(0, (110., 300.)), Some((intro, 10)));
+		assert_eq!(synctex.inverse(1, (120., 105.)), Some((intro, 12)));
+		assert_eq!(synctex.inverse(2, (120., 105.)), None);
+	}
+}
@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+use ratatui::{
+	style::{Color, Style},
+	text::{Line, Span}
+};
+use regex::Regex;
+
+use crate::selection::TextWord;
+
+const RESULT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+const ACTIVE_RESULT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::LightRed);
+
+/// Lays out the words of a page as plain text, for terminals that can't show the page as an image.
+/// Each line of the page gets its own line (wrapped to `width`), with an empty line between
+/// blocks, and anything that `search` matches is highlighted (with the `active_result`th match
+/// standing out from the rest).
+#[must_use]
+pub fn layout(
+	words: &[TextWord],
+	width: u16,
+	search: Option<&Regex>,
+	active_result: Option<usize>
+) -> Vec<Line<'static>> {
+	let width = usize::from(width).max(1);
+
+	// First, put each line back together, keeping track of where each word is in it so we know
+	// where we're allowed to wrap
+	let mut page_lines: Vec<(String, Vec<Range<usize>>)> = Vec::new();
+	let mut prev: Option<&TextWord> = None;
+	for word in words {
+		match prev {
+			Some(prev) if prev.line == word.line => (),
+			Some(prev) if prev.block != word.block => {
+				page_lines.push(Default::default());
+				page_lines.push(Default::default());
+			}
+			_ => page_lines.push(Default::default())
+		}
+		prev = Some(word);
+
+		let Some((text, word_ranges)) = page_lines.last_mut() else {
+			unreachable!()
+		};
+		if !text.is_empty() {
+			text.push(' ');
+		}
+		word_ranges.push(text.len()..text.len() + word.text.len());
+		text.push_str(&word.text);
+	}
+
+	let mut match_idx = 0;
+	let mut lines = Vec::new();
+	for (text, word_ranges) in page_lines {
+		let matches = search
+			.iter()
+			.flat_map(|search| search.find_iter(&text))
+			// Patterns like `a*` match the empty string everywhere, but there's nothing to
+			// highlight there
+			.filter(|found| !found.is_empty())
+			.map(|found| {
+				let style = if active_result == Some(match_idx) {
+					ACTIVE_RESULT_STYLE
+				} else {
+					RESULT_STYLE
+				};
+				match_idx += 1;
+				(found.range(), style)
+			})
+			.collect::<Vec<_>>();
+
+		lines.extend(
+			wrap(&text, word_ranges, width)
+				.into_iter()
+				.map(|row| highlight(&text, row, &matches))
+		);
+	}
+
+	lines
+}
+
+// Splits a line up into rows that are at most `width` chars long, breaking between words where
+// possible
+fn wrap(text: &str, word_ranges: Vec<Range<usize>>, width: usize) -> Vec<Range<usize>> {
+	let mut rows = Vec::new();
+	// where the current row starts, and how many chars are in it
+	let mut row: Option<(usize, usize)> = None;
+	let mut row_end = 0;
+
+	for Range { mut start, end } in word_ranges {
+		let mut len = slice(text, start..end).chars().count();
+
+		if let Some((row_start, row_len)) = row {
+			if row_len + 1 + len <= width {
+				row = Some((row_start, row_len + 1 + len));
+				row_end = end;
+				continue;
+			}
+			rows.push(row_start..row_end);
+		}
+
+		// Words that are too long to fit on a row by themselves get split over as many as they
+		// need
+		while len > width {
+			let split = slice(text, start..end)
+				.char_indices()
+				.nth(width)
+				.map_or(end, |(idx, _)| start + idx);
+			rows.push(start..split);
+			start = split;
+			len -= width;
+		}
+
+		row = Some((start, len));
+		row_end = end;
+	}
+
+	match row {
+		Some((row_start, _)) => rows.push(row_start..row_end),
+		// lines with nothing in them (like the ones between blocks) still need to show up
+		None => rows.push(0..0)
+	}
+	rows
+}
+
+fn highlight(text: &str, row: Range<usize>, matches: &[(Range<usize>, Style)]) -> Line<'static> {
+	let mut spans = Vec::new();
+	let mut pos = row.start;
+	for (found, style) in matches
+		.iter()
+		.filter(|(found, _)| found.start < row.end && found.end > row.start)
+	{
+		let start = found.start.max(row.start);
+		let end = found.end.min(row.end);
+		if start > pos {
+			spans.push(Span::raw(slice(text, pos..start).to_owned()));
+		}
+		spans.push(Span::styled(slice(text, start..end).to_owned(), *style));
+		pos = end;
+	}
+	if pos < row.end {
+		spans.push(Span::raw(slice(text, pos..row.end).to_owned()));
+	}
+	Line::from(spans)
+}
+
+// Every range we slice with comes from either the words or the regex matches, so they're always on
+// char boundaries
+fn slice(text: &str, range: Range<usize>) -> &str {
+	text.get(range).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{search::SearchQuery, selection::PdfRect};
+
+	fn words(text: &[(&str, usize, usize)]) -> Vec<TextWord> {
+		text.iter()
+			.map(|(text, line, block)| TextWord {
+				text: (*text).to_owned(),
+				rect: PdfRect {
+					x0: 0.,
+					y0: 0.,
+					x1: 0.,
+					y1: 0.
+				},
+				line: *line,
+				block: *block
+			})
+			.collect()
+	}
+
+	fn plain(lines: &[Line<'_>]) -> Vec<String> {
+		lines.iter().map(ToString::to_string).collect()
+	}
+
+	#[test]
+	fn wraps_lines_and_separates_blocks() {
+		let words = words(&[
+			("the", 0, 0),
+			("quick", 0, 0),
+			("brown", 0, 0),
+			("fox", 1, 0),
+			("abcdefghij", 2, 1)
+		]);
+
+		assert_eq!(plain(&layout(&words, 11, None, None)), [
+			"the quick",
+			"brown",
+			"fox",
+			"",
+			"abcdefghij"
+		]);
+		assert_eq!(plain(&layout(&words, 4, None, None)), [
+			"the", "quic", "k", "brow", "n", "fox", "", "abcd", "efgh", "ij"
+		]);
+	}
+
+	#[test]
+	fn highlights_results() {
+		let words = words(&[("one", 0, 0), ("two", 0, 0), ("one", 1, 0)]);
+		let query = SearchQuery::parse("one").unwrap();
+		let lines = layout(&words, 80, Some(query.pattern()), Some(1));
+
+		assert_eq!(lines[0].spans[0].style, RESULT_STYLE);
+		assert_eq!(lines[0].spans[1].content, " two");
+		assert_eq!(lines[1].spans[0].style, ACTIVE_RESULT_STYLE);
+	}
+}
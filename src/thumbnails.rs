@@ -0,0 +1,139 @@
+use ratatui::layout::Rect;
+
+/// How many cells (width, height) each thumbnail is rendered to fit in. Cells are about twice as
+/// tall as they are wide, so this is roughly the shape of a portrait page.
+pub const THUMBNAIL_SIZE: (u16, u16) = (18, 12);
+
+// Each thumbnail gets a border around it, with its page number in the top of it
+const BOX_W: u16 = THUMBNAIL_SIZE.0 + 2;
+const BOX_H: u16 = THUMBNAIL_SIZE.1 + 2;
+
+/// Where the cursor is in the overview, and how far down it's scrolled
+#[derive(Debug, Default)]
+pub struct ThumbnailGrid {
+	selected: usize,
+	// The first row of thumbnails that's on screen
+	first_row: usize,
+	// How many thumbnails fit across the screen on the last layout, so that moving up and down
+	// can skip a whole row
+	columns: usize
+}
+
+impl ThumbnailGrid {
+	#[must_use]
+	pub fn new(selected: usize) -> Self {
+		Self {
+			selected,
+			first_row: 0,
+			columns: 1
+		}
+	}
+
+	#[must_use]
+	pub fn selected(&self) -> usize {
+		self.selected
+	}
+
+	#[must_use]
+	pub fn columns(&self) -> usize {
+		self.columns
+	}
+
+	/// Moves the cursor by `amt` thumbnails, stopping at the first and last pages
+	pub fn move_by(&mut self, amt: isize, n_pages: usize) {
+		self.selected = self
+			.selected
+			.saturating_add_signed(amt)
+			.min(n_pages.saturating_sub(1));
+	}
+
+	/// Lays the thumbnails out in a grid that's centered in `area`, scrolling it so that the
+	/// selected one is on screen. Returns each page that's shown along with the area that its box
+	/// (including the border) takes up.
+	pub fn layout(&mut self, area: Rect, n_pages: usize) -> Vec<(usize, Rect)> {
+		let columns = (area.width / BOX_W).max(1);
+		let rows = (area.height / BOX_H).max(1);
+		self.columns = usize::from(columns);
+
+		let selected_row = self.selected / self.columns;
+		let visible_rows = usize::from(rows);
+		if selected_row < self.first_row {
+			self.first_row = selected_row;
+		} else if selected_row >= self.first_row + visible_rows {
+			self.first_row = selected_row + 1 - visible_rows;
+		}
+
+		// With too few pages to fill the screen, the grid is only as big as it needs to be
+		let total_rows = n_pages.div_ceil(self.columns);
+		let used_w = columns.min(u16::try_from(n_pages).unwrap_or(u16::MAX));
+		let used_h = rows.min(u16::try_from(total_rows).unwrap_or(u16::MAX));
+		let left = area.x + area.width.saturating_sub(used_w * BOX_W) / 2;
+		let top = area.y + area.height.saturating_sub(used_h * BOX_H) / 2;
+
+		let first = self.first_row * self.columns;
+		let last = (first + self.columns * visible_rows).min(n_pages);
+		(first..last)
+			.map(|page| {
+				let idx = page - first;
+				let col = (idx % self.columns) as u16;
+				let row = (idx / self.columns) as u16;
+				let rect = Rect {
+					x: left + col * BOX_W,
+					y: top + row * BOX_H,
+					width: BOX_W,
+					height: BOX_H
+				};
+				// So that it doesn't go off the edge if the screen is smaller than a single box
+				(page, rect.intersection(area))
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn moves_within_the_pages() {
+		let mut grid = ThumbnailGrid::new(3);
+		grid.move_by(-5, 10);
+		assert_eq!(grid.selected(), 0);
+		grid.move_by(4, 10);
+		assert_eq!(grid.selected(), 4);
+		grid.move_by(20, 10);
+		assert_eq!(grid.selected(), 9);
+	}
+
+	#[test]
+	fn scrolls_to_the_selected_page() {
+		// Room for 3 columns and 2 rows, with some left over on each side
+		let area = Rect::new(0, 0, 66, 30);
+		let mut grid = ThumbnailGrid::new(0);
+
+		let shown = grid.layout(area, 20);
+		assert_eq!(grid.columns(), 3);
+		assert_eq!(shown.len(), 6);
+		assert_eq!(shown[0], (0, Rect::new(3, 1, 20, 14)));
+		assert_eq!(shown[4], (4, Rect::new(23, 15, 20, 14)));
+
+		// Page 13 is on the fifth row, so that has to be the bottom one shown
+		grid.move_by(13, 20);
+		let shown = grid.layout(area, 20);
+		assert_eq!(shown.first().map(|(page, _)| *page), Some(9));
+		assert_eq!(shown.last().map(|(page, _)| *page), Some(14));
+
+		// And going back up only scrolls as far as it needs to
+		grid.move_by(-6, 20);
+		let shown = grid.layout(area, 20);
+		assert_eq!(shown.first().map(|(page, _)| *page), Some(6));
+
+		// With only a couple of pages, they're centered
+		let mut grid = ThumbnailGrid::new(0);
+		let shown = grid.layout(area, 2);
+		assert_eq!(shown, vec![
+			(0, Rect::new(13, 8, 20, 14)),
+			(1, Rect::new(33, 8, 20, 14))
+		]);
+	}
+}
@@ -1,7 +1,10 @@
-use std::{borrow::Cow, io::stdout, num::NonZeroUsize};
+use std::{borrow::Cow, collections::BTreeMap, io::stdout, num::NonZeroUsize};
 
 use crossterm::{
-	event::{Event, KeyCode, KeyModifiers, MouseEventKind},
+	event::{
+		Event, KeyCode, KeyEvent, KeyModifiers, KeyboardEnhancementFlags, MouseButton,
+		MouseEventKind, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags
+	},
 	execute,
 	terminal::{
 		BeginSynchronizedUpdate, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
@@ -11,7 +14,7 @@ use crossterm::{
 use kittage::display::DisplayLocation;
 use ratatui::{
 	Frame,
-	layout::{Constraint, Flex, Layout, Position, Rect},
+	layout::{Constraint, Flex, Layout, Margin, Position, Rect},
 	prelude::{Line, Text},
 	style::{Color, Style},
 	symbols::border,
@@ -20,12 +23,25 @@ use ratatui::{
 };
 use ratatui_image::{FontSize, Image};
 
+#[cfg(unix)]
+use crate::remote::RemoteStatus;
 use crate::{
 	FitOrFill,
+	annotations::{self, Annotation, AnnotationList},
 	converter::{ConvertedImage, MaybeTransferred},
+	info::DocInfo,
+	jumps::JumpList,
+	keymap::{Action, Keymap},
 	kitty::{KittyDisplay, KittyReadyToDisplay},
-	renderer::{RenderError, fill_default},
-	skip::Skip
+	outline::{Outline, OutlineEntry},
+	page_labels::PageLabels,
+	renderer::{HighlightRect, LinkTarget, PageLink, PageTransform, RenderError, fill_default},
+	search::SearchQuery,
+	selection::{Motion, PageSelection, PdfRect, Selection, TextWord},
+	skip::Skip,
+	state::{DocState, Mark, SavedZoom},
+	text_view,
+	thumbnails::{THUMBNAIL_SIZE, ThumbnailGrid}
 };
 
 pub struct Tui {
@@ -38,7 +54,40 @@ pub struct Tui {
 	prev_msg: Option<BottomMessage>,
 	rendered: Vec<RenderedInfo>,
 	page_constraints: PageConstraints,
-	showing_help_msg: bool,
+	popup: Option<Popup>,
+	showing_outline: bool,
+	outline: Outline,
+	// Every annotation in the document, along with the page it's on, in page order
+	annotations: Vec<(usize, Annotation)>,
+	annotation_list: AnnotationList,
+	// What the document calls its pages, which is what's printed on them (like `xiv` in the
+	// preface), as opposed to where they are in the document
+	page_labels: PageLabels,
+	// Only asked for when the info popup is opened, so that it's always up to date
+	doc_info: Option<DocInfo>,
+	// Set while the overview of every page's thumbnail is being shown instead of the pages
+	thumbnails: Option<ThumbnailGrid>,
+	// The labels for links that are shown while the user is picking a link to follow
+	hints: Vec<LinkHint>,
+	selection: Option<Selection>,
+	mouse: MouseState,
+	marks: BTreeMap<char, Mark>,
+	jumps: JumpList,
+	// The search result that was last jumped to with `n`/`N`, as (page, index within that page)
+	active_result: Option<(usize, usize)>,
+	// Set when we've just moved to a new search result, so that the next render can pan to it if
+	// we're zoomed in
+	reveal_active_result: bool,
+	// Somewhere on a page that was just jumped to (like where a forward search ended up or an
+	// annotation), until the next render has had a chance to pan to it (if we're zoomed in)
+	reveal_rect: Option<(usize, PdfRect)>,
+	// The last search that was made, so the text view can highlight its results
+	search: Option<SearchQuery>,
+	view: PageView,
+	// Set in continuous mode, where the pages are stacked on top of each other at the width of the
+	// screen. It's how many rows of the current page are scrolled off the top of the screen.
+	continuous: Option<u16>,
+	keymap: Keymap,
 	is_kitty: bool,
 	zoom: Option<Zoom>
 }
@@ -49,27 +98,133 @@ struct LastRender {
 	// diffing work
 	rect: Rect,
 	pages_shown: usize,
-	unused_width: u16
+	unused_width: u16,
+	shown: Vec<ShownPage>,
+	// The page that would've been shown after the last one in `shown` if it had been ready, so
+	// that it isn't thrown out to stay within the memory budget before it gets the chance to be
+	waiting_on: Option<usize>,
+	// How far the text view could be scrolled down before it runs out of text
+	text_max_scroll: u16,
+	// How many rows of text (or of pages, in continuous mode) fit on the screen
+	rows_shown: u16,
+	// How many columns the pages were laid out in, so we can tell whether both pages of a spread
+	// fit next to each other
+	pages_width: u16
+}
+
+// Where a page ended up on the screen during the last render, so that we can figure out which
+// terminal cell a given pixel of the rendered image ended up in
+#[derive(Clone, Copy)]
+struct ShownPage {
+	num: usize,
+	area: Rect,
+	// the pixel of the rendered image that was drawn at the top-left corner of `area`
+	src_x: f32,
+	src_y: f32,
+	// how many terminal cells one pixel of the rendered image takes up
+	cells_per_px_x: f32,
+	cells_per_px_y: f32
+}
+
+impl ShownPage {
+	fn unscaled(num: usize, area: Rect, font_size: FontSize) -> Self {
+		Self {
+			num,
+			area,
+			src_x: 0.,
+			src_y: 0.,
+			cells_per_px_x: 1. / f32::from(font_size.width),
+			cells_per_px_y: 1. / f32::from(font_size.height)
+		}
+	}
+
+	fn px_to_cell(&self, x: u32, y: u32) -> Option<Position> {
+		let col = (x as f32 - self.src_x) * self.cells_per_px_x;
+		let row = (y as f32 - self.src_y) * self.cells_per_px_y;
+
+		(col >= 0.
+			&& row >= 0.
+			&& col < f32::from(self.area.width)
+			&& row < f32::from(self.area.height))
+		.then(|| Position {
+			x: self.area.x + col as u16,
+			y: self.area.y + row as u16
+		})
+	}
+
+	// The inverse of `px_to_cell`, giving the pixel at the center of the cell. This doesn't check
+	// that the cell is within `area` since it's fine for a selection to be dragged past the edge
+	// of the page
+	fn cell_to_px(&self, pos: Position) -> (f32, f32) {
+		(
+			(f32::from(pos.x) - f32::from(self.area.x) + 0.5) / self.cells_per_px_x + self.src_x,
+			(f32::from(pos.y) - f32::from(self.area.y) + 0.5) / self.cells_per_px_y + self.src_y
+		)
+	}
+}
+
+#[derive(Clone, Copy)]
+enum MouseState {
+	Idle,
+	// The left button was pressed at this point (as (page, page coordinates)), so that we know
+	// where to start the selection from if they start dragging
+	Pressed(usize, (f32, f32)),
+	Dragging
+}
+
+#[derive(Clone, Copy)]
+enum PageView {
+	Images,
+	// Just the text of the page, for terminals that can't show images. `scroll` is how many lines
+	// of it are scrolled off the top of the screen
+	Text { scroll: u16 }
+}
+
+// Something drawn over the whole screen, in place of the pages
+#[derive(Clone, Copy)]
+enum Popup {
+	Help,
+	Marks,
+	Annotations,
+	Info
+}
+
+struct LinkHint {
+	label: String,
+	pos: Position,
+	target: LinkTarget
 }
 
 #[derive(Default)]
 pub enum BottomMessage {
 	#[default]
 	Help,
-	SearchResults(String),
+	SearchResults(SearchQuery),
 	Error(String),
 	Input(InputCommand),
+	Selecting,
+	Copied(usize),
 	Reloaded
 }
 
 pub enum InputCommand {
-	GoToPage(usize),
-	Search(String)
+	// Either a page number or one of the document's page labels
+	GoToPage(String),
+	Search(String),
+	FollowLink(String),
+	SetMark,
+	JumpToMark,
+	// `wrong` is set if the last password they gave us didn't work
+	Password { typed: String, wrong: bool }
 }
 
 struct PageConstraints {
 	max_wide: Option<NonZeroUsize>,
-	r_to_l: bool
+	r_to_l: bool,
+	// Whether to pair the pages up into spreads like a printed book, with `cover` (and every page
+	// before it) shown by itself
+	spread: bool,
+	cover: usize
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -139,6 +294,24 @@ impl Zoom {
 		self.cell_pan_from_left = u16::MAX;
 	}
 }
+impl From<SavedZoom> for Zoom {
+	fn from(zoom: SavedZoom) -> Self {
+		Self {
+			level: zoom.level,
+			cell_pan_from_left: zoom.pan_from_left,
+			cell_pan_from_top: zoom.pan_from_top
+		}
+	}
+}
+impl From<Zoom> for SavedZoom {
+	fn from(zoom: Zoom) -> Self {
+		Self {
+			level: zoom.level,
+			pan_from_left: zoom.cell_pan_from_left,
+			pan_from_top: zoom.cell_pan_from_top
+		}
+	}
+}
 #[derive(Clone, Copy, Debug)]
 enum Direction {
 	Up,
@@ -170,22 +343,65 @@ impl Direction {
 pub struct RenderedInfo {
 	// The image, if it has been rendered by `Converter` to that struct
 	img: Option<ConvertedImage>,
+	// A much smaller version of the image, for the overview. This is kept separately so that
+	// switching to and from the overview doesn't make us wait for the pages to be rendered again
+	thumbnail: Option<ConvertedImage>,
 	// The number of results for the current search term that have been found on this page. None if
 	// we haven't checked this page yet
 	// Also this isn't the most efficient representation of this value, but it's accurate, so like
 	// whatever I guess
-	num_results: Option<usize>
+	num_results: Option<usize>,
+	// Where each search result is on the rendered image, so we can pan over to them
+	result_rects: Vec<HighlightRect>,
+	links: Vec<PageLink>,
+	// How this page's coordinates were mapped onto the image it was rendered to, so we can map
+	// from the screen back to the page
+	transform: Option<PageTransform>,
+	// Only extracted for pages that are shown in the text view
+	text: PageText
+}
+
+impl RenderedInfo {
+	// Where `rect` ended up on the rendered image, in the same pixels as `result_rects`
+	fn px_rect(&self, rect: PdfRect) -> Option<HighlightRect> {
+		self.transform.map(|transform| transform.to_px(rect))
+	}
+}
+
+#[derive(Default)]
+enum PageText {
+	#[default]
+	NotRequested,
+	Requested,
+	Extracted(Vec<TextWord>)
+}
+
+/// The documents that are open, for drawing the tab bar
+#[derive(Clone, Copy)]
+pub struct Tabs<'a> {
+	pub names: &'a [String],
+	// Which of them are waiting on a password, which can only be typed in once they're switched to
+	pub need_password: &'a [bool],
+	pub active: usize
 }
 
 #[derive(PartialEq)]
 pub struct RenderLayout {
 	pub page_area: Rect,
-	pub top_and_bottom: Option<(Rect, Rect)>
+	pub top_and_bottom: Option<(Rect, Rect)>,
+	pub outline: Option<Rect>
 }
 
 impl Tui {
 	#[must_use]
-	pub fn new(name: String, max_wide: Option<NonZeroUsize>, r_to_l: bool, is_kitty: bool) -> Self {
+	pub fn new(
+		name: String,
+		max_wide: Option<NonZeroUsize>,
+		r_to_l: bool,
+		cover_page: NonZeroUsize,
+		is_kitty: bool,
+		keymap: Keymap
+	) -> Self {
 		Self {
 			name,
 			page: 0,
@@ -193,20 +409,41 @@ impl Tui {
 			bottom_msg: BottomMessage::Help,
 			last_render: LastRender::default(),
 			rendered: vec![],
-			page_constraints: PageConstraints { max_wide, r_to_l },
-			showing_help_msg: false,
+			page_constraints: PageConstraints {
+				max_wide,
+				r_to_l,
+				spread: false,
+				cover: cover_page.get() - 1
+			},
+			popup: None,
+			showing_outline: false,
+			outline: Outline::default(),
+			annotations: Vec::new(),
+			annotation_list: AnnotationList::default(),
+			page_labels: PageLabels::default(),
+			doc_info: None,
+			thumbnails: None,
+			hints: Vec::new(),
+			selection: None,
+			mouse: MouseState::Idle,
+			marks: BTreeMap::new(),
+			jumps: JumpList::default(),
+			active_result: None,
+			reveal_active_result: false,
+			reveal_rect: None,
+			search: None,
+			view: PageView::Images,
+			continuous: None,
+			keymap,
 			is_kitty,
 			zoom: None
 		}
 	}
 
 	#[must_use]
-	pub fn main_layout(frame: &Frame<'_>, fullscreened: bool) -> RenderLayout {
-		if fullscreened {
-			RenderLayout {
-				page_area: frame.area(),
-				top_and_bottom: None
-			}
+	pub fn main_layout(&self, frame: &Frame<'_>, fullscreened: bool) -> RenderLayout {
+		let (page_area, top_and_bottom) = if fullscreened {
+			(frame.area(), None)
 		} else {
 			let layout = Layout::default()
 				.constraints([
@@ -218,13 +455,30 @@ impl Tui {
 				.vertical_margin(1)
 				.split(frame.area());
 
+			(layout[1], Some((layout[0], layout[2])))
+		};
+
+		if self.showing_outline {
+			let [outline, page_area] =
+				Layout::horizontal([Constraint::Percentage(30), Constraint::Fill(1)])
+					.spacing(1)
+					.areas(page_area);
+
 			RenderLayout {
-				page_area: layout[1],
-				top_and_bottom: Some((layout[0], layout[2]))
+				page_area,
+				top_and_bottom,
+				outline: Some(outline)
+			}
+		} else {
+			RenderLayout {
+				page_area,
+				top_and_bottom,
+				outline: None
 			}
 		}
 	}
 
+	#[expect(clippy::too_many_arguments)]
 	fn render_zoomed<'s>(
 		// area of the 'fit-screen' page
 		mut img_area: Rect,
@@ -233,7 +487,9 @@ impl Tui {
 		img: &'s mut MaybeTransferred,
 		page_num: usize,
 		img_cell_w: u16,
-		img_cell_h: u16
+		img_cell_h: u16,
+		// A search result that should be panned to, if it isn't already on screen
+		reveal: Option<&HighlightRect>
 	) -> KittyDisplay<'s> {
 		log::debug!("zoom is {zoom:#?}");
 		log::debug!("page area is {img_area:#?}");
@@ -352,6 +608,38 @@ impl Tui {
 		let width = (img_section_w * f32::from(font_size.width)) as u32;
 		let height = (img_section_h * f32::from(font_size.height)) as u32;
 
+		if let Some(rect) = reveal {
+			// Centers the result along this axis if any part of it is off-screen
+			fn reveal_axis(
+				pan: &mut u16,
+				start_px: u32,
+				end_px: u32,
+				px_per_cell: u16,
+				shown: f32
+			) {
+				let start = start_px as f32 / f32::from(px_per_cell);
+				let end = end_px as f32 / f32::from(px_per_cell);
+				if start < f32::from(*pan) || end > f32::from(*pan) + shown {
+					*pan = ((start + end - shown) / 2.).max(0.) as u16;
+				}
+			}
+
+			reveal_axis(
+				&mut zoom.cell_pan_from_left,
+				rect.ul_x,
+				rect.lr_x,
+				font_size.width,
+				img_section_w
+			);
+			reveal_axis(
+				&mut zoom.cell_pan_from_top,
+				rect.ul_y,
+				rect.lr_y,
+				font_size.height,
+				img_section_h
+			);
+		}
+
 		zoom.cell_pan_from_left = zoom
 			.cell_pan_from_left
 			.min(img_cell_w.saturating_sub(img_section_w.ceil() as u16));
@@ -383,27 +671,109 @@ impl Tui {
 		&'s mut self,
 		frame: &mut Frame<'_>,
 		full_layout: &RenderLayout,
-		font_size: FontSize
+		font_size: FontSize,
+		tabs: Tabs<'_>
 	) -> KittyDisplay<'s> {
-		if self.showing_help_msg {
-			self.render_help_msg(frame);
-			return KittyDisplay::ClearImages;
+		match self.popup {
+			Some(Popup::Help) => {
+				self.render_help_msg(frame);
+				return KittyDisplay::ClearImages;
+			}
+			Some(Popup::Marks) => {
+				self.render_marks(frame);
+				return KittyDisplay::ClearImages;
+			}
+			Some(Popup::Annotations) => {
+				self.annotation_list
+					.render(frame, &self.annotations, &self.keymap);
+				return KittyDisplay::ClearImages;
+			}
+			Some(Popup::Info) => {
+				self.render_info(frame);
+				return KittyDisplay::ClearImages;
+			}
+			None => ()
 		}
 
 		if let Some(t_and_b) = full_layout.top_and_bottom {
 			Self::render_top_and_bottom(
 				t_and_b,
 				self.page,
+				&self.page_labels,
 				&self.rendered,
-				&self.name,
+				&self.annotations,
+				tabs,
 				frame,
-				&self.bottom_msg
+				&self.bottom_msg,
+				self.active_result
 			);
 		}
 
+		if let Some(outline_area) = full_layout.outline {
+			self.outline.render(frame, outline_area);
+		}
+
 		let mut img_area = full_layout.page_area;
 
 		let size = frame.area();
+		if let PageView::Text { ref mut scroll } = self.view {
+			let text_area = img_area.inner(Margin {
+				horizontal: 1,
+				vertical: 0
+			});
+
+			let max_scroll = match self.rendered.get(self.page).map(|r| &r.text) {
+				Some(PageText::Extracted(words)) if words.is_empty() => {
+					frame.render_widget(
+						Paragraph::new("This page doesn't have any text")
+							.style(Style::new().fg(Color::DarkGray)),
+						text_area
+					);
+					0
+				}
+				Some(PageText::Extracted(words)) => {
+					let active_result = self
+						.active_result
+						.filter(|(page, _)| *page == self.page)
+						.map(|(_, idx)| idx);
+					let lines = text_view::layout(
+						words,
+						text_area.width,
+						self.search.as_ref().map(SearchQuery::pattern),
+						active_result
+					);
+
+					let max_scroll = u16::try_from(lines.len())
+						.unwrap_or(u16::MAX)
+						.saturating_sub(text_area.height);
+					*scroll = (*scroll).min(max_scroll);
+					frame.render_widget(Paragraph::new(lines).scroll((*scroll, 0)), text_area);
+					max_scroll
+				}
+				Some(PageText::NotRequested | PageText::Requested) | None => {
+					Self::render_loading_in(frame, text_area);
+					0
+				}
+			};
+
+			let display = if size == self.last_render.rect {
+				KittyDisplay::NoChange
+			} else {
+				// Any images that were shown before we switched to this view need to be cleared
+				KittyDisplay::ClearImages
+			};
+			self.last_render = LastRender {
+				rect: size,
+				pages_shown: 1,
+				unused_width: 0,
+				shown: Vec::new(),
+				text_max_scroll: max_scroll,
+				rows_shown: text_area.height,
+				..LastRender::default()
+			};
+			return display;
+		}
+
 		if size == self.last_render.rect {
 			// If we haven't resized (and haven't used the Rect as a way to mark that we need to
 			// resize this time), then go through every element in the buffer where any Image would
@@ -413,6 +783,19 @@ impl Tui {
 			return KittyDisplay::NoChange;
 		}
 
+		if let Some(ref mut grid) = self.thumbnails {
+			self.last_render = LastRender {
+				rect: size,
+				pages_shown: 1,
+				..LastRender::default()
+			};
+			return Self::render_thumbnails(frame, img_area, grid, &mut self.rendered);
+		}
+
+		if let Some(row) = self.continuous {
+			return self.render_continuous(frame, img_area, font_size, row);
+		}
+
 		if let Some(ref mut zoom) = self.zoom {
 			// yes this is ugly and I hate it. it's due to the limitations that currently exist
 			// in the borrow checker. Once `-Zpolonius=next` is stabilized, we can rework this
@@ -420,11 +803,28 @@ impl Tui {
 			// See https://github.com/rust-lang/rfcs/blob/master/text/2094-nll.md#problem-case-3-conditional-control-flow-across-functions
 			// You can also rewrite this to just if an `if let` and run it under
 			// `RUSTFLAGS="-Zpolonius=next"` and see that it works
-			if self.rendered[self.page]
-				.img
-				.as_ref()
+			if self
+				.rendered
+				.get(self.page)
+				.and_then(|r| r.img.as_ref())
 				.is_some_and(|c| matches!(c, ConvertedImage::Kitty { .. }))
 			{
+				let reveal = self
+					.active_result
+					.filter(|(page, _)| self.reveal_active_result && *page == self.page)
+					.and_then(|(page, idx)| self.rendered[page].result_rects.get(idx))
+					.cloned()
+					.or_else(|| {
+						self.reveal_rect
+							.filter(|(page, _)| *page == self.page)
+							.and_then(|(page, rect)| self.rendered[page].px_rect(rect))
+					});
+				// If the page hasn't been rendered with its results yet, we'll try again once it has
+				if reveal.is_some() {
+					self.reveal_active_result = false;
+					self.reveal_rect = None;
+				}
+
 				let Some(ConvertedImage::Kitty {
 					ref mut img,
 					cell_w,
@@ -434,41 +834,93 @@ impl Tui {
 					unreachable!()
 				};
 
+				let display = Self::render_zoomed(
+					img_area,
+					font_size,
+					zoom,
+					img,
+					self.page,
+					cell_w,
+					cell_h,
+					reveal.as_ref()
+				);
+
+				let shown = match display {
+					KittyDisplay::DisplayImages(ref imgs) => imgs
+						.iter()
+						.map(|img| ShownPage {
+							num: img.page_num,
+							area: Rect {
+								x: img.pos.x,
+								y: img.pos.y,
+								width: img.display_loc.columns,
+								height: img.display_loc.rows
+							},
+							src_x: img.display_loc.x as f32,
+							src_y: img.display_loc.y as f32,
+							cells_per_px_x: f32::from(img.display_loc.columns)
+								/ img.display_loc.width as f32,
+							cells_per_px_y: f32::from(img.display_loc.rows)
+								/ img.display_loc.height as f32
+						})
+						.collect(),
+					_ => Vec::new()
+				};
+
 				self.last_render = LastRender {
 					rect: size,
 					pages_shown: 1,
-					unused_width: 0
+					unused_width: 0,
+					shown,
+					..LastRender::default()
 				};
-				return Self::render_zoomed(
-					img_area, font_size, zoom, img, self.page, cell_w, cell_h
-				);
+
+				Self::render_hints(frame, &self.hints, &self.bottom_msg);
+				return display;
 			}
 		}
 
 		// here we calculate how many pages can fit in the available area.
 		let mut test_area_w = img_area.width;
+		self.last_render.pages_width = img_area.width;
 		// go through our pages, starting at the first one we want to view
-		let mut page_sizes = self.rendered[self.page..]
+		// This is empty until we know how many pages there are, which could take a while if we're
+		// waiting on the document's password
+		// If we're showing spreads, we might be on the second page of one, in which case we need to
+		// start from the page before
+		let (first_page, max_shown) = match self.spread_of(self.page) {
+			Some((first, len)) => (first, Some(len)),
+			None => (
+				self.page,
+				self.page_constraints.max_wide.map(NonZeroUsize::get)
+			)
+		};
+		let waiting_on = self
+			.rendered
+			.get(first_page..)
+			.unwrap_or_default()
+			.iter()
+			.take(max_shown.unwrap_or(usize::MAX))
+			.position(|page| page.img.is_none())
+			.map(|idx| idx + first_page);
+		let mut page_sizes = self
+			.rendered
+			.get_mut(first_page..)
+			.unwrap_or_default()
 			.iter_mut()
 			// and get this to represent a count of how many we're looking at so far to render
 			.enumerate()
 			// and only take as many as are ready to be rendered
-			.take_while(|(idx, page)| {
-				let mut take = page.img.is_some();
-				if let Some(max) = self.page_constraints.max_wide {
-					take &= *idx < max.get();
-				}
-				take
-			})
+			.take_while(|(idx, page)| page.img.is_some() && max_shown.is_none_or(|max| *idx < max))
 			// and map it to their width (in cells on the terminal, not pixels)
-			.filter_map(|(_, page)| {
+			.filter_map(|(idx, page)| {
 				page.img.as_mut().map(|img| {
 					let (w, h) = img.w_h();
-					(w, h, img)
+					(w, h, img, idx + first_page)
 				})
 			})
 			// and then take them as long as they won't overflow the available area.
-			.take_while(|(width, _, _)| match test_area_w.checked_sub(*width) {
+			.take_while(|(width, _, _, _)| match test_area_w.checked_sub(*width) {
 				Some(new_val) => {
 					test_area_w = new_val;
 					true
@@ -476,6 +928,9 @@ impl Tui {
 				None => false
 			})
 			.collect::<Vec<_>>();
+		// If the pages ran out of room before getting to it, it wouldn't be shown anyways
+		self.last_render.waiting_on =
+			waiting_on.filter(|page| *page == first_page + page_sizes.len());
 
 		if self.page_constraints.r_to_l {
 			page_sizes.reverse();
@@ -488,7 +943,7 @@ impl Tui {
 		} else {
 			execute!(stdout(), BeginSynchronizedUpdate).unwrap();
 
-			let total_width = page_sizes.iter().map(|(w, _, _)| w).sum::<u16>();
+			let total_width = page_sizes.iter().map(|(w, _, _, _)| w).sum::<u16>();
 
 			self.last_render.pages_shown = page_sizes.len();
 
@@ -496,29 +951,40 @@ impl Tui {
 			self.last_render.unused_width = unused_width;
 			img_area.x += unused_width / 2;
 
-			if let Some(total_height) = page_sizes.iter().map(|(_, h, _)| h).max() {
+			if let Some(total_height) = page_sizes.iter().map(|(_, h, _, _)| h).max() {
 				// This subtraction might sporadicly fail while shrinking the window.
 				if let Some(unused_height) = img_area.height.checked_sub(*total_height) {
 					img_area.y += unused_height / 2;
 				}
 			}
 
+			self.last_render.shown.clear();
 			let to_display = page_sizes
 				.into_iter()
-				.enumerate()
-				.filter_map(|(idx, (width, _, img))| {
-					let maybe_img =
-						Self::render_single_page(frame, img, Rect { width, ..img_area });
+				.filter_map(|(width, height, img, page_num)| {
+					let page_area = Rect {
+						width,
+						height,
+						..img_area
+					};
+					self.last_render
+						.shown
+						.push(ShownPage::unscaled(page_num, page_area, font_size));
+
+					let maybe_img = Self::render_single_page(frame, img, page_area);
 					img_area.x += width;
 					maybe_img.map(|(img, pos)| KittyReadyToDisplay {
 						img,
-						page_num: idx + self.page,
+						page_num,
 						pos,
 						display_loc: DisplayLocation::default()
 					})
 				})
 				.collect::<Vec<_>>();
 
+			// The hints need to be drawn on top of the pages, so they have to come after them
+			Self::render_hints(frame, &self.hints, &self.bottom_msg);
+
 			// we want to set this at the very end so it doesn't get set somewhere halfway through and
 			// then the whole diffing thing messes it up
 			self.last_render.rect = size;
@@ -527,14 +993,186 @@ impl Tui {
 		}
 	}
 
+	// Stacks the pages on top of each other, starting `row` rows into the current one, and cropping
+	// the ones that are only partly on screen
+	fn render_continuous<'s>(
+		&'s mut self,
+		frame: &mut Frame<'_>,
+		img_area: Rect,
+		font_size: FontSize,
+		mut row: u16
+	) -> KittyDisplay<'s> {
+		execute!(stdout(), BeginSynchronizedUpdate).unwrap();
+
+		self.last_render.shown.clear();
+		self.last_render.pages_shown = 0;
+		self.last_render.waiting_on = None;
+
+		let mut y = img_area.y;
+		let mut to_display = Vec::new();
+		for (page_num, page) in self.rendered.iter_mut().enumerate().skip(self.page) {
+			if y >= img_area.bottom() {
+				break;
+			}
+			self.last_render.pages_shown += 1;
+
+			let Some(img) = page.img.as_mut() else {
+				self.last_render.waiting_on = Some(page_num);
+				// We don't know how tall this page is going to be, so nothing after it can be
+				// placed yet either
+				Self::render_loading_in(frame, Rect {
+					y,
+					height: img_area.bottom() - y,
+					..img_area
+				});
+				break;
+			};
+
+			let (cell_w, cell_h) = img.w_h();
+			// Only the first page is scrolled into
+			let skip = std::mem::take(&mut row).min(cell_h);
+			let rows = (cell_h - skip).min(img_area.bottom() - y);
+			let area = Rect {
+				x: img_area.x + img_area.width.saturating_sub(cell_w) / 2,
+				y,
+				width: cell_w.min(img_area.width),
+				height: rows
+			};
+			y += rows;
+
+			let src_y = u32::from(skip) * u32::from(font_size.height);
+			self.last_render.shown.push(ShownPage {
+				src_y: src_y as f32,
+				..ShownPage::unscaled(page_num, area, font_size)
+			});
+
+			let prot = match img {
+				ConvertedImage::Kitty { img, .. } => {
+					to_display.push(KittyReadyToDisplay {
+						img,
+						page_num,
+						pos: Position {
+							x: area.x,
+							y: area.y
+						},
+						display_loc: DisplayLocation {
+							y: src_y,
+							height: u32::from(rows) * u32::from(font_size.height),
+							columns: area.width,
+							rows,
+							..DisplayLocation::default()
+						}
+					});
+					continue;
+				}
+				ConvertedImage::Croppable(src) => src.cropped(skip..skip + rows),
+				ConvertedImage::Generic(prot) if rows == cell_h => Some(&*prot),
+				// This was converted before we switched to continuous mode, so it'll be replaced by
+				// one that can be cropped soon
+				ConvertedImage::Generic(_) => None
+			};
+			match prot {
+				Some(prot) => frame.render_widget(Image::new(prot), area),
+				None => Self::render_loading_in(frame, area)
+			}
+		}
+
+		// This only happens before we know how many pages there are
+		if self.last_render.pages_shown == 0 {
+			Self::render_loading_in(frame, img_area);
+			self.last_render.pages_shown = 1;
+		}
+		self.last_render.unused_width = 0;
+		self.last_render.rows_shown = img_area.height;
+
+		Self::render_hints(frame, &self.hints, &self.bottom_msg);
+
+		self.last_render.rect = frame.area();
+		KittyDisplay::DisplayImages(to_display)
+	}
+
+	// Draws each thumbnail in its own box, with its page number at the top and how many search
+	// results are on it at the bottom
+	fn render_thumbnails<'s>(
+		frame: &mut Frame<'_>,
+		area: Rect,
+		grid: &mut ThumbnailGrid,
+		rendered: &'s mut [RenderedInfo]
+	) -> KittyDisplay<'s> {
+		let selected = grid.selected();
+		let boxes = grid.layout(area, rendered.len());
+		// This is empty until we know how many pages there are
+		let Some(&(first, _)) = boxes.first() else {
+			Self::render_loading_in(frame, area);
+			return KittyDisplay::ClearImages;
+		};
+
+		execute!(stdout(), BeginSynchronizedUpdate).unwrap();
+
+		let mut to_display = Vec::new();
+		for ((page_num, box_area), page) in boxes.into_iter().zip(&mut rendered[first..]) {
+			let border_color = if page_num == selected {
+				Color::Blue
+			} else {
+				Color::DarkGray
+			};
+			let mut block = Block::new()
+				.title(format!(" {} ", page_num + 1))
+				.borders(Borders::ALL)
+				.border_set(border::ROUNDED)
+				.border_style(border_color);
+			if let Some(hits) = page.num_results.filter(|n| *n > 0) {
+				let label = if hits == 1 { "hit" } else { "hits" };
+				block = block.title_bottom(
+					Line::styled(format!(" {hits} {label} "), Style::new().fg(Color::Yellow))
+						.right_aligned()
+				);
+			}
+			let inner = block.inner(box_area);
+			frame.render_widget(block, box_area);
+
+			let Some(img) = page.thumbnail.as_mut() else {
+				Self::render_loading_in(frame, inner);
+				continue;
+			};
+
+			let (width, height) = img.w_h();
+			let img_area = Rect {
+				x: inner.x + inner.width.saturating_sub(width) / 2,
+				y: inner.y + inner.height.saturating_sub(height) / 2,
+				width: width.min(inner.width),
+				height: height.min(inner.height)
+			};
+			if let Some((img, pos)) = Self::render_single_page(frame, img, img_area) {
+				to_display.push(KittyReadyToDisplay {
+					img,
+					page_num,
+					pos,
+					display_loc: DisplayLocation::default()
+				});
+			}
+		}
+
+		KittyDisplay::DisplayImages(to_display)
+	}
+
 	fn render_single_page<'img>(
 		frame: &mut Frame<'_>,
 		page_img: &'img mut ConvertedImage,
 		img_area: Rect
 	) -> Option<(&'img mut MaybeTransferred, Position)> {
+		let (_, cell_h) = page_img.w_h();
 		match page_img {
-			ConvertedImage::Generic(page_img) => {
-				frame.render_widget(Image::new(page_img), img_area);
+			ConvertedImage::Generic(prot) => {
+				frame.render_widget(Image::new(prot), img_area);
+				None
+			}
+			// Continuous mode was just turned off, so this'll be replaced by a page that can be
+			// shown without cropping soon
+			ConvertedImage::Croppable(src) => {
+				if let Some(prot) = src.cropped(0..cell_h) {
+					frame.render_widget(Image::new(prot), img_area);
+				}
 				None
 			}
 			ConvertedImage::Kitty {
@@ -548,6 +1186,33 @@ impl Tui {
 		}
 	}
 
+	fn render_hints(frame: &mut Frame<'_>, hints: &[LinkHint], bottom_msg: &BottomMessage) {
+		let BottomMessage::Input(InputCommand::FollowLink(typed)) = bottom_msg else {
+			return;
+		};
+
+		let frame_area = frame.area();
+		for hint in hints.iter().filter(|h| h.label.starts_with(typed.as_str())) {
+			// labels are always ascii, so we know this won't split a character
+			let (already_typed, remaining) = hint.label.split_at(typed.len());
+			let line = Line::from(vec![
+				Span::styled(
+					already_typed,
+					Style::new().fg(Color::DarkGray).bg(Color::Yellow)
+				),
+				Span::styled(remaining, Style::new().fg(Color::Black).bg(Color::Yellow)),
+			]);
+
+			let hint_area = Rect {
+				x: hint.pos.x,
+				y: hint.pos.y,
+				width: hint.label.len() as u16,
+				height: 1
+			};
+			frame.render_widget(line, hint_area.intersection(frame_area));
+		}
+	}
+
 	fn render_loading_in(frame: &mut Frame<'_>, area: Rect) {
 		const LOADING_STR: &str = "Loading...";
 		let inner_space =
@@ -578,10 +1243,22 @@ impl Tui {
 		}
 
 		let old = self.page;
-		match change {
-			PageChange::Next =>
-				self.set_page((self.page + diff).min(self.rendered.len().saturating_sub(1))),
-			PageChange::Prev => self.set_page(self.page.saturating_sub(diff))
+		if let Some((first, len)) = self.spread_of(self.page) {
+			// Spreads are always moved through whole, no matter how far we were asked to go
+			let new = match change {
+				PageChange::Next => Some(first + len).filter(|page| *page < self.rendered.len()),
+				PageChange::Prev => first
+					.checked_sub(1)
+					.and_then(|prev| self.spread_of(prev))
+					.map(|(prev_first, _)| prev_first)
+			};
+			self.set_page(new.unwrap_or(old));
+		} else {
+			match change {
+				PageChange::Next =>
+					self.set_page((self.page + diff).min(self.rendered.len().saturating_sub(1))),
+				PageChange::Prev => self.set_page(self.page.saturating_sub(diff))
+			}
 		}
 
 		// Yes these conversions could wrap around if you have > isize::MAX pages, but we already
@@ -592,9 +1269,91 @@ impl Tui {
 		}
 	}
 
+	// The spread that `page` is part of, as (its first page, how many pages are in it), if we're
+	// showing spreads at all. The cover and every page before it are shown alone, and the rest
+	// are paired up after it. If the two pages of a spread are too wide to fit next to each other,
+	// they're each shown (and moved through) on their own instead.
+	fn spread_of(&self, page: usize) -> Option<(usize, usize)> {
+		let PageConstraints { spread, cover, .. } = self.page_constraints;
+		// These only ever show one page at a time
+		let single_page = self.zoom.is_some()
+			|| self.continuous.is_some()
+			|| !matches!(self.view, PageView::Images);
+		if !spread || single_page {
+			return None;
+		}
+
+		if page <= cover {
+			return Some((page, 1));
+		}
+		let first = page - (page - cover - 1) % 2;
+		// The last page doesn't have anything to pair up with if it's an odd one out
+		let len = self.rendered.len().saturating_sub(first).clamp(1, 2);
+
+		// Pages that haven't been converted yet (or a layout we haven't drawn yet) are assumed to
+		// fit, so that we don't jump around once they're ready
+		let width = self
+			.rendered
+			.get(first..first + len)
+			.unwrap_or_default()
+			.iter()
+			.filter_map(|r| r.img.as_ref())
+			.map(|img| img.w_h().0)
+			.sum::<u16>();
+		let pages_width = self.last_render.pages_width;
+		Some(if pages_width == 0 || width <= pages_width {
+			(first, len)
+		} else {
+			(page, 1)
+		})
+	}
+
+	/// Pairs the pages up into spreads like a printed book
+	pub fn use_spreads(&mut self) {
+		self.page_constraints.spread = true;
+	}
+
 	pub fn set_n_pages(&mut self, n_pages: usize) {
 		fill_default(&mut self.rendered, n_pages);
 		self.page = self.page.min(n_pages - 1);
+
+		// The renderer forgets about the selection when the document is reloaded, since the text
+		// it was made on could be gone, so we need to as well
+		if self.selection.take().is_some() {
+			self.set_msg(MessageSetting::Pop);
+		}
+	}
+
+	/// Puts us back at the page and zoom that the document was last viewed with (and brings back
+	/// the marks that were saved with it), returning how the renderer should size the pages to
+	/// match. If the saved page doesn't exist anymore, it gets clamped once we learn how many pages
+	/// there are.
+	pub fn restore_state(&mut self, state: &DocState) -> FitOrFill {
+		self.set_page(state.position.page);
+		self.marks.extend(&state.marks);
+
+		// Zooming is only supported with the kitty protocol, so if this was saved while using a
+		// different terminal, we just ignore it. It's also ignored if we were asked to start in
+		// continuous mode, since the two can't be used together.
+		match state.position.zoom {
+			_ if self.continuous.is_some() => FitOrFill::FitWidth,
+			Some(zoom) if self.is_kitty => {
+				self.zoom = Some(zoom.into());
+				FitOrFill::Fill
+			}
+			_ => FitOrFill::Fit
+		}
+	}
+
+	pub fn record_state(&self, state: &mut DocState) {
+		state.position.page = self.page;
+		state.position.zoom = self.zoom.map(SavedZoom::from);
+		state.marks = self
+			.marks
+			.iter()
+			.filter(|(name, _)| name.is_ascii_uppercase())
+			.map(|(name, mark)| (*name, *mark))
+			.collect();
 	}
 
 	pub fn page_ready(&mut self, img: ConvertedImage, page_num: usize, num_results: usize) {
@@ -602,13 +1361,16 @@ impl Tui {
 		// within the range where it might've been rendered with the last shown pages, then reset
 		// the last rect marker so that all images are forced to redraw on next render and this one
 		// is drawn with them
-		if page_num >= self.page && page_num <= self.page + self.last_render.pages_shown {
+		let first_shown = self
+			.spread_of(self.page)
+			.map_or(self.page, |(first, _)| first);
+		if page_num >= first_shown && page_num <= first_shown + self.last_render.pages_shown {
 			self.last_render.rect = Rect::default();
 		} else {
 			let img_w = img.w_h().0;
 			if img_w <= self.last_render.unused_width {
 				let num_fit = self.last_render.unused_width / img_w;
-				if page_num >= self.page && (self.page + num_fit as usize) >= page_num {
+				if page_num >= first_shown && (first_shown + num_fit as usize) >= page_num {
 					self.last_render.rect = Rect::default();
 				}
 			}
@@ -617,100 +1379,870 @@ impl Tui {
 		// We always just set this here because we handle reloading in the `set_n_pages` function.
 		// If the document was reloaded, then It'll have the `set_n_pages` called to set the new
 		// number of pages, so the vec will already be cleared
-		self.rendered[page_num] = RenderedInfo {
-			img: Some(img),
-			num_results: Some(num_results)
-		};
+		let rendered = &mut self.rendered[page_num];
+		rendered.img = Some(img);
+		rendered.num_results = Some(num_results);
+	}
+
+	pub fn thumbnail_ready(&mut self, img: ConvertedImage, page_num: usize) {
+		self.rendered[page_num].thumbnail = Some(img);
+		if self.thumbnails.is_some() {
+			self.last_render.rect = Rect::default();
+		}
+	}
+
+	/// The pages that are on screen, or would be once they're rendered, which have to be kept
+	/// however little memory they're allowed to take up
+	#[must_use]
+	pub fn pages_on_screen(&self) -> Vec<usize> {
+		std::iter::once(self.page)
+			.chain(self.last_render.shown.iter().map(|shown| shown.num))
+			.chain(self.last_render.waiting_on)
+			.collect()
+	}
+
+	/// Drops the image of `page_num` to free up memory, returning whether it had already been sent
+	/// to kitty (in which case it needs to be deleted from there too)
+	pub fn evict_page(&mut self, page_num: usize) -> bool {
+		let img = self
+			.rendered
+			.get_mut(page_num)
+			.and_then(|page| page.img.take());
+		if self
+			.last_render
+			.shown
+			.iter()
+			.any(|shown| shown.num == page_num)
+		{
+			self.last_render.rect = Rect::default();
+		}
+		matches!(
+			img,
+			Some(ConvertedImage::Kitty {
+				img: MaybeTransferred::Transferred(_),
+				..
+			})
+		)
 	}
 
 	pub fn page_failed_display(&mut self, page_num: usize) {
-		self.rendered[page_num].img = None;
+		// Only the thumbnails are displayed while the overview is open
+		let rendered = &mut self.rendered[page_num];
+		if self.thumbnails.is_some() {
+			rendered.thumbnail = None;
+		} else {
+			rendered.img = None;
+		}
 	}
 
 	pub fn got_num_results_on_page(&mut self, page_num: usize, num_results: usize) {
 		self.rendered[page_num].num_results = Some(num_results);
+		// The overview shows how many results are on each page, so it needs to be redrawn
+		if self.thumbnails.is_some() {
+			self.last_render.rect = Rect::default();
+		}
 	}
 
-	pub fn render_top_and_bottom(
-		(top_area, bottom_area): (Rect, Rect),
+	#[must_use]
+	pub fn showing_thumbnails(&self) -> bool {
+		self.thumbnails.is_some()
+	}
+
+	pub fn got_page_details(
+		&mut self,
 		page_num: usize,
-		rendered: &[RenderedInfo],
-		doc_name: &str,
-		frame: &mut Frame<'_>,
-		bottom_msg: &BottomMessage
+		links: Vec<PageLink>,
+		result_rects: Vec<HighlightRect>,
+		transform: PageTransform
 	) {
-		// use the extra space here to add some padding to the right side
-		let page_nums_text = format!("{} / {} ", page_num + 1, rendered.len());
+		let rendered = &mut self.rendered[page_num];
+		rendered.links = links;
+		rendered.result_rects = result_rects;
+		rendered.transform = Some(transform);
+	}
 
-		let top_block = Block::new()
-			// use this first title to add a bit of padding to the left side
-			.title_top(" ")
-			.title_top(Span::styled(doc_name, Style::new().fg(Color::Cyan)))
-			.title_top(
-				Span::styled(&page_nums_text, Style::new().fg(Color::Cyan))
-					.into_right_aligned_line()
-			)
-			.padding(Padding {
-				bottom: 1,
-				..Padding::default()
-			})
-			.borders(Borders::BOTTOM);
+	pub fn got_page_text(
+		&mut self,
+		page_num: usize,
+		words: Vec<TextWord>
+	) -> Option<PageSelection> {
+		let selection = self
+			.selection
+			.as_mut()
+			.filter(|s| s.page == page_num)
+			.map(|selection| {
+				selection.set_words(words.clone());
+				selection.to_page_selection()
+			});
+
+		let text = &mut self.rendered[page_num].text;
+		if matches!(text, PageText::Requested) {
+			*text = PageText::Extracted(words);
+		}
 
-		frame.render_widget(top_block, top_area);
+		selection
+	}
 
-		let bottom_block = Block::new()
-			.padding(Padding {
-				top: 1,
-				right: 2,
-				left: 2,
-				bottom: 0
-			})
-			.borders(Borders::TOP);
-		let bottom_inside_block = bottom_block.inner(bottom_area);
+	/// The page whose text needs to be extracted so it can be shown in the text view, if there is
+	/// one. This only returns each page once, so the caller needs to actually ask the renderer for
+	/// it.
+	pub fn text_to_extract(&mut self) -> Option<usize> {
+		let PageView::Text { .. } = self.view else {
+			return None;
+		};
 
-		frame.render_widget(bottom_block, bottom_area);
+		let text = &mut self.rendered.get_mut(self.page)?.text;
+		matches!(text, PageText::NotRequested).then(|| {
+			*text = PageText::Requested;
+			self.page
+		})
+	}
 
-		let rendered_str = if !rendered.is_empty() {
-			format!(
-				"Rendered: {}%",
-				(rendered.iter().filter(|i| i.img.is_some()).count() * 100) / rendered.len()
-			)
-		} else {
-			String::new()
-		};
-		let bottom_layout = Layout::horizontal([
-			Constraint::Fill(1),
-			Constraint::Length(rendered_str.len() as u16)
-		])
-		.split(bottom_inside_block);
+	#[must_use]
+	pub fn name(&self) -> &str {
+		&self.name
+	}
 
-		let rendered_span = Span::styled(&rendered_str, Style::new().fg(Color::Cyan));
-		frame.render_widget(rendered_span, bottom_layout[1]);
+	/// Makes the next render draw everything from scratch, for when something else (like another
+	/// document) has been drawn over what we last drew
+	pub fn mark_for_redraw(&mut self) {
+		self.last_render.rect = Rect::default();
+	}
 
-		let (msg_str, color): (Cow<'_, str>, _) = match bottom_msg {
-			BottomMessage::Help => ("?: Show help page".into(), Color::Blue),
-			BottomMessage::Error(e) => (e.as_str().into(), Color::Red),
-			BottomMessage::Input(input_state) => (
-				match input_state {
-					InputCommand::GoToPage(page) => format!("Go to: {page}"),
-					InputCommand::Search(s) => format!("Search: {s}")
-				}
-				.into(),
-				Color::Blue
+	/// Whether the password is being asked for, so nothing can be shown until it's typed in
+	#[must_use]
+	pub fn needs_password(&self) -> bool {
+		matches!(
+			self.bottom_msg,
+			BottomMessage::Input(InputCommand::Password { .. })
+		)
+	}
+
+	pub fn ask_for_password(&mut self, wrong: bool) {
+		self.set_msg(MessageSetting::Some(BottomMessage::Input(
+			InputCommand::Password {
+				typed: String::new(),
+				wrong
+			}
+		)));
+	}
+
+	/// Shows the text of each page instead of its image, for terminals that can't display images
+	pub fn use_text_view(&mut self) {
+		self.view = PageView::Text { scroll: 0 };
+	}
+
+	/// Stacks the pages on top of each other so they can be scrolled through continuously
+	pub fn use_continuous(&mut self) {
+		self.continuous = Some(0);
+	}
+
+	fn toggle_continuous(&mut self) -> InputAction {
+		let f_or_f = if self.continuous.take().is_some() {
+			FitOrFill::Fit
+		} else {
+			self.zoom = None;
+			self.continuous = Some(0);
+			FitOrFill::FitWidth
+		};
+		self.last_render.rect = Rect::default();
+		InputAction::SwitchRenderZoom(f_or_f)
+	}
+
+	// Scrolls through the pages in continuous mode, moving on to the next (or previous) page
+	// whenever we go past the end (or start) of one
+	fn scroll_continuous(&mut self, forwards: bool, rows: u16) -> Option<InputAction> {
+		let mut row = self.continuous?;
+		let mut page = self.page;
+		let n_pages = self.rendered.len();
+		let height = |page: usize| self.rendered.get(page)?.img.as_ref().map(|img| img.w_h().1);
+
+		if forwards {
+			row = row.saturating_add(rows);
+			while let Some(h) = height(page)
+				&& row >= h && page + 1 < n_pages
+			{
+				row -= h;
+				page += 1;
+			}
+			row = match height(page) {
+				// The bottom of the last page shouldn't go any further up than the bottom of the
+				// screen
+				Some(h) if page + 1 >= n_pages =>
+					row.min(h.saturating_sub(self.last_render.rows_shown)),
+				Some(_) => row,
+				// We can't scroll through a page until we know how tall it is
+				None => 0
+			};
+		} else {
+			let mut back = rows;
+			loop {
+				if back <= row {
+					row -= back;
+					break;
+				}
+				let Some(prev) = page.checked_sub(1) else {
+					row = 0;
+					break;
+				};
+				// The bottom of the previous page is the same spot as the top of this one
+				back -= row;
+				page = prev;
+				let Some(h) = height(page) else {
+					row = 0;
+					break;
+				};
+				row = h;
+			}
+		}
+
+		self.continuous = Some(row);
+		self.last_render.rect = Rect::default();
+		if page == self.page {
+			Some(InputAction::Redraw)
+		} else {
+			// `set_page` would put us back at the top of the page
+			self.page = page;
+			Some(InputAction::JumpingToPage(page))
+		}
+	}
+
+	fn toggle_text_view(&mut self) {
+		self.view = match self.view {
+			PageView::Images => PageView::Text { scroll: 0 },
+			PageView::Text { .. } => PageView::Images
+		};
+		self.last_render.rect = Rect::default();
+	}
+
+	// Scrolls through the text view, moving on to the next (or previous) page once we're at the end
+	// (or start) of this one. This ignores `r_to_l` since the text always reads top to bottom.
+	fn scroll_text(&mut self, forwards: bool, lines: u16) -> Option<InputAction> {
+		let PageView::Text { ref mut scroll } = self.view else {
+			return None;
+		};
+
+		if forwards && *scroll < self.last_render.text_max_scroll {
+			*scroll = scroll
+				.saturating_add(lines)
+				.min(self.last_render.text_max_scroll);
+			return Some(InputAction::Redraw);
+		}
+		if !forwards && *scroll > 0 {
+			*scroll = scroll.saturating_sub(lines);
+			return Some(InputAction::Redraw);
+		}
+
+		let page = if forwards {
+			Some(self.page + 1).filter(|page| *page < self.rendered.len())
+		} else {
+			self.page.checked_sub(1)
+		}?;
+		self.set_page(page);
+		if !forwards {
+			// so we start at the bottom of the previous page; this gets clamped when it's rendered
+			self.view = PageView::Text { scroll: u16::MAX };
+		}
+		Some(InputAction::JumpingToPage(page))
+	}
+
+	fn start_selection(&mut self, page: usize) -> InputAction {
+		self.selection = Some(Selection::new(page));
+		if !matches!(self.bottom_msg, BottomMessage::Selecting) {
+			self.set_msg(MessageSetting::Some(BottomMessage::Selecting));
+		}
+		// We don't keep the text of every page around, so we need to ask for it
+		InputAction::ExtractText(page)
+	}
+
+	fn end_selection(&mut self) {
+		self.selection = None;
+		self.mouse = MouseState::Idle;
+		self.set_msg(MessageSetting::Pop);
+	}
+
+	fn copy_selection(&mut self) -> InputAction {
+		let text = self
+			.selection
+			.as_ref()
+			.map(Selection::text)
+			.unwrap_or_default();
+		self.end_selection();
+
+		if text.is_empty() {
+			self.set_msg(MessageSetting::Some(BottomMessage::Error(
+				"There's no text selected to copy".into()
+			)));
+			InputAction::Select(None)
+		} else {
+			self.set_msg(MessageSetting::Some(BottomMessage::Copied(
+				text.chars().count()
+			)));
+			InputAction::CopySelection(text)
+		}
+	}
+
+	// The motions are always on the same keys, but starting over and quitting follow the keymap
+	fn handle_selection_key(&mut self, key: &KeyEvent) -> Option<InputAction> {
+		let motion = match key.code {
+			KeyCode::Char('w') | KeyCode::Right => Motion::NextWord,
+			KeyCode::Char('b') | KeyCode::Left => Motion::PrevWord,
+			KeyCode::Char('j') | KeyCode::Down => Motion::NextLine,
+			KeyCode::Char('k') | KeyCode::Up => Motion::PrevLine,
+			KeyCode::Char('0') | KeyCode::Home => Motion::LineStart,
+			KeyCode::Char('$') | KeyCode::End => Motion::LineEnd,
+			KeyCode::Char('}') => Motion::NextBlock,
+			KeyCode::Char('{') => Motion::PrevBlock,
+			KeyCode::Char('y') | KeyCode::Enter => return Some(self.copy_selection()),
+			KeyCode::Esc => {
+				self.end_selection();
+				return Some(InputAction::Select(None));
+			}
+			_ => match self.keymap.action_for(key)? {
+				Action::Select => {
+					let selection = self.selection.as_mut()?;
+					selection.reanchor();
+					return Some(InputAction::Select(Some(selection.to_page_selection())));
+				}
+				Action::Quit => return Some(InputAction::QuitApp),
+				_ => return None
+			}
+		};
+
+		let selection = self.selection.as_mut()?;
+		selection.move_cursor(motion);
+		Some(InputAction::Select(Some(selection.to_page_selection())))
+	}
+
+	// Finds where the given cell is on the given page (or, if `page` is None, whatever page is
+	// shown at that cell), in the page's own coordinates
+	fn page_point_at(&self, pos: Position, page: Option<usize>) -> Option<(usize, (f32, f32))> {
+		let shown = match page {
+			Some(page) => self.last_render.shown.iter().find(|s| s.num == page),
+			None => self.last_render.shown.iter().find(|s| s.area.contains(pos))
+		}?;
+
+		let transform = self.rendered[shown.num].transform?;
+		Some((shown.num, transform.to_page(shown.cell_to_px(pos))))
+	}
+
+	fn drag_selection(&mut self, pos: Position) -> Option<InputAction> {
+		// If this is the first drag event since the button was pressed, then this is the start of
+		// a new selection
+		if let MouseState::Pressed(page, start) = self.mouse {
+			let (_, point) = self.page_point_at(pos, Some(page))?;
+			self.mouse = MouseState::Dragging;
+
+			let needs_text = self.selection.as_ref().is_none_or(|s| s.page != page);
+			let action = needs_text.then(|| self.start_selection(page));
+
+			let selection = self.selection.as_mut()?;
+			selection.drag(start, true);
+			selection.drag(point, false);
+			return Some(
+				action.unwrap_or_else(|| InputAction::Select(Some(selection.to_page_selection())))
+			);
+		}
+
+		if !matches!(self.mouse, MouseState::Dragging) {
+			return None;
+		}
+
+		let page = self.selection.as_ref()?.page;
+		let (_, point) = self.page_point_at(pos, Some(page))?;
+		let selection = self.selection.as_mut()?;
+		selection.drag(point, false);
+		Some(InputAction::Select(Some(selection.to_page_selection())))
+	}
+
+	fn start_following_link(&mut self) {
+		let targets = self
+			.last_render
+			.shown
+			.iter()
+			.flat_map(|shown| {
+				self.rendered[shown.num].links.iter().filter_map(|link| {
+					shown
+						.px_to_cell(link.rect.ul_x, link.rect.ul_y)
+						.map(|pos| (pos, link.target.clone()))
+				})
+			})
+			.collect::<Vec<_>>();
+
+		if targets.is_empty() {
+			self.set_msg(MessageSetting::Some(BottomMessage::Error(
+				"There are no links on the shown pages".into()
+			)));
+		} else {
+			self.hints = hint_labels(targets.len())
+				.zip(targets)
+				.map(|(label, (pos, target))| LinkHint { label, pos, target })
+				.collect();
+			self.set_msg(MessageSetting::Some(BottomMessage::Input(
+				InputCommand::FollowLink(String::new())
+			)));
+			// So that the hints get drawn on top of the pages
+			self.last_render.rect = Rect::default();
+		}
+	}
+
+	// Called every time the user types another character of a hint's label
+	fn narrow_hints(&mut self) -> Option<InputAction> {
+		let BottomMessage::Input(InputCommand::FollowLink(ref typed)) = self.bottom_msg else {
+			return None;
+		};
+
+		let mut matching = self
+			.hints
+			.iter()
+			.filter(|hint| hint.label.starts_with(typed.as_str()));
+
+		match (matching.next(), matching.next()) {
+			(Some(hint), None) => {
+				let target = hint.target.clone();
+				self.set_msg(MessageSetting::Pop);
+
+				match target {
+					LinkTarget::Page(page) if page < self.rendered.len() => {
+						self.record_jump();
+						self.set_page(page);
+						Some(InputAction::JumpingToPage(page))
+					}
+					LinkTarget::Page(page) => {
+						self.set_msg(MessageSetting::Some(BottomMessage::Error(format!(
+							"That link points to page {}, which doesn't exist",
+							page + 1
+						))));
+						Some(InputAction::Redraw)
+					}
+					LinkTarget::External(uri) => Some(InputAction::OpenLink(uri))
+				}
+			}
+			(None, _) => {
+				let msg = format!("No link is labelled '{typed}'");
+				self.set_msg(MessageSetting::Pop);
+				self.set_msg(MessageSetting::Some(BottomMessage::Error(msg)));
+				Some(InputAction::Redraw)
+			}
+			(Some(_), Some(_)) => {
+				// we need to redraw the pages so that the hints that don't match anymore are
+				// removed
+				self.last_render.rect = Rect::default();
+				Some(InputAction::Redraw)
+			}
+		}
+	}
+
+	pub fn set_outline(&mut self, entries: Vec<OutlineEntry>) {
+		self.outline = Outline::new(entries);
+		if self.showing_outline {
+			self.outline.select_page(self.page);
+		}
+	}
+
+	pub fn set_annotations(&mut self, annotations: Vec<(usize, Annotation)>) {
+		self.annotations = annotations;
+	}
+
+	fn toggle_outline(&mut self) {
+		self.showing_outline = !self.showing_outline;
+		if self.showing_outline {
+			self.outline.select_page(self.page);
+		}
+		// The page area changes size when the outline is shown or hidden, so we need to redraw
+		// everything
+		self.last_render.rect = Rect::default();
+	}
+
+	// Moving through the outline uses whatever moves through the pages, with the keys that would
+	// go to the next or previous page expanding or collapsing the selected entry instead
+	fn handle_outline_key(&mut self, key: &KeyEvent) -> Option<InputAction> {
+		match key.code {
+			KeyCode::Esc => self.toggle_outline(),
+			KeyCode::Enter => {
+				// Entries that don't point anywhere within this document just can't be jumped to
+				let page = self
+					.outline
+					.selected_page()
+					.filter(|p| *p < self.rendered.len())?;
+				self.record_jump();
+				self.set_page(page);
+				return Some(InputAction::JumpingToPage(page));
+			}
+			_ => match self.keymap.action_for(key)? {
+				Action::NextScreen => self.outline.select_next(),
+				Action::PrevScreen => self.outline.select_prev(),
+				Action::PrevPage => self.outline.collapse(),
+				Action::NextPage => self.outline.expand(),
+				Action::ToggleOutline => self.toggle_outline(),
+				Action::Quit => return Some(InputAction::QuitApp),
+				_ => return None
+			}
+		}
+		Some(InputAction::Redraw)
+	}
+
+	fn toggle_thumbnails(&mut self) -> InputAction {
+		self.thumbnails = match self.thumbnails {
+			Some(_) => None,
+			None => Some(ThumbnailGrid::new(self.page))
+		};
+		self.last_render.rect = Rect::default();
+		InputAction::Thumbnails(self.thumbnails.is_some().then_some(THUMBNAIL_SIZE))
+	}
+
+	fn handle_thumbnails_key(&mut self, key: &KeyEvent) -> Option<InputAction> {
+		let grid = self.thumbnails.as_ref()?;
+		let (selected, columns) = (grid.selected(), grid.columns() as isize);
+
+		match key.code {
+			KeyCode::Enter => {
+				self.record_jump();
+				self.set_page(selected);
+				return Some(self.toggle_thumbnails());
+			}
+			KeyCode::Esc => return Some(self.toggle_thumbnails()),
+			_ => ()
+		}
+
+		// Pages can't be moved through while the overview is open, so those keys move the cursor
+		// instead, with the ones that would move a whole screen moving a whole row
+		let amt = match self.keymap.action_for(key)? {
+			Action::NextPage => 1,
+			Action::PrevPage => -1,
+			Action::NextScreen => columns,
+			Action::PrevScreen => -columns,
+			action @ (Action::ToggleThumbnails
+			| Action::Search
+			| Action::Invert
+			| Action::Rotate
+			| Action::ToggleAnnotations
+			| Action::Fullscreen
+			| Action::Help
+			| Action::NextTab
+			| Action::PrevTab
+			| Action::CloseTab
+			| Action::Suspend
+			| Action::Quit) => return self.handle_action(action),
+			_ => return None
+		};
+
+		let n_pages = self.rendered.len();
+		if let Some(ref mut grid) = self.thumbnails {
+			grid.move_by(amt, n_pages);
+		}
+		self.last_render.rect = Rect::default();
+		Some(InputAction::Redraw)
+	}
+
+	fn scroll_thumbnails(&mut self, kind: MouseEventKind) -> Option<InputAction> {
+		let n_pages = self.rendered.len();
+		let grid = self.thumbnails.as_mut()?;
+		let columns = grid.columns() as isize;
+		match kind {
+			MouseEventKind::ScrollDown => grid.move_by(columns, n_pages),
+			MouseEventKind::ScrollUp => grid.move_by(-columns, n_pages),
+			_ => return None
+		}
+		self.last_render.rect = Rect::default();
+		Some(InputAction::Redraw)
+	}
+
+	fn set_mark(&mut self, name: char) -> InputAction {
+		if !name.is_ascii_alphabetic() {
+			self.set_msg(MessageSetting::Some(BottomMessage::Error(format!(
+				"Can't set mark '{name}'; marks can only be named with letters"
+			))));
+		} else {
+			self.marks.insert(name, self.position());
+		}
+		InputAction::Redraw
+	}
+
+	fn jump_to_mark(&mut self, name: char) -> InputAction {
+		let rendered_len = self.rendered.len();
+		let err = match self.marks.get(&name) {
+			None => format!("Mark '{name}' isn't set"),
+			// Marks that were saved with the document could be past the end if it got shorter
+			Some(mark) if mark.page >= rendered_len => format!(
+				"Can't jump to mark '{name}' on page {}; there are only {rendered_len} pages in the document",
+				mark.page + 1
 			),
-			BottomMessage::SearchResults(term) => {
+			Some(mark) => {
+				let mark = *mark;
+				self.record_jump();
+				return self.restore_position(mark);
+			}
+		};
+
+		self.set_msg(MessageSetting::Some(BottomMessage::Error(err)));
+		InputAction::Redraw
+	}
+
+	// Where we are right now, for setting marks and remembering where we jumped from
+	fn position(&self) -> Mark {
+		Mark {
+			page: self.page,
+			zoom: self.zoom.map(SavedZoom::from)
+		}
+	}
+
+	fn restore_position(&mut self, pos: Mark) -> InputAction {
+		// We only pan to where it was if we're zoomed in now, since otherwise we'd need to switch
+		// the renderer over to filling the screen too
+		if let (Some(zoom), Some(pos_zoom)) = (&mut self.zoom, pos.zoom) {
+			*zoom = pos_zoom.into();
+			self.last_render.rect = Rect::default();
+		}
+		// The document could've gotten shorter since we were there
+		let page = pos.page.min(self.rendered.len().saturating_sub(1));
+		self.set_page(page);
+		InputAction::JumpingToPage(page)
+	}
+
+	/// Remembers where we are before moving somewhere that isn't just the next or previous page,
+	/// so that `jump_back` can come back here
+	fn record_jump(&mut self) {
+		let pos = self.position();
+		self.jumps.push(pos);
+	}
+
+	fn jump_back(&mut self) -> Option<InputAction> {
+		let pos = self.jumps.back(self.position())?;
+		Some(self.restore_position(pos))
+	}
+
+	fn jump_forward(&mut self) -> Option<InputAction> {
+		let pos = self.jumps.forward(self.position())?;
+		Some(self.restore_position(pos))
+	}
+
+	fn handle_marks_popup_key(&mut self, key: &KeyEvent) -> Option<InputAction> {
+		match key.code {
+			KeyCode::Char(c) if self.marks.contains_key(&c) => {
+				self.set_msg(MessageSetting::ClosePopup);
+				Some(self.jump_to_mark(c))
+			}
+			KeyCode::Esc => {
+				self.set_msg(MessageSetting::ClosePopup);
+				Some(InputAction::Redraw)
+			}
+			_ => match self.keymap.action_for(key)? {
+				Action::ShowMarks => {
+					self.set_msg(MessageSetting::ClosePopup);
+					Some(InputAction::Redraw)
+				}
+				Action::Quit => Some(InputAction::QuitApp),
+				_ => None
+			}
+		}
+	}
+
+	fn handle_annotations_key(&mut self, key: &KeyEvent) -> Option<InputAction> {
+		match key.code {
+			KeyCode::Enter => {
+				let (page, rect) = self
+					.annotations
+					.get(self.annotation_list.selected())
+					.map(|(page, annotation)| (*page, annotation.rect))?;
+				self.set_msg(MessageSetting::ClosePopup);
+				self.show_rect(page, rect);
+				Some(InputAction::JumpingToPage(page))
+			}
+			KeyCode::Esc => {
+				self.set_msg(MessageSetting::ClosePopup);
+				Some(InputAction::Redraw)
+			}
+			_ => match self.keymap.action_for(key)? {
+				Action::NextScreen => {
+					self.annotation_list.select_next(self.annotations.len());
+					Some(InputAction::Redraw)
+				}
+				Action::PrevScreen => {
+					self.annotation_list.select_prev();
+					Some(InputAction::Redraw)
+				}
+				Action::ShowAnnotations => {
+					self.set_msg(MessageSetting::ClosePopup);
+					Some(InputAction::Redraw)
+				}
+				Action::Quit => Some(InputAction::QuitApp),
+				_ => None
+			}
+		}
+	}
+
+	#[expect(clippy::too_many_arguments)]
+	pub fn render_top_and_bottom(
+		(top_area, bottom_area): (Rect, Rect),
+		page_num: usize,
+		page_labels: &PageLabels,
+		rendered: &[RenderedInfo],
+		annotations: &[(usize, Annotation)],
+		tabs: Tabs<'_>,
+		frame: &mut Frame<'_>,
+		bottom_msg: &BottomMessage,
+		active_result: Option<(usize, usize)>
+	) {
+		// The label is only worth showing if it's not just the page's number
+		let label = page_labels
+			.label(page_num)
+			.filter(|label| *label != (page_num + 1).to_string());
+		// use the extra space here to add some padding to the right side
+		let page_nums_text = match label {
+			Some(label) => format!("{label} ({}) / {} ", page_num + 1, rendered.len()),
+			None => format!("{} / {} ", page_num + 1, rendered.len())
+		};
+
+		// Every document that's open gets a tab, with the one that's shown standing out
+		let mut tab_spans = Vec::with_capacity(tabs.names.len() * 2);
+		for (idx, name) in tabs.names.iter().enumerate() {
+			if idx > 0 {
+				tab_spans.push(Span::styled(" | ", Style::new().fg(Color::DarkGray)));
+			}
+			let color = if idx == tabs.active {
+				Color::Cyan
+			} else {
+				Color::DarkGray
+			};
+			tab_spans.push(Span::styled(name.as_str(), Style::new().fg(color)));
+			// Otherwise there'd be no way to tell that a document in the background is stuck
+			// waiting for its password
+			if idx != tabs.active && tabs.need_password.get(idx).copied().unwrap_or_default() {
+				tab_spans.push(Span::styled(
+					" (needs password)",
+					Style::new().fg(Color::Yellow)
+				));
+			}
+		}
+
+		let top_block = Block::new()
+			// use this first title to add a bit of padding to the left side
+			.title_top(" ")
+			.title_top(Line::from(tab_spans))
+			.title_top(
+				Span::styled(&page_nums_text, Style::new().fg(Color::Cyan))
+					.into_right_aligned_line()
+			)
+			.padding(Padding {
+				bottom: 1,
+				..Padding::default()
+			})
+			.borders(Borders::BOTTOM);
+
+		frame.render_widget(top_block, top_area);
+
+		let bottom_block = Block::new()
+			.padding(Padding {
+				top: 1,
+				right: 2,
+				left: 2,
+				bottom: 0
+			})
+			.borders(Borders::TOP);
+		let bottom_inside_block = bottom_block.inner(bottom_area);
+
+		frame.render_widget(bottom_block, bottom_area);
+
+		let rendered_str = if !rendered.is_empty() {
+			format!(
+				"Rendered: {}%",
+				(rendered.iter().filter(|i| i.img.is_some()).count() * 100) / rendered.len()
+			)
+		} else {
+			String::new()
+		};
+		let bottom_layout = Layout::horizontal([
+			Constraint::Fill(1),
+			Constraint::Length(rendered_str.len() as u16)
+		])
+		.split(bottom_inside_block);
+
+		let rendered_span = Span::styled(&rendered_str, Style::new().fg(Color::Cyan));
+		frame.render_widget(rendered_span, bottom_layout[1]);
+
+		let (msg_str, color): (Cow<'_, str>, _) = match bottom_msg {
+			// The notes on the current page are more useful than a reminder of how to get help
+			BottomMessage::Help => match annotations::summary(
+				annotations
+					.iter()
+					.filter(|(page, _)| *page == page_num)
+					.map(|(_, annotation)| annotation)
+			) {
+				Some(summary) => (summary.into(), Color::Yellow),
+				None => ("?: Show help page".into(), Color::Blue)
+			},
+			BottomMessage::Error(e) => (e.as_str().into(), Color::Red),
+			BottomMessage::Input(input_state) => (
+				match input_state {
+					InputCommand::GoToPage(page) => format!("Go to: {page}"),
+					InputCommand::Search(s) => format!("Search: {s}"),
+					InputCommand::FollowLink(typed) => format!("Follow link: {typed}"),
+					InputCommand::SetMark => "Set mark: ".into(),
+					InputCommand::JumpToMark => "Jump to mark: ".into(),
+					// We obviously don't want to show the password itself, just how much of it
+					// has been typed
+					InputCommand::Password { typed, wrong } => format!(
+						"{}: {}",
+						if *wrong {
+							"Wrong password, try again"
+						} else {
+							"This document is encrypted. Password"
+						},
+						"*".repeat(typed.chars().count())
+					)
+				}
+				.into(),
+				if matches!(input_state, InputCommand::Password { wrong: true, .. }) {
+					Color::Red
+				} else {
+					Color::Blue
+				}
+			),
+			BottomMessage::SearchResults(query) => {
 				let num_found = rendered.iter().filter_map(|r| r.num_results).sum::<usize>();
 				let num_searched =
 					rendered.iter().filter(|r| r.num_results.is_some()).count() * 100;
+				let modes = query
+					.modes()
+					.map(|modes| format!(" ({modes})"))
+					.unwrap_or_default();
+				// There could be more to come from the pages that haven't been searched yet
+				let num_found = if rendered.iter().any(|r| r.num_results.is_none()) {
+					format!("{num_found}+")
+				} else {
+					num_found.to_string()
+				};
+				// Which result we're on, counting across the whole document
+				let found = match active_result {
+					Some((page, idx)) => {
+						let before = rendered
+							.iter()
+							.take(page)
+							.filter_map(|r| r.num_results)
+							.sum::<usize>();
+						format!("hit {}/{num_found}", before + idx + 1)
+					}
+					None => num_found
+				};
 				(
 					format!(
-						"Results for '{term}': {num_found} (searched: {}%)",
-						num_searched / rendered.len()
+						"Results for '{}'{modes}: {found} (searched: {}%)",
+						query.term,
+						// this could be empty if we're still waiting on the document's password
+						num_searched / rendered.len().max(1)
 					)
 					.into(),
 					Color::Blue
 				)
 			}
+			BottomMessage::Selecting => (
+				"Selecting: w/b: word, j/k: line, {/}: block, 0/$: line start/end, v: restart, y: copy"
+					.into(),
+				Color::Blue
+			),
+			BottomMessage::Copied(n) =>
+				(format!("Copied {n} characters to the clipboard").into(), Color::Blue),
 			BottomMessage::Reloaded => ("Document was reloaded!".into(), Color::Blue)
 		};
 
@@ -719,16 +2251,21 @@ impl Tui {
 	}
 
 	pub fn handle_event(&mut self, ev: &Event) -> Option<InputAction> {
-		fn jump_to_page(page: &mut usize, rect: &mut Rect, new_page: usize) -> InputAction {
-			*page = new_page;
-			// Make sure we re-render
-			*rect = Rect::default();
-			InputAction::JumpingToPage(new_page)
-		}
-
 		let can_zoom = self.is_kitty && self.zoom.is_some();
 
 		match ev {
+			Event::Key(key) if self.selection.is_some() => self.handle_selection_key(key),
+			Event::Key(key) if self.showing_outline => self.handle_outline_key(key),
+			Event::Key(key) if matches!(self.popup, Some(Popup::Marks)) =>
+				self.handle_marks_popup_key(key),
+			Event::Key(key) if matches!(self.popup, Some(Popup::Annotations)) =>
+				self.handle_annotations_key(key),
+			Event::Key(key)
+				if self.thumbnails.is_some()
+					&& self.popup.is_none()
+					&& !matches!(self.bottom_msg, BottomMessage::Input(_)) =>
+				self.handle_thumbnails_key(key),
+			Event::Mouse(mouse) if self.thumbnails.is_some() => self.scroll_thumbnails(mouse.kind),
 			Event::Key(key) => {
 				match key.code {
 					KeyCode::Char(c)
@@ -739,8 +2276,45 @@ impl Tui {
 						InputAction::Redraw.into()
 					}
 					KeyCode::Char(c)
-						if let BottomMessage::Input(InputCommand::GoToPage(_)) =
-							self.bottom_msg && matches!(c, 'g' if self.is_kitty) =>
+						if let BottomMessage::Input(InputCommand::FollowLink(ref mut typed)) =
+							self.bottom_msg =>
+					{
+						typed.push(c);
+						self.narrow_hints()
+					}
+					KeyCode::Char(c)
+						if let BottomMessage::Input(InputCommand::Password {
+							ref mut typed,
+							..
+						}) = self.bottom_msg =>
+					{
+						typed.push(c);
+						InputAction::Redraw.into()
+					}
+					KeyCode::Char(c)
+						if matches!(
+							self.bottom_msg,
+							BottomMessage::Input(InputCommand::SetMark)
+						) =>
+					{
+						self.set_msg(MessageSetting::Pop);
+						Some(self.set_mark(c))
+					}
+					KeyCode::Char(c)
+						if matches!(
+							self.bottom_msg,
+							BottomMessage::Input(InputCommand::JumpToMark)
+						) =>
+					{
+						self.set_msg(MessageSetting::Pop);
+						Some(self.jump_to_mark(c))
+					}
+					// `gg` only goes to the top of the page if it's the first thing typed, since
+					// page labels could have a `g` in them
+					KeyCode::Char(c)
+						if let BottomMessage::Input(InputCommand::GoToPage(ref page)) =
+							self.bottom_msg && page.is_empty()
+							&& matches!(c, 'g' if self.is_kitty) =>
 					{
 						self.set_msg(MessageSetting::Pop);
 						self.update_zoom(Zoom::pan_bottom)
@@ -748,118 +2322,10 @@ impl Tui {
 					KeyCode::Char(c)
 						if let BottomMessage::Input(InputCommand::GoToPage(ref mut page)) =
 							self.bottom_msg =>
-						c.to_digit(10).map(|input_num| {
-							*page = (*page * 10) + input_num as usize;
-							InputAction::Redraw
-						}),
-					KeyCode::Char(c) => match c {
-						'l' => self.change_page(PageChange::Next, ChangeAmount::Single),
-						'j' => self.change_page(PageChange::Next, ChangeAmount::WholeScreen),
-						'h' => self.change_page(PageChange::Prev, ChangeAmount::Single),
-						'k' => self.change_page(PageChange::Prev, ChangeAmount::WholeScreen),
-						'q' => Some(InputAction::QuitApp),
-						'g' => {
-							self.set_msg(MessageSetting::Some(BottomMessage::Input(
-								InputCommand::GoToPage(0)
-							)));
-							Some(InputAction::Redraw)
-						}
-						'/' => {
-							self.set_msg(MessageSetting::Some(BottomMessage::Input(
-								InputCommand::Search(String::new())
-							)));
-							Some(InputAction::Redraw)
-						}
-						'i' => Some(InputAction::Invert),
-						'?' => {
-							self.showing_help_msg = true;
-							Some(InputAction::Redraw)
-						}
-						'f' => Some(InputAction::Fullscreen),
-						// TODO: If we can't find one, then maybe like block until we've verified
-						// all the pages have been checked?
-						'n' if self.page < self.rendered.len() - 1 => self.rendered
-							[(self.page + 1)..]
-							.iter()
-							.enumerate()
-							.find_map(|(idx, p)| {
-								p.num_results
-									.is_some_and(|num| num > 0)
-									.then_some(self.page + 1 + idx)
-							})
-							.map(|next_page| {
-								jump_to_page(&mut self.page, &mut self.last_render.rect, next_page)
-							}),
-						'N' if self.page > 0 => self.rendered[..(self.page)]
-							.iter()
-							.rev()
-							.enumerate()
-							.find_map(|(idx, p)| {
-								p.num_results
-									.is_some_and(|num| num > 0)
-									.then_some(self.page - (idx + 1))
-							})
-							.map(|prev_page| {
-								jump_to_page(&mut self.page, &mut self.last_render.rect, prev_page)
-							}),
-						'z' if key.modifiers.contains(KeyModifiers::CONTROL) => {
-							// [todo] better error handling here?
-
-							let mut backend = stdout();
-							execute!(
-								&mut backend,
-								LeaveAlternateScreen,
-								crossterm::cursor::Show,
-								crossterm::event::DisableMouseCapture
-							)
-							.unwrap();
-							disable_raw_mode().unwrap();
-
-							#[cfg(unix)]
-							{
-								// This process will hang after the SIGSTOP call until we get
-								// foregrounded again by something else, at which point we need to
-								// re-setup everything so that it all gets drawn again.
-								nix::sys::signal::kill(
-									nix::unistd::Pid::this(),
-									nix::sys::signal::Signal::SIGSTOP
-								)
-								.unwrap();
-							}
-
-							enable_raw_mode().unwrap();
-							execute!(
-								&mut backend,
-								EnterAlternateScreen,
-								crossterm::cursor::Hide,
-								crossterm::event::EnableMouseCapture
-							)
-							.unwrap();
-
-							self.last_render.rect = Rect::default();
-							Some(InputAction::Redraw)
-						}
-						'z' if self.is_kitty => {
-							let (zoom, f_or_f) = match self.zoom {
-								None => (Some(Zoom::default()), FitOrFill::Fill),
-								Some(_) => (None, FitOrFill::Fit)
-							};
-							self.zoom = zoom;
-							self.last_render.rect = Rect::default();
-							Some(InputAction::SwitchRenderZoom(f_or_f))
-						}
-						'o' if can_zoom => self.update_zoom(Zoom::step_in),
-						'O' if can_zoom => self.update_zoom(Zoom::step_out),
-						'L' if can_zoom => self.update_zoom(|z| z.pan(Direction::Right)),
-						'H' if can_zoom => self.update_zoom(|z| z.pan(Direction::Left)),
-						'J' if can_zoom => self.update_zoom(|z| z.pan(Direction::Down)),
-						'K' if can_zoom => self.update_zoom(|z| z.pan(Direction::Up)),
-						'G' if can_zoom => self.update_zoom(Zoom::pan_top),
-						'0' if can_zoom => self.update_zoom(Zoom::pan_left),
-						'$' if can_zoom => self.update_zoom(Zoom::pan_right),
-						'r' => Some(InputAction::Rotate),
-						_ => None
-					},
+					{
+						page.push(c);
+						InputAction::Redraw.into()
+					}
 					KeyCode::Backspace
 						if let BottomMessage::Input(InputCommand::Search(ref mut term)) =
 							self.bottom_msg =>
@@ -867,14 +2333,42 @@ impl Tui {
 						term.pop();
 						InputAction::Redraw.into()
 					}
-					KeyCode::Right => self.change_page(PageChange::Next, ChangeAmount::Single),
-					KeyCode::Down | KeyCode::PageDown =>
-						self.change_page(PageChange::Next, ChangeAmount::WholeScreen),
-					KeyCode::Left => self.change_page(PageChange::Prev, ChangeAmount::Single),
-					KeyCode::Up | KeyCode::PageUp =>
-						self.change_page(PageChange::Prev, ChangeAmount::WholeScreen),
-					KeyCode::Esc => match (self.showing_help_msg, &self.bottom_msg) {
-						(false, BottomMessage::Help) => Some(InputAction::QuitApp),
+					KeyCode::Backspace
+						if let BottomMessage::Input(InputCommand::GoToPage(ref mut page)) =
+							self.bottom_msg =>
+					{
+						page.pop();
+						InputAction::Redraw.into()
+					}
+					KeyCode::Backspace
+						if let BottomMessage::Input(InputCommand::Password {
+							ref mut typed,
+							..
+						}) = self.bottom_msg =>
+					{
+						typed.pop();
+						InputAction::Redraw.into()
+					}
+					KeyCode::Backspace
+						if let BottomMessage::Input(InputCommand::FollowLink(ref mut typed)) =
+							self.bottom_msg =>
+					{
+						typed.pop();
+						// so that the hints that were hidden come back
+						self.last_render.rect = Rect::default();
+						InputAction::Redraw.into()
+					}
+					KeyCode::Esc => match (self.popup, &self.bottom_msg) {
+						(None, BottomMessage::Help) => Some(InputAction::QuitApp),
+						// There's nothing we can show without the password, so backing out of
+						// typing it in closes the document (which quits if it's the only one
+						// that's open)
+						(None, BottomMessage::Input(InputCommand::Password { .. })) =>
+							Some(InputAction::CloseTab),
+						(Some(_), _) => {
+							self.set_msg(MessageSetting::ClosePopup);
+							Some(InputAction::Redraw)
+						}
 						_ => {
 							// When we hit escape, we just want to pop off the current message and
 							// show the underlying one.
@@ -882,7 +2376,7 @@ impl Tui {
 							Some(InputAction::Redraw)
 						}
 					},
-					KeyCode::Enter => {
+					KeyCode::Enter if matches!(self.bottom_msg, BottomMessage::Input(_)) => {
 						let mut default = BottomMessage::default();
 						std::mem::swap(&mut self.bottom_msg, &mut default);
 						let BottomMessage::Input(ref cmd) = default else {
@@ -891,53 +2385,32 @@ impl Tui {
 						};
 
 						match cmd {
-							// Only forward the command if it's within range
-							InputCommand::GoToPage(page) => {
-								// We need to subtract 1 b/c they're tracked internally as
-								// 0-indexed but input and displayed as 1-indexed
-								let zero_page = page.saturating_sub(1);
-								let rendered_len = self.rendered.len();
-
-								if zero_page < rendered_len {
-									self.set_page(zero_page);
-									Some(InputAction::JumpingToPage(zero_page))
-								} else {
-									self.set_msg(MessageSetting::Some(BottomMessage::Error(
-										format!(
-											"Cannot jump to page {page}; there are only {rendered_len} pages in the document"
-										)
-									)));
-									Some(InputAction::Redraw)
-								}
-							}
-							InputCommand::Search(term) => {
-								let term = term.clone();
-
-								// We only want to show search results if there would actually be
-								// data to show
-								if !term.is_empty() {
-									self.set_msg(MessageSetting::Some(
-										BottomMessage::SearchResults(term.clone())
-									));
-								} else {
-									// else, if it's not empty, we just want to reset the bottom
-									// area to show the default data; we don't want it to like show
-									// the data from a previous search
-									self.set_msg(MessageSetting::Reset);
-								}
-
-								// Reset all the search results
-								for img in &mut self.rendered {
-									img.num_results = None;
-								}
-								// but we still want to tell the rest of the system that we set the
-								// search term to '' so that they can re-render the pages wthout
-								// the highlighting
-								Some(InputAction::Search(term))
+							// Links get followed as soon as enough of their label is typed (and
+							// marks as soon as their letter is), so there's nothing to do here
+							InputCommand::FollowLink(_)
+							| InputCommand::SetMark
+							| InputCommand::JumpToMark => {
+								std::mem::swap(&mut self.bottom_msg, &mut default);
+								None
 							}
+							InputCommand::Password { typed, .. } =>
+								Some(InputAction::Password(typed.clone())),
+							InputCommand::GoToPage(page) =>
+								Some(self.go_to_label(page).unwrap_or_else(|e| {
+									self.set_msg(MessageSetting::Some(BottomMessage::Error(e)));
+									InputAction::Redraw
+								})),
+							InputCommand::Search(term) =>
+								Some(self.search(term).unwrap_or_else(|e| {
+									self.set_msg(MessageSetting::Some(BottomMessage::Error(e)));
+									InputAction::Redraw
+								})),
 						}
 					}
-					_ => None
+					_ => self
+						.keymap
+						.action_for(key)
+						.and_then(|action| self.handle_action(action))
 				}
 			}
 			Event::Mouse(mouse) => {
@@ -955,6 +2428,10 @@ impl Tui {
 							}
 							self.update_zoom(|z| z.pan(direction))
 						}
+					} else if self.continuous.is_some() && direction.is_vertical() {
+						// A whole screen at a time would be way too fast for a scroll wheel
+						const SCROLL_ROWS: u16 = 3;
+						self.scroll_continuous(matches!(direction, Direction::Down), SCROLL_ROWS)
 					} else {
 						let (change, amount) = match direction {
 							Direction::Right => (PageChange::Next, ChangeAmount::Single),
@@ -970,6 +2447,30 @@ impl Tui {
 					MouseEventKind::ScrollDown => handle_scroll(Direction::Down),
 					MouseEventKind::ScrollLeft => handle_scroll(Direction::Left),
 					MouseEventKind::ScrollUp => handle_scroll(Direction::Up),
+					// ctrl+clicking finds the source of whatever was clicked on, for documents
+					// made with TeX
+					MouseEventKind::Down(MouseButton::Left)
+						if mouse.modifiers.contains(KeyModifiers::CONTROL) =>
+						self.page_point_at(Position::new(mouse.column, mouse.row), None)
+							.map(|(page, point)| InputAction::InverseSearch { page, point }),
+					MouseEventKind::Down(MouseButton::Left) => {
+						self.mouse = self
+							.page_point_at(Position::new(mouse.column, mouse.row), None)
+							.map_or(MouseState::Idle, |(page, point)| {
+								MouseState::Pressed(page, point)
+							});
+						None
+					}
+					MouseEventKind::Drag(MouseButton::Left) =>
+						self.drag_selection(Position::new(mouse.column, mouse.row)),
+					// Like most terminals, we copy the selection as soon as the mouse is released
+					MouseEventKind::Up(MouseButton::Left) => match self.mouse {
+						MouseState::Dragging => Some(self.copy_selection()),
+						MouseState::Idle | MouseState::Pressed(..) => {
+							self.mouse = MouseState::Idle;
+							None
+						}
+					},
 					_ => None
 				}
 			}
@@ -978,6 +2479,235 @@ impl Tui {
 		}
 	}
 
+	fn handle_action(&mut self, action: Action) -> Option<InputAction> {
+		let can_zoom = self.is_kitty && self.zoom.is_some();
+		let in_text_view = matches!(self.view, PageView::Text { .. });
+		let continuous = self.continuous.is_some();
+
+		match action {
+			Action::NextScreen if in_text_view =>
+				self.scroll_text(true, self.last_render.rows_shown.max(1)),
+			Action::PrevScreen if in_text_view =>
+				self.scroll_text(false, self.last_render.rows_shown.max(1)),
+			Action::PanDown if in_text_view => self.scroll_text(true, 1),
+			Action::PanUp if in_text_view => self.scroll_text(false, 1),
+			Action::NextScreen if continuous =>
+				self.scroll_continuous(true, self.last_render.rows_shown.max(1)),
+			Action::PrevScreen if continuous =>
+				self.scroll_continuous(false, self.last_render.rows_shown.max(1)),
+			Action::PanDown if continuous => self.scroll_continuous(true, 1),
+			Action::PanUp if continuous => self.scroll_continuous(false, 1),
+			Action::NextPage => self.change_page(PageChange::Next, ChangeAmount::Single),
+			Action::NextScreen => self.change_page(PageChange::Next, ChangeAmount::WholeScreen),
+			Action::PrevPage => self.change_page(PageChange::Prev, ChangeAmount::Single),
+			Action::PrevScreen => self.change_page(PageChange::Prev, ChangeAmount::WholeScreen),
+			Action::Quit => Some(InputAction::QuitApp),
+			Action::GoToPage => {
+				self.set_msg(MessageSetting::Some(BottomMessage::Input(
+					InputCommand::GoToPage(String::new())
+				)));
+				Some(InputAction::Redraw)
+			}
+			Action::Search => {
+				self.set_msg(MessageSetting::Some(BottomMessage::Input(
+					InputCommand::Search(String::new())
+				)));
+				Some(InputAction::Redraw)
+			}
+			Action::Invert => Some(InputAction::Invert),
+			Action::Help => {
+				self.popup = Some(Popup::Help);
+				Some(InputAction::Redraw)
+			}
+			Action::SetMark => {
+				self.set_msg(MessageSetting::Some(BottomMessage::Input(
+					InputCommand::SetMark
+				)));
+				Some(InputAction::Redraw)
+			}
+			Action::JumpToMark => {
+				self.set_msg(MessageSetting::Some(BottomMessage::Input(
+					InputCommand::JumpToMark
+				)));
+				Some(InputAction::Redraw)
+			}
+			Action::JumpBack => self.jump_back(),
+			Action::JumpForward => self.jump_forward(),
+			Action::ShowMarks => {
+				self.popup = Some(Popup::Marks);
+				Some(InputAction::Redraw)
+			}
+			Action::ShowAnnotations => {
+				self.annotation_list
+					.select_page(self.page, self.annotations.iter().map(|(page, _)| *page));
+				self.popup = Some(Popup::Annotations);
+				Some(InputAction::Redraw)
+			}
+			Action::ToggleAnnotations => Some(InputAction::ToggleAnnotations),
+			Action::ShowInfo => {
+				self.doc_info = None;
+				self.popup = Some(Popup::Info);
+				Some(InputAction::LoadInfo)
+			}
+			Action::Fullscreen => Some(InputAction::Fullscreen),
+			Action::FollowLink => {
+				self.start_following_link();
+				Some(InputAction::Redraw)
+			}
+			Action::ToggleOutline => {
+				self.toggle_outline();
+				Some(InputAction::Redraw)
+			}
+			Action::ToggleTextView => {
+				self.toggle_text_view();
+				Some(InputAction::Redraw)
+			}
+			Action::ToggleContinuous => Some(self.toggle_continuous()),
+			// The text view is for terminals that can't show images at all
+			Action::ToggleThumbnails if in_text_view => None,
+			Action::ToggleThumbnails => Some(self.toggle_thumbnails()),
+			Action::ToggleSpread => {
+				self.page_constraints.spread = !self.page_constraints.spread;
+				self.last_render.rect = Rect::default();
+				Some(InputAction::Redraw)
+			}
+			Action::NextTab => Some(InputAction::NextTab),
+			Action::PrevTab => Some(InputAction::PrevTab),
+			Action::CloseTab => Some(InputAction::CloseTab),
+			Action::Select => Some(self.start_selection(self.page)),
+			Action::NextResult => self.go_to_result(true),
+			Action::PrevResult => self.go_to_result(false),
+			Action::Suspend => {
+				// [todo] better error handling here?
+
+				let mut backend = stdout();
+				// This needs to be undone before leaving the alternate screen, since the
+				// terminal keeps track of it separately for each screen
+				_ = execute!(&mut backend, PopKeyboardEnhancementFlags);
+				execute!(
+					&mut backend,
+					LeaveAlternateScreen,
+					crossterm::cursor::Show,
+					crossterm::event::DisableMouseCapture
+				)
+				.unwrap();
+				disable_raw_mode().unwrap();
+
+				#[cfg(unix)]
+				{
+					// This process will hang after the SIGSTOP call until we get
+					// foregrounded again by something else, at which point we need to
+					// re-setup everything so that it all gets drawn again.
+					nix::sys::signal::kill(
+						nix::unistd::Pid::this(),
+						nix::sys::signal::Signal::SIGSTOP
+					)
+					.unwrap();
+				}
+
+				enable_raw_mode().unwrap();
+				execute!(
+					&mut backend,
+					EnterAlternateScreen,
+					crossterm::cursor::Hide,
+					crossterm::event::EnableMouseCapture
+				)
+				.unwrap();
+				_ = execute!(
+					&mut backend,
+					PushKeyboardEnhancementFlags(
+						KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+					)
+				);
+
+				self.last_render.rect = Rect::default();
+				Some(InputAction::Redraw)
+			}
+			Action::ToggleZoom if self.is_kitty => {
+				let (zoom, f_or_f) = match self.zoom {
+					None => (Some(Zoom::default()), FitOrFill::Fill),
+					Some(_) => (None, FitOrFill::Fit)
+				};
+				self.zoom = zoom;
+				self.continuous = None;
+				self.last_render.rect = Rect::default();
+				Some(InputAction::SwitchRenderZoom(f_or_f))
+			}
+			Action::ZoomIn if can_zoom => self.update_zoom(Zoom::step_in),
+			Action::ZoomOut if can_zoom => self.update_zoom(Zoom::step_out),
+			Action::PanRight if can_zoom => self.update_zoom(|z| z.pan(Direction::Right)),
+			Action::PanLeft if can_zoom => self.update_zoom(|z| z.pan(Direction::Left)),
+			Action::PanDown if can_zoom => self.update_zoom(|z| z.pan(Direction::Down)),
+			Action::PanUp if can_zoom => self.update_zoom(|z| z.pan(Direction::Up)),
+			// `Zoom::pan_bottom` resets the pan so the top of the page is shown, and `pan_top` pans
+			// all the way to the bottom, so these look swapped but aren't
+			Action::PanToTop if can_zoom => self.update_zoom(Zoom::pan_bottom),
+			Action::PanToBottom if can_zoom => self.update_zoom(Zoom::pan_top),
+			Action::PanToLeftEdge if can_zoom => self.update_zoom(Zoom::pan_left),
+			Action::PanToRightEdge if can_zoom => self.update_zoom(Zoom::pan_right),
+			Action::ToggleZoom
+			| Action::ZoomIn
+			| Action::ZoomOut
+			| Action::PanRight
+			| Action::PanLeft
+			| Action::PanDown
+			| Action::PanUp
+			| Action::PanToTop
+			| Action::PanToBottom
+			| Action::PanToLeftEdge
+			| Action::PanToRightEdge => None,
+			Action::Rotate => Some(InputAction::Rotate),
+			Action::CycleTrim => Some(InputAction::CycleTrim)
+		}
+	}
+
+	fn go_to_result(&mut self, forwards: bool) -> Option<InputAction> {
+		let (page, idx) = self.step_result(forwards)?;
+		self.active_result = Some((page, idx));
+		self.record_jump();
+		self.set_page(page);
+		if self.zoom.is_some() {
+			self.reveal_active_result = true;
+			// so that the image gets redisplayed with the new pan
+			self.last_render.rect = Rect::default();
+		}
+		Some(InputAction::GoToResult { page, idx })
+	}
+
+	/// Finds the search result after (or before) the one we're on, going across pages if needed.
+	/// If we aren't on one on the current page, this starts from the top of the page, so `n`
+	/// goes to its first result.
+	// TODO: If we can't find one, then maybe like block until we've verified all the pages have
+	// been checked?
+	fn step_result(&self, forwards: bool) -> Option<(usize, usize)> {
+		let current = self.active_result.filter(|(page, _)| *page == self.page);
+		// There aren't any pages yet if we're still waiting on the document's password
+		let count_on = |page: usize| {
+			self.rendered
+				.get(page)
+				.and_then(|r| r.num_results)
+				.unwrap_or_default()
+		};
+
+		if forwards {
+			let next_idx = current.map_or(0, |(_, idx)| idx + 1);
+			if next_idx < count_on(self.page) {
+				return Some((self.page, next_idx));
+			}
+			(self.page + 1..self.rendered.len())
+				.find(|page| count_on(*page) > 0)
+				.map(|page| (page, 0))
+		} else {
+			if let Some((page, idx @ 1..)) = current {
+				return Some((page, idx - 1));
+			}
+			(0..self.page)
+				.rev()
+				.find(|page| count_on(*page) > 0)
+				.map(|page| (page, count_on(page) - 1))
+		}
+	}
+
 	// I want this to always return an option 'cause I just use it to return from `Self::handle_event`
 	#[expect(clippy::unnecessary_wraps)]
 	fn update_zoom(&mut self, f: impl FnOnce(&mut Zoom)) -> Option<InputAction> {
@@ -992,21 +2722,126 @@ impl Tui {
 		self.set_msg(MessageSetting::Some(BottomMessage::Error(match err {
 			RenderError::Notify(e) => format!("Auto-reload failed: {e}"),
 			RenderError::Doc(e) => format!("Couldn't process document: {e}"),
-			RenderError::Converting(e) => format!("Couldn't convert page after rendering: {e}")
+			RenderError::Converting(e) => format!("Couldn't convert page after rendering: {e}"),
+			RenderError::SyncTex(e) => e
 		})));
 	}
 
+	/// Moves to `rect` on `page` (like where a forward search ended up), panning over to it if
+	/// we're zoomed in
+	pub fn show_rect(&mut self, page: usize, rect: PdfRect) {
+		self.record_jump();
+		self.set_page(page);
+		if self.zoom.is_some() {
+			self.reveal_rect = Some((page, rect));
+			// so that the image gets redisplayed with the new pan
+			self.last_render.rect = Rect::default();
+		}
+	}
+
+	/// Jumps to `page`, which is 1-indexed (like it's shown and typed in), as long as it's in the
+	/// document
+	pub fn go_to_page(&mut self, page: usize) -> Result<InputAction, String> {
+		// We need to subtract 1 b/c they're tracked internally as 0-indexed but input and
+		// displayed as 1-indexed
+		let zero_page = page.saturating_sub(1);
+		let rendered_len = self.rendered.len();
+
+		if zero_page < rendered_len {
+			self.record_jump();
+			self.set_page(zero_page);
+			Ok(InputAction::JumpingToPage(zero_page))
+		} else {
+			Err(format!(
+				"Cannot jump to page {page}; there are only {rendered_len} pages in the document"
+			))
+		}
+	}
+
+	/// Jumps to the page with the label `typed`, or if there isn't one, to the page with that
+	/// number (like `go_to_page`). Labels come first since they're what's printed on the pages.
+	fn go_to_label(&mut self, typed: &str) -> Result<InputAction, String> {
+		let typed = typed.trim();
+		if let Some(page) = self.page_labels.find(typed) {
+			self.record_jump();
+			self.set_page(page);
+			return Ok(InputAction::JumpingToPage(page));
+		}
+
+		match typed.parse() {
+			Ok(page) => self.go_to_page(page),
+			Err(_) => Err(format!("There's no page labelled '{typed}'"))
+		}
+	}
+
+	pub fn set_page_labels(&mut self, labels: PageLabels) {
+		self.page_labels = labels;
+	}
+
+	/// Starts searching for `term` (or stops searching, if it's empty)
+	pub fn search(&mut self, term: &str) -> Result<InputAction, String> {
+		let query = SearchQuery::parse(term).map_err(|e| format!("Invalid search: {e}"))?;
+
+		// We only want to show search results if there would actually be data to show
+		if !query.is_empty() {
+			self.set_msg(MessageSetting::Some(BottomMessage::SearchResults(
+				query.clone()
+			)));
+		} else {
+			// else, if it's not empty, we just want to reset the bottom area to show the default
+			// data; we don't want it to like show the data from a previous search
+			self.set_msg(MessageSetting::Reset);
+		}
+
+		// Reset all the search results
+		for img in &mut self.rendered {
+			img.num_results = None;
+		}
+		self.active_result = None;
+		self.search = (!query.is_empty()).then(|| query.clone());
+		// but we still want to tell the rest of the system that we set the search term to '' so
+		// that they can re-render the pages wthout the highlighting
+		Ok(InputAction::Search(query))
+	}
+
+	/// Where we're at, for answering commands that come in over the remote-control socket
+	#[cfg(unix)]
+	#[must_use]
+	pub fn status(&self) -> RemoteStatus {
+		RemoteStatus {
+			page: self.page + 1,
+			pages: self.rendered.len(),
+			results: self
+				.search
+				.is_some()
+				.then(|| self.rendered.iter().filter_map(|r| r.num_results).sum()),
+			searching: self.search.is_some()
+				&& self.rendered.iter().any(|r| r.num_results.is_none())
+		}
+	}
+
 	fn set_page(&mut self, page: usize) {
 		if page != self.page {
 			// mark that we need to re-render the images
 			self.last_render.rect = Rect::default();
 			self.page = page;
+			if let PageView::Text { ref mut scroll } = self.view {
+				*scroll = 0;
+			}
+			if let Some(ref mut row) = self.continuous {
+				*row = 0;
+			}
 		}
 	}
 
 	// We have `msg` as optional so that if they reset it to none, it'll replace it with
 	// `prev_msg`, but if they reset it to something else, it'll put the current thing in prev_msg
 	pub fn set_msg(&mut self, msg: MessageSetting) {
+		// If we're leaving the link-following mode, the hints need to be erased from the screen
+		if let BottomMessage::Input(InputCommand::FollowLink(_)) = self.bottom_msg {
+			self.last_render.rect = Rect::default();
+		}
+
 		match msg {
 			MessageSetting::Some(mut msg) => {
 				std::mem::swap(&mut self.bottom_msg, &mut msg);
@@ -1017,49 +2852,185 @@ impl Tui {
 				self.prev_msg = None;
 				self.bottom_msg = BottomMessage::default();
 			}
-			MessageSetting::Pop =>
-				if self.showing_help_msg {
+			MessageSetting::Pop => self.bottom_msg = self.prev_msg.take().unwrap_or_default(),
+			MessageSetting::ClosePopup =>
+				if self.popup.take().is_some() {
 					self.last_render.rect = Rect::default();
-					self.showing_help_msg = false;
-				} else {
-					self.bottom_msg = self.prev_msg.take().unwrap_or_default();
 				},
 		}
 	}
 
 	pub fn render_help_msg(&self, frame: &mut Frame<'_>) {
-		let frame_area = frame.area();
-		frame.render_widget(Clear, frame_area);
-
-		let block = Block::new()
-			.title("Help")
-			.padding(Padding::proportional(1))
-			.borders(Borders::ALL)
-			.border_set(border::ROUNDED)
-			.border_style(Color::Blue);
+		// These keys aren't part of the keymap since they depend on what's currently open
+		let mut general = self.keymap.help_entries(false).collect::<Vec<_>>();
+		general.push((
+			"esc".into(),
+			"Close whatever prompt or popup is open, or quit if there's nothing open"
+		));
+		let mut kitty = self.keymap.help_entries(true).collect::<Vec<_>>();
+		kitty.push(("gg".into(), "Scroll to top of page (when on fill-screen)"));
+
+		let keys_w = general
+			.iter()
+			.chain(&kitty)
+			.map(|(keys, _)| keys.len())
+			.max()
+			.unwrap_or_default();
+		let to_text = |entries: Vec<(String, &'static str)>| {
+			entries
+				.into_iter()
+				.map(|(keys, description)| Line::from(format!("{keys:keys_w$}    {description}")))
+				.collect::<Text<'_>>()
+		};
 
 		let help_sections = [
-			Text::from(HELP_PAGE),
+			to_text(general),
 			// just some spacing
 			Text::from(""),
 			if self.is_kitty {
-				Text::from(KITTY_HELP)
+				let mut text = to_text(kitty);
+				text.lines
+					.insert(0, Line::from("When using Kitty Protocol:"));
+				text
 			} else {
 				Text::from("Not using kitty, kitty-specific keybindings hidden")
 					.style(Color::DarkGray)
 			}
 		];
 
-		let max_w: u16 = help_sections
+		Self::render_popup(frame, "Help", &help_sections);
+	}
+
+	fn render_marks(&self, frame: &mut Frame<'_>) {
+		let to_lines = |uppercase: bool| {
+			self.marks
+				.iter()
+				.filter(move |(name, _)| name.is_ascii_uppercase() == uppercase)
+				.map(|(name, mark)| {
+					let zoomed = if mark.zoom.is_some() {
+						" (zoomed in)"
+					} else {
+						""
+					};
+					Line::from(format!("{name}    page {}{zoomed}", mark.page + 1))
+				})
+				.collect::<Vec<_>>()
+		};
+		let (local, saved) = (to_lines(false), to_lines(true));
+
+		let mut sections = vec![];
+		if !local.is_empty() {
+			sections.push(Text::from(local));
+		}
+		if !saved.is_empty() {
+			if !sections.is_empty() {
+				// just some spacing
+				sections.push(Text::from(""));
+			}
+			let mut text = Text::from(saved);
+			text.lines
+				.insert(0, Line::from("Saved with this document:"));
+			sections.push(text);
+		}
+
+		if sections.is_empty() {
+			sections.push(Text::from("No marks have been set yet").style(Color::DarkGray));
+		} else {
+			sections.extend([
+				Text::from(""),
+				Text::from("Type a mark's letter to jump to it").style(Color::DarkGray)
+			]);
+		}
+
+		Self::render_popup(frame, "Marks", &sections);
+	}
+
+	pub fn got_doc_info(&mut self, info: DocInfo) {
+		self.doc_info = Some(info);
+	}
+
+	fn render_info(&self, frame: &mut Frame<'_>) {
+		// Enough to see which fonts are being used without running off the bottom of the screen
+		const MAX_FONTS: usize = 20;
+
+		let Some(ref info) = self.doc_info else {
+			let loading = Text::from("Loading...").style(Color::DarkGray);
+			Self::render_popup(frame, "Info", &[loading]);
+			return;
+		};
+
+		let fields = info.fields();
+		let label_w = fields
+			.iter()
+			.map(|(label, _)| label.len())
+			.max()
+			.unwrap_or_default();
+		let mut sections = vec![
+			fields
+				.into_iter()
+				.map(|(label, value)| {
+					Line::from(vec![
+						Span::styled(
+							format!("{label:label_w$}    "),
+							Style::new().fg(Color::Cyan)
+						),
+						Span::raw(value),
+					])
+				})
+				.collect::<Text<'_>>(),
+		];
+
+		if let Some(ref fonts) = info.fonts {
+			let mut lines = fonts
+				.iter()
+				.take(MAX_FONTS)
+				.map(|font| {
+					let embedded = if font.embedded { "" } else { " (not embedded)" };
+					Line::from(format!("{}{embedded}", font.name))
+				})
+				.collect::<Vec<_>>();
+			if fonts.len() > MAX_FONTS {
+				lines.push(Line::from(format!(
+					"...and {} more",
+					fonts.len() - MAX_FONTS
+				)));
+			}
+			if lines.is_empty() {
+				lines.push(Line::from("None"));
+			}
+
+			let mut text = Text::from(lines);
+			text.lines
+				.insert(0, Line::from("Fonts:").style(Color::Cyan));
+			// just some spacing
+			sections.extend([Text::from(""), text]);
+		}
+
+		Self::render_popup(frame, "Info", &sections);
+	}
+
+	// Draws `sections` one after another in a box in the middle of the screen
+	fn render_popup(frame: &mut Frame<'_>, title: &str, sections: &[Text<'_>]) {
+		let frame_area = frame.area();
+		frame.render_widget(Clear, frame_area);
+
+		let block = Block::new()
+			.title(title)
+			.padding(Padding::proportional(1))
+			.borders(Borders::ALL)
+			.border_set(border::ROUNDED)
+			.border_style(Color::Blue);
+
+		let max_w: u16 = sections
 			.iter()
 			.flat_map(|section| section.lines.as_slice())
-			// We don't really need full unicode-width since we're using all ascii for the help
-			// pages, but this is the function they give us.
+			// We don't really need full unicode-width since we're using all ascii for the popups,
+			// but this is the function they give us.
 			.map(Line::width)
 			.max()
 			.unwrap_or_default()
 			.try_into()
-			.expect("Every help text line must be shorter than u16::MAX");
+			.expect("Every popup line must be shorter than u16::MAX");
 
 		let layout = Layout::horizontal([
 			Constraint::Fill(1),
@@ -1071,8 +3042,7 @@ impl Tui {
 		let block_area = Layout::vertical([
 			Constraint::Fill(1),
 			Constraint::Length(
-				u16::try_from(help_sections.iter().map(|s| s.lines.len()).sum::<usize>()).unwrap()
-					+ 4
+				u16::try_from(sections.iter().map(|s| s.lines.len()).sum::<usize>()).unwrap() + 4
 			),
 			Constraint::Fill(1)
 		])
@@ -1082,63 +3052,65 @@ impl Tui {
 
 		frame.render_widget(block, block_area[1]);
 
-		for section in help_sections {
+		for section in sections {
 			let section_lines = section.lines.len();
-			let span = Paragraph::new(section).wrap(Wrap { trim: false });
+			let span = Paragraph::new(section.clone()).wrap(Wrap { trim: false });
 			frame.render_widget(span, block_inner);
 			block_inner.y += u16::try_from(section_lines).unwrap();
 		}
 	}
 }
 
-static HELP_PAGE: &str = "\
-l, h, left, right:
-    Go forward/backwards a single page
-j, k, down, up:
-    Go forwards/backwards a screen's worth of pages
-q, esc:
-    Quit
-g:
-    Go to specific page (type numbers after 'g')
-/:
-    Search
-n, N:
-    Next/Previous search result
-i:
-    Invert colors
-f:
-    Remove borders/fullscreen
-?:
-    Show this page
-ctrl+z:
-    Suspend & background tdf \
-";
-
-static KITTY_HELP: &str = "\
-When using Kitty Protocol:
-z:
-    Toggle between fill-screen and fit-screen
-o/O (when on fill-screen):
-    Zoom in and out, respectively
-gg/G (when on fill-screen):
-    Scroll to top/bottom of page
-H, J, K, L (when zoomed in):
-    Pan direction around page
-0/$ (when on fill-screen):
-    Scroll to left/right side of page
-r:
-		Rotate by 90 degrees
-";
-
 pub enum InputAction {
 	Redraw,
 	JumpingToPage(usize),
-	Search(String),
+	// Jumping to a specific search result, which may or may not be on a different page
+	GoToResult { page: usize, idx: usize },
+	Search(SearchQuery),
+	OpenLink(String),
+	ExtractText(usize),
+	Select(Option<PageSelection>),
+	// Once the selection's been copied, it's done, so this also means it should stop being drawn
+	CopySelection(String),
 	QuitApp,
 	Invert,
 	Rotate,
+	CycleTrim,
+	ToggleAnnotations,
+	// The info popup was just opened, so the renderer needs to gather up what goes in it
+	LoadInfo,
 	Fullscreen,
-	SwitchRenderZoom(crate::FitOrFill)
+	// The password the user typed in to unlock the document
+	Password(String),
+	NextTab,
+	PrevTab,
+	CloseTab,
+	SwitchRenderZoom(crate::FitOrFill),
+	// The user ctrl+clicked on this point of the page, to find the TeX source of what's there
+	InverseSearch { page: usize, point: (f32, f32) },
+	// The overview was opened, so thumbnails need to be rendered to fit in this many cells, or
+	// closed (if it's None), so the current page needs to be shown again
+	Thumbnails(Option<(u16, u16)>)
+}
+
+// Generates `count` labels, all with the same length, so that no label is a prefix of another one
+fn hint_labels(count: usize) -> impl Iterator<Item = String> {
+	// home row first, since those are the easiest to type
+	const HINT_CHARS: [char; 9] = ['a', 's', 'd', 'f', 'j', 'k', 'l', 'g', 'h'];
+
+	let mut len = 1;
+	while HINT_CHARS.len().pow(len) < count {
+		len += 1;
+	}
+
+	(0..count).map(move |mut idx| {
+		let mut label = String::with_capacity(len as usize);
+		for _ in 0..len {
+			label.insert(0, HINT_CHARS[idx % HINT_CHARS.len()]);
+			idx /= HINT_CHARS.len();
+		}
+		label
+	})
 }
 
 #[derive(Copy, Clone)]
@@ -1157,5 +3129,310 @@ pub enum MessageSetting {
 	Some(BottomMessage),
 	Default,
 	Reset,
-	Pop
+	// Puts back the message that was shown before the current one
+	Pop,
+	// Closes whatever popup is open, leaving the bottom message alone
+	ClosePopup
+}
+
+#[cfg(test)]
+mod tests {
+	use image::DynamicImage;
+	use ratatui::{Terminal, backend::TestBackend};
+
+	use super::*;
+	use crate::renderer::RotateDirection;
+
+	const FONT_SIZE: FontSize = FontSize {
+		width: 8,
+		height: 16
+	};
+
+	fn test_tui(n_pages: usize, cover_page: usize) -> Tui {
+		let mut tui = Tui::new(
+			"test.pdf".into(),
+			None,
+			false,
+			NonZeroUsize::new(cover_page).unwrap(),
+			true,
+			Keymap::default()
+		);
+		tui.set_n_pages(n_pages);
+		tui
+	}
+
+	fn add_page(tui: &mut Tui, page: usize, cell_w: u16, cell_h: u16) {
+		let img = kittage::image::Image::from(DynamicImage::new_rgb8(1, 1));
+		let img = ConvertedImage::Kitty {
+			img: MaybeTransferred::NotYet(img),
+			cell_w,
+			cell_h
+		};
+		tui.page_ready(img, page, 0);
+	}
+
+	// A document in continuous mode whose pages are already converted, each this many rows tall
+	fn continuous_tui(heights: &[u16]) -> Tui {
+		let mut tui = test_tui(heights.len(), 1);
+		tui.use_continuous();
+		for (page, cell_h) in heights.iter().enumerate() {
+			add_page(&mut tui, page, 40, *cell_h);
+		}
+		tui
+	}
+
+	// Which pages were drawn, and (in cells) how far into each one and how much of it
+	fn draw_continuous(tui: &mut Tui, rows: u16) -> Vec<(usize, u32, u16)> {
+		let mut term = Terminal::new(TestBackend::new(40, rows)).unwrap();
+		let mut shown = Vec::new();
+		let row = tui.continuous.unwrap();
+		term.draw(|frame| {
+			let area = frame.area();
+			if let KittyDisplay::DisplayImages(imgs) =
+				tui.render_continuous(frame, area, FONT_SIZE, row)
+			{
+				shown = imgs
+					.into_iter()
+					.map(|img| {
+						let loc = img.display_loc;
+						(img.page_num, loc.y / u32::from(FONT_SIZE.height), loc.rows)
+					})
+					.collect();
+			}
+		})
+		.unwrap();
+		shown
+	}
+
+	#[test]
+	fn scrolls_across_page_boundaries() {
+		let mut tui = continuous_tui(&[30, 20, 30]);
+		assert_eq!(draw_continuous(&mut tui, 10), vec![(0, 0, 10)]);
+
+		// Staying within the first page
+		assert!(matches!(
+			tui.scroll_continuous(true, 25),
+			Some(InputAction::Redraw)
+		));
+		assert_eq!((tui.page, tui.continuous), (0, Some(25)));
+		assert_eq!(draw_continuous(&mut tui, 10), vec![(0, 25, 5), (1, 0, 5)]);
+
+		// Onto the next page, with what's left over after the end of the first one
+		assert!(matches!(
+			tui.scroll_continuous(true, 10),
+			Some(InputAction::JumpingToPage(1))
+		));
+		assert_eq!((tui.page, tui.continuous), (1, Some(5)));
+		assert_eq!(draw_continuous(&mut tui, 10), vec![(1, 5, 10)]);
+
+		// And back over the boundary again
+		assert!(matches!(
+			tui.scroll_continuous(false, 10),
+			Some(InputAction::JumpingToPage(0))
+		));
+		assert_eq!((tui.page, tui.continuous), (0, Some(25)));
+
+		// Past two boundaries at once
+		tui.scroll_continuous(true, 30);
+		assert_eq!((tui.page, tui.continuous), (2, Some(5)));
+		tui.scroll_continuous(false, 30);
+		assert_eq!((tui.page, tui.continuous), (0, Some(25)));
+	}
+
+	#[test]
+	fn stops_at_the_first_and_last_pages() {
+		let mut tui = continuous_tui(&[30, 20, 30]);
+		draw_continuous(&mut tui, 10);
+
+		// The top of the first page is as far back as it goes
+		tui.scroll_continuous(true, 5);
+		tui.scroll_continuous(false, 100);
+		assert_eq!((tui.page, tui.continuous), (0, Some(0)));
+
+		// And the bottom of the last page only goes up to the bottom of the screen
+		tui.scroll_continuous(true, 1000);
+		assert_eq!((tui.page, tui.continuous), (2, Some(20)));
+		assert_eq!(draw_continuous(&mut tui, 10), vec![(2, 20, 10)]);
+
+		// But the last page can still be shorter than the screen
+		let mut tui = continuous_tui(&[30, 4]);
+		draw_continuous(&mut tui, 10);
+		tui.scroll_continuous(true, 1000);
+		assert_eq!((tui.page, tui.continuous), (1, Some(0)));
+		assert_eq!(draw_continuous(&mut tui, 10), vec![(1, 0, 4)]);
+	}
+
+	#[test]
+	fn waits_for_pages_that_arent_converted_yet() {
+		let mut tui = continuous_tui(&[30, 20, 30, 30]);
+		tui.rendered[2].img = None;
+		draw_continuous(&mut tui, 10);
+
+		// Nothing after a page we don't know the height of can be placed, or scrolled to
+		tui.scroll_continuous(true, 1000);
+		assert_eq!((tui.page, tui.continuous), (2, Some(0)));
+		assert!(draw_continuous(&mut tui, 10).is_empty());
+		assert_eq!(tui.last_render.pages_shown, 1);
+	}
+
+	#[test]
+	fn pairs_pages_up_after_the_cover() {
+		let mut tui = test_tui(7, 1);
+		assert_eq!(tui.spread_of(0), None);
+		tui.use_spreads();
+
+		let spreads = (0..7).map(|page| tui.spread_of(page)).collect::<Vec<_>>();
+		assert_eq!(spreads, vec![
+			Some((0, 1)),
+			Some((1, 2)),
+			Some((1, 2)),
+			Some((3, 2)),
+			Some((3, 2)),
+			Some((5, 2)),
+			Some((5, 2))
+		]);
+
+		// With an even number of pages, the last one is left on its own
+		let mut tui = test_tui(6, 1);
+		tui.use_spreads();
+		assert_eq!(tui.spread_of(4), Some((3, 2)));
+		assert_eq!(tui.spread_of(5), Some((5, 1)));
+
+		// And everything up to a later cover is shown alone too
+		let mut tui = test_tui(6, 3);
+		tui.use_spreads();
+		let spreads = (0..6).map(|page| tui.spread_of(page)).collect::<Vec<_>>();
+		assert_eq!(spreads, vec![
+			Some((0, 1)),
+			Some((1, 1)),
+			Some((2, 1)),
+			Some((3, 2)),
+			Some((3, 2)),
+			Some((5, 1))
+		]);
+	}
+
+	#[test]
+	fn moves_one_page_at_a_time_when_spreads_dont_fit() {
+		let mut tui = test_tui(5, 1);
+		tui.use_spreads();
+		for page in 0..5 {
+			add_page(&mut tui, page, 30, 40);
+		}
+
+		// Both fit next to each other
+		tui.last_render.pages_width = 60;
+		assert_eq!(tui.spread_of(2), Some((1, 2)));
+		tui.change_page(PageChange::Next, ChangeAmount::Single);
+		assert_eq!(tui.page, 1);
+		tui.change_page(PageChange::Next, ChangeAmount::Single);
+		assert_eq!(tui.page, 3);
+
+		// They don't anymore, so the second page of each spread isn't skipped over
+		tui.last_render.pages_width = 59;
+		assert_eq!(tui.spread_of(4), Some((4, 1)));
+		tui.change_page(PageChange::Next, ChangeAmount::Single);
+		assert_eq!(tui.page, 4);
+		tui.change_page(PageChange::Prev, ChangeAmount::Single);
+		assert_eq!(tui.page, 3);
+		tui.change_page(PageChange::Prev, ChangeAmount::Single);
+		assert_eq!(tui.page, 2);
+	}
+
+	#[test]
+	fn setting_a_mark_leaves_the_popup_alone() {
+		let mut tui = test_tui(3, 1);
+		for c in ['?', 'm', 'a', 'e'] {
+			tui.handle_event(&Event::Key(KeyEvent::from(KeyCode::Char(c))));
+		}
+
+		// Only `a` is set, since typing it finished setting the mark
+		assert_eq!(tui.marks.keys().collect::<Vec<_>>(), [&'a']);
+		assert!(
+			!matches!(tui.bottom_msg, BottomMessage::Input(_)),
+			"The mark prompt should be gone once a mark is set"
+		);
+		assert!(
+			matches!(tui.popup, Some(Popup::Help)),
+			"Setting a mark shouldn't close the help page"
+		);
+
+		tui.handle_event(&Event::Key(KeyEvent::from(KeyCode::Esc)));
+		assert!(tui.popup.is_none(), "Esc should close the help page");
+	}
+
+	#[test]
+	fn backing_out_of_the_password_only_closes_its_tab() {
+		let mut tui = test_tui(1, 1);
+		tui.ask_for_password(false);
+		assert!(
+			matches!(
+				tui.handle_event(&Event::Key(KeyEvent::from(KeyCode::Esc))),
+				Some(InputAction::CloseTab)
+			),
+			"Esc on the password prompt should close the document instead of quitting"
+		);
+	}
+
+	#[test]
+	fn hint_labels_are_never_prefixes_of_each_other() {
+		// Right at the number of hint characters, and one past it, where they need two each
+		for (count, len) in [(9, 1), (10, 2)] {
+			let labels = hint_labels(count).collect::<Vec<_>>();
+			assert_eq!(labels.len(), count);
+			assert!(labels.iter().all(|label| label.len() == len), "{labels:?}");
+			for (idx, label) in labels.iter().enumerate() {
+				for (other_idx, other) in labels.iter().enumerate() {
+					assert!(
+						idx == other_idx || !other.starts_with(label.as_str()),
+						"{label} is a prefix of {other}"
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn maps_pixels_to_the_cells_they_were_drawn_in() {
+		// Scrolled 2 columns and 2 rows into the page
+		let shown = ShownPage {
+			num: 0,
+			area: Rect::new(10, 5, 20, 10),
+			src_x: 16.,
+			src_y: 32.,
+			cells_per_px_x: 1. / f32::from(FONT_SIZE.width),
+			cells_per_px_y: 1. / f32::from(FONT_SIZE.height)
+		};
+		assert_eq!(shown.px_to_cell(16, 32), Some(Position { x: 10, y: 5 }));
+		assert_eq!(shown.px_to_cell(47, 64), Some(Position { x: 13, y: 7 }));
+		// Scrolled past, and past the right and bottom edges of the area
+		assert_eq!(shown.px_to_cell(15, 32), None);
+		assert_eq!(shown.px_to_cell(176, 32), None);
+		assert_eq!(shown.px_to_cell(16, 192), None);
+
+		// On a page that's rotated a quarter turn, a link near the top-left corner of the page
+		// ends up near the top-right corner of the screen
+		let transform = PageTransform::new(
+			PdfRect {
+				x0: 0.,
+				y0: 0.,
+				x1: 100.,
+				y1: 200.
+			},
+			2.,
+			RotateDirection::Deg90,
+			(0, 0)
+		);
+		let link = transform.to_px(PdfRect {
+			x0: 10.,
+			y0: 20.,
+			x1: 30.,
+			y1: 40.
+		});
+		let shown = ShownPage::unscaled(0, Rect::new(0, 0, 60, 30), FONT_SIZE);
+		assert_eq!(
+			shown.px_to_cell(link.ul_x, link.ul_y),
+			Some(Position { x: 40, y: 1 })
+		);
+	}
 }